@@ -0,0 +1,21 @@
+//! # my-pinocchio-cpi client
+//!
+//! 链下构建 `my-pinocchio-cpi` 指令：按程序读取的字节布局编码指令数据，
+//! 并按 header 账户 + 各池账户的顺序生成 `AccountMeta` 列表。
+//!
+//! ```ignore
+//! let header = RouteHeader::new(payer, usdc_mint, usdc_ata, vec![token]);
+//! let ix = Route::two_hop(PoolKeys::Cpmm(buy), PoolKeys::Dlmm(sell))
+//!     .base_pool_0(true, false)?
+//!     .amount_in(1_000_000_000)
+//!     .min_profit(10_000)
+//!     .instruction(PROGRAM_ID, &header)?;
+//! ```
+
+pub mod pools;
+pub mod route;
+pub mod simulation;
+
+pub use pools::*;
+pub use route::*;
+pub use simulation::*;
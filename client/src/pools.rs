@@ -0,0 +1,529 @@
+//! 每种 DEX 池在指令中需要的账户，字段顺序即链上程序读取的下标顺序。
+//!
+//! `metas()` 返回定长数组，长度直接使用程序中的 `*_ACCOUNT_COUNT`，
+//! 账户数量与链上不一致时无法通过编译。
+
+use my_pinocchio_cpi::utils::{
+    CLMM_ACCOUNT_COUNT, CPMM_ACCOUNT_COUNT, DAMMV1_ACCOUNT_COUNT, DAMMV2_ACCOUNT_COUNT, DBC_ACCOUNT_COUNT,
+    DLMM_ACCOUNT_COUNT, LAUNCHLAB_ACCOUNT_COUNT, PUMP_ACCOUNT_COUNT, PUMP_FUN_ACCOUNT_COUNT, PUMP_FUN_SELL_ACCOUNT_COUNT,
+    RAYDIUM_ACCOUNT_COUNT, RAYDIUM_OPENBOOK_ACCOUNT_COUNT, WHIRLPOOL_ACCOUNT_COUNT,
+};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+/// Raydium CPMM (pool type 0)
+#[derive(Clone, Debug)]
+pub struct CpmmKeys {
+    pub program: Pubkey,
+    pub authority: Pubkey,
+    pub amm_config: Pubkey,
+    pub observation_state: Pubkey,
+    pub pool_state: Pubkey,
+    pub token_0_vault: Pubkey,
+    pub token_1_vault: Pubkey,
+}
+
+impl CpmmKeys {
+    pub fn metas(&self) -> [AccountMeta; CPMM_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new_readonly(self.amm_config, false),
+            AccountMeta::new(self.observation_state, false),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.token_0_vault, false),
+            AccountMeta::new(self.token_1_vault, false),
+        ]
+    }
+}
+
+/// Meteora DLMM (pool type 1)
+#[derive(Clone, Debug)]
+pub struct DlmmKeys {
+    pub program: Pubkey,
+    pub event_authority: Pubkey,
+    pub oracle: Pubkey,
+    pub lb_pair: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub bin_array_minus_1: Pubkey,
+    pub bin_array_0: Pubkey,
+    pub bin_array_1: Pubkey,
+}
+
+impl DlmmKeys {
+    pub fn metas(&self) -> [AccountMeta; DLMM_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new(self.oracle, false),
+            AccountMeta::new(self.lb_pair, false),
+            AccountMeta::new(self.reserve_x, false),
+            AccountMeta::new(self.reserve_y, false),
+            AccountMeta::new(self.bin_array_minus_1, false),
+            AccountMeta::new(self.bin_array_0, false),
+            AccountMeta::new(self.bin_array_1, false),
+        ]
+    }
+}
+
+/// Meteora DAMM v2 (pool type 2)
+#[derive(Clone, Debug)]
+pub struct Dammv2Keys {
+    pub program: Pubkey,
+    pub event_authority: Pubkey,
+    pub pool_authority: Pubkey,
+    pub pool: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+}
+
+impl Dammv2Keys {
+    pub fn metas(&self) -> [AccountMeta; DAMMV2_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new_readonly(self.pool_authority, false),
+            AccountMeta::new(self.pool, false),
+            AccountMeta::new(self.token_a_vault, false),
+            AccountMeta::new(self.token_b_vault, false),
+        ]
+    }
+}
+
+/// Pump AMM (pool type 3)
+#[derive(Clone, Debug)]
+pub struct PumpKeys {
+    pub program: Pubkey,
+    pub pool: Pubkey,
+    pub global_config: Pubkey,
+    pub event_authority: Pubkey,
+    pub coin_creator_vault_ata: Pubkey,
+    pub coin_creator_vault_authority: Pubkey,
+    pub protocol_fee_recipient: Pubkey,
+    pub protocol_fee_recipient_token_account: Pubkey,
+    pub global_volume_accumulator: Pubkey,
+    pub user_volume_accumulator: Pubkey,
+    pub system_program: Pubkey,
+    pub associated_token_program: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+    pub fee_config: Pubkey,
+    pub fee_program: Pubkey,
+}
+
+impl PumpKeys {
+    pub fn metas(&self) -> [AccountMeta; PUMP_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.pool, false),
+            AccountMeta::new_readonly(self.global_config, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new(self.coin_creator_vault_ata, false),
+            AccountMeta::new_readonly(self.coin_creator_vault_authority, false),
+            AccountMeta::new_readonly(self.protocol_fee_recipient, false),
+            AccountMeta::new(self.protocol_fee_recipient_token_account, false),
+            AccountMeta::new(self.global_volume_accumulator, false),
+            AccountMeta::new(self.user_volume_accumulator, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.associated_token_program, false),
+            AccountMeta::new(self.pool_base_token_account, false),
+            AccountMeta::new(self.pool_quote_token_account, false),
+            AccountMeta::new_readonly(self.fee_config, false),
+            AccountMeta::new_readonly(self.fee_program, false),
+        ]
+    }
+}
+
+/// Raydium AMM v4 (pool type 4)
+#[derive(Clone, Debug)]
+pub struct RaydiumKeys {
+    pub program: Pubkey,
+    pub authority: Pubkey,
+    pub amm: Pubkey,
+    pub pool_coin_token_account: Pubkey,
+    pub pool_pc_token_account: Pubkey,
+}
+
+impl RaydiumKeys {
+    pub fn metas(&self) -> [AccountMeta; RAYDIUM_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new(self.amm, false),
+            AccountMeta::new(self.pool_coin_token_account, false),
+            AccountMeta::new(self.pool_pc_token_account, false),
+        ]
+    }
+}
+
+/// Raydium CLMM (pool type 5)
+#[derive(Clone, Debug)]
+pub struct ClmmKeys {
+    pub program: Pubkey,
+    pub pool_state: Pubkey,
+    pub amm_config: Pubkey,
+    pub observation_state: Pubkey,
+    pub bitmap_extension: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub tick_array_minus_1: Pubkey,
+    pub tick_array_0: Pubkey,
+    pub tick_array_1: Pubkey,
+}
+
+impl ClmmKeys {
+    pub fn metas(&self) -> [AccountMeta; CLMM_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new_readonly(self.amm_config, false),
+            AccountMeta::new(self.observation_state, false),
+            AccountMeta::new(self.bitmap_extension, false),
+            AccountMeta::new(self.token_vault_0, false),
+            AccountMeta::new(self.token_vault_1, false),
+            AccountMeta::new(self.tick_array_minus_1, false),
+            AccountMeta::new(self.tick_array_0, false),
+            AccountMeta::new(self.tick_array_1, false),
+        ]
+    }
+}
+
+/// Orca Whirlpool (pool type 6)
+#[derive(Clone, Debug)]
+pub struct WhirlpoolKeys {
+    pub program: Pubkey,
+    pub whirlpool: Pubkey,
+    pub oracle: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub token_vault_b: Pubkey,
+    pub tick_array_0: Pubkey,
+    pub tick_array_1: Pubkey,
+    pub tick_array_2: Pubkey,
+}
+
+impl WhirlpoolKeys {
+    pub fn metas(&self) -> [AccountMeta; WHIRLPOOL_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new(self.whirlpool, false),
+            AccountMeta::new(self.oracle, false),
+            AccountMeta::new(self.token_vault_a, false),
+            AccountMeta::new(self.token_vault_b, false),
+            AccountMeta::new(self.tick_array_0, false),
+            AccountMeta::new(self.tick_array_1, false),
+            AccountMeta::new(self.tick_array_2, false),
+        ]
+    }
+}
+
+/// Raydium AMM v4 + OpenBook 市场账户 (pool type 7)，用于仍需真实 Serum 账户的旧池
+#[derive(Clone, Debug)]
+pub struct RaydiumOpenbookKeys {
+    pub program: Pubkey,
+    pub authority: Pubkey,
+    pub amm: Pubkey,
+    pub pool_coin_token_account: Pubkey,
+    pub pool_pc_token_account: Pubkey,
+    pub open_orders: Pubkey,
+    pub serum_program: Pubkey,
+    pub serum_market: Pubkey,
+    pub serum_bids: Pubkey,
+    pub serum_asks: Pubkey,
+    pub serum_event_queue: Pubkey,
+    pub serum_coin_vault: Pubkey,
+    pub serum_pc_vault: Pubkey,
+    pub serum_vault_signer: Pubkey,
+}
+
+impl RaydiumOpenbookKeys {
+    pub fn metas(&self) -> [AccountMeta; RAYDIUM_OPENBOOK_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new(self.amm, false),
+            AccountMeta::new(self.pool_coin_token_account, false),
+            AccountMeta::new(self.pool_pc_token_account, false),
+            AccountMeta::new(self.open_orders, false),
+            AccountMeta::new_readonly(self.serum_program, false),
+            AccountMeta::new(self.serum_market, false),
+            AccountMeta::new(self.serum_bids, false),
+            AccountMeta::new(self.serum_asks, false),
+            AccountMeta::new(self.serum_event_queue, false),
+            AccountMeta::new(self.serum_coin_vault, false),
+            AccountMeta::new(self.serum_pc_vault, false),
+            AccountMeta::new_readonly(self.serum_vault_signer, false),
+        ]
+    }
+}
+
+/// Pump.fun 联合曲线 (pool type 8)，只能在 WSOL 基础资产和迁移前的 token 之间兑换。
+/// `user_sol_account` 是 payer 持有的原生 WSOL 临时账户，买入时用于解包并被关闭，
+/// 需要在同一笔交易中预先创建；卖出跳不传入该账户（`Route::accounts` 使用 `sell_metas`）。
+/// 临时账户被关闭后不能再用，每条路由最多一个 Pump.fun 买入跳
+#[derive(Clone, Debug)]
+pub struct PumpFunKeys {
+    pub program: Pubkey,
+    pub global: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub creator_vault: Pubkey,
+    pub event_authority: Pubkey,
+    pub global_volume_accumulator: Pubkey,
+    pub user_volume_accumulator: Pubkey,
+    pub fee_config: Pubkey,
+    pub fee_program: Pubkey,
+    pub system_program: Pubkey,
+    pub user_sol_account: Pubkey,
+}
+
+impl PumpFunKeys {
+    pub fn metas(&self) -> [AccountMeta; PUMP_FUN_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.global, false),
+            AccountMeta::new(self.fee_recipient, false),
+            AccountMeta::new(self.bonding_curve, false),
+            AccountMeta::new(self.associated_bonding_curve, false),
+            AccountMeta::new(self.creator_vault, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new(self.global_volume_accumulator, false),
+            AccountMeta::new(self.user_volume_accumulator, false),
+            AccountMeta::new_readonly(self.fee_config, false),
+            AccountMeta::new_readonly(self.fee_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new(self.user_sol_account, false),
+        ]
+    }
+
+    /// 卖出跳的账户：不含 `user_sol_account`
+    pub fn sell_metas(&self) -> [AccountMeta; PUMP_FUN_SELL_ACCOUNT_COUNT] {
+        let metas = self.metas();
+        core::array::from_fn(|i| metas[i].clone())
+    }
+}
+
+/// Meteora Dynamic AMM / DAMM v1 (pool type 9)，池中资产存放在 Meteora vault 中
+#[derive(Clone, Debug)]
+pub struct Dammv1Keys {
+    pub program: Pubkey,
+    pub pool: Pubkey,
+    pub a_vault: Pubkey,
+    pub b_vault: Pubkey,
+    pub a_token_vault: Pubkey,
+    pub b_token_vault: Pubkey,
+    pub a_vault_lp_mint: Pubkey,
+    pub b_vault_lp_mint: Pubkey,
+    pub a_vault_lp: Pubkey,
+    pub b_vault_lp: Pubkey,
+    pub protocol_token_a_fee: Pubkey,
+    pub protocol_token_b_fee: Pubkey,
+    pub vault_program: Pubkey,
+}
+
+impl Dammv1Keys {
+    pub fn metas(&self) -> [AccountMeta; DAMMV1_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new(self.pool, false),
+            AccountMeta::new(self.a_vault, false),
+            AccountMeta::new(self.b_vault, false),
+            AccountMeta::new(self.a_token_vault, false),
+            AccountMeta::new(self.b_token_vault, false),
+            AccountMeta::new(self.a_vault_lp_mint, false),
+            AccountMeta::new(self.b_vault_lp_mint, false),
+            AccountMeta::new(self.a_vault_lp, false),
+            AccountMeta::new(self.b_vault_lp, false),
+            AccountMeta::new(self.protocol_token_a_fee, false),
+            AccountMeta::new(self.protocol_token_b_fee, false),
+            AccountMeta::new_readonly(self.vault_program, false),
+        ]
+    }
+}
+
+/// Meteora Dynamic Bonding Curve (pool type 10)，base 为发行的 token，quote 为报价资产
+#[derive(Clone, Debug)]
+pub struct DbcKeys {
+    pub program: Pubkey,
+    pub event_authority: Pubkey,
+    pub pool_authority: Pubkey,
+    pub config: Pubkey,
+    pub pool: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+}
+
+impl DbcKeys {
+    pub fn metas(&self) -> [AccountMeta; DBC_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new_readonly(self.pool_authority, false),
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.pool, false),
+            AccountMeta::new(self.base_vault, false),
+            AccountMeta::new(self.quote_vault, false),
+        ]
+    }
+}
+
+/// Raydium LaunchLab 联合曲线 (pool type 11)，base 为发行的 token，quote 为报价资产
+#[derive(Clone, Debug)]
+pub struct LaunchlabKeys {
+    pub program: Pubkey,
+    pub authority: Pubkey,
+    pub global_config: Pubkey,
+    pub platform_config: Pubkey,
+    pub pool_state: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub event_authority: Pubkey,
+}
+
+impl LaunchlabKeys {
+    pub fn metas(&self) -> [AccountMeta; LAUNCHLAB_ACCOUNT_COUNT] {
+        [
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new_readonly(self.global_config, false),
+            AccountMeta::new_readonly(self.platform_config, false),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.base_vault, false),
+            AccountMeta::new(self.quote_vault, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+        ]
+    }
+}
+
+/// 任意一个受支持的池
+#[derive(Clone, Debug)]
+pub enum PoolKeys {
+    Cpmm(CpmmKeys),
+    Dlmm(DlmmKeys),
+    Dammv2(Dammv2Keys),
+    Pump(PumpKeys),
+    Raydium(RaydiumKeys),
+    Clmm(ClmmKeys),
+    Whirlpool(WhirlpoolKeys),
+    RaydiumOpenbook(RaydiumOpenbookKeys),
+    PumpFun(PumpFunKeys),
+    Dammv1(Dammv1Keys),
+    Dbc(DbcKeys),
+    Launchlab(LaunchlabKeys),
+}
+
+impl PoolKeys {
+    /// 指令数据中的池类型编号，与 `execute_swap_optimized` 的分支一致
+    pub fn pool_type(&self) -> u8 {
+        match self {
+            PoolKeys::Cpmm(_) => 0,
+            PoolKeys::Dlmm(_) => 1,
+            PoolKeys::Dammv2(_) => 2,
+            PoolKeys::Pump(_) => 3,
+            PoolKeys::Raydium(_) => 4,
+            PoolKeys::Clmm(_) => 5,
+            PoolKeys::Whirlpool(_) => 6,
+            PoolKeys::RaydiumOpenbook(_) => 7,
+            PoolKeys::PumpFun(_) => 8,
+            PoolKeys::Dammv1(_) => 9,
+            PoolKeys::Dbc(_) => 10,
+            PoolKeys::Launchlab(_) => 11,
+        }
+    }
+
+    /// 按链上读取顺序追加该池的账户
+    pub fn append_metas(&self, metas: &mut Vec<AccountMeta>) {
+        match self {
+            PoolKeys::Cpmm(keys) => metas.extend(keys.metas()),
+            PoolKeys::Dlmm(keys) => metas.extend(keys.metas()),
+            PoolKeys::Dammv2(keys) => metas.extend(keys.metas()),
+            PoolKeys::Pump(keys) => metas.extend(keys.metas()),
+            PoolKeys::Raydium(keys) => metas.extend(keys.metas()),
+            PoolKeys::Clmm(keys) => metas.extend(keys.metas()),
+            PoolKeys::Whirlpool(keys) => metas.extend(keys.metas()),
+            PoolKeys::RaydiumOpenbook(keys) => metas.extend(keys.metas()),
+            PoolKeys::PumpFun(keys) => metas.extend(keys.metas()),
+            PoolKeys::Dammv1(keys) => metas.extend(keys.metas()),
+            PoolKeys::Dbc(keys) => metas.extend(keys.metas()),
+            PoolKeys::Launchlab(keys) => metas.extend(keys.metas()),
+        }
+    }
+}
+
+/// Solend flash_borrow_reserve_liquidity / flash_repay_reserve_liquidity 的指令 tag
+pub use my_pinocchio_cpi::flash_loan::{FLASH_BORROW_TAG, FLASH_REPAY_TAG};
+
+/// 程序只接受该程序拥有的 reserve，并只认可它发出的借还款指令
+pub const SOLEND_PROGRAM_ID: Pubkey = Pubkey::from_str_const("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo");
+
+pub const INSTRUCTIONS_SYSVAR_ID: Pubkey = Pubkey::from_str_const("Sysvar1nstructions1111111111111111111111111");
+
+/// 闪电贷借贷池（Solend 的 reserve）。
+///
+/// Solend 只接受交易顶层的 flash_borrow / flash_repay：借款通过 instructions sysvar 检查
+/// 同一交易中存在引用它的还款指令，通过 CPI 调用会被拒绝。因此两条指令由客户端放在
+/// 套利指令的前后（见 `Route::instructions`），套利程序本身不调用借贷程序，而是在执行前
+/// 通过 instructions sysvar 确认还款指令存在，并从 reserve 读取手续费率。
+///
+/// 只支持 Solend：Kamino / MarginFi 的闪电贷指令和费率模型不同，程序不接受它们的账户。
+#[derive(Clone, Debug)]
+pub struct FlashLoanKeys {
+    pub reserve: Pubkey,
+    pub reserve_liquidity_supply: Pubkey,
+    pub reserve_liquidity_fee_receiver: Pubkey,
+    pub host_fee_receiver: Pubkey,
+    pub lending_market: Pubkey,
+    pub lending_market_authority: Pubkey,
+}
+
+impl FlashLoanKeys {
+    /// 从 reserve 借出 `amount` 到 `destination`
+    pub fn borrow_instruction(&self, amount: u64, destination: Pubkey, token_program: Pubkey) -> Instruction {
+        let mut data = vec![FLASH_BORROW_TAG];
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction {
+            program_id: SOLEND_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.reserve_liquidity_supply, false), // source_liquidity
+                AccountMeta::new(destination, false),                   // destination_liquidity
+                AccountMeta::new(self.reserve, false),
+                AccountMeta::new_readonly(self.lending_market, false),
+                AccountMeta::new_readonly(self.lending_market_authority, false),
+                AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false),
+                AccountMeta::new_readonly(token_program, false),
+            ],
+            data,
+        }
+    }
+
+    /// 从 `source` 归还 `amount` 本金，手续费由借贷程序按 reserve 的费率额外扣除。
+    /// `borrow_instruction_index` 是对应 flash_borrow 在交易中的指令下标
+    pub fn repay_instruction(
+        &self,
+        amount: u64,
+        source: Pubkey,
+        user_transfer_authority: Pubkey,
+        token_program: Pubkey,
+        borrow_instruction_index: u8,
+    ) -> Instruction {
+        let mut data = vec![FLASH_REPAY_TAG];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(borrow_instruction_index);
+        Instruction {
+            program_id: SOLEND_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(source, false),                        // source_liquidity
+                AccountMeta::new(self.reserve_liquidity_supply, false), // destination_liquidity
+                AccountMeta::new(self.reserve_liquidity_fee_receiver, false),
+                AccountMeta::new(self.host_fee_receiver, false),
+                AccountMeta::new(self.reserve, false),
+                AccountMeta::new_readonly(self.lending_market, false),
+                AccountMeta::new_readonly(user_transfer_authority, true),
+                AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false),
+                AccountMeta::new_readonly(token_program, false),
+            ],
+            data,
+        }
+    }
+}
@@ -0,0 +1,628 @@
+//! 路由构建器：生成与 `execute_direct_cpi` / `execute_direct_cpi_3hop` /
+//! `execute_direct_cpi_route` 完全一致的指令数据和账户顺序。
+
+use my_pinocchio_cpi::utils::{
+    DIRECTION_EXACT_OUT, FLAG_FLASH_LOAN, FLAG_INCLUDE_EXISTING_BALANCE, FLAG_SIMULATE, FLAG_SPLIT, FLAG_TIP,
+    FLAG_UNWRAP_SOL, FLAG_VALIDATE_ACCOUNTS, FLAG_WRAP_SOL, HEADER_ACCOUNT_COUNT, HEADER_ACCOUNT_COUNT_3HOP,
+    ROUTE_HEADER_LEN, ROUTE_HEADER_LEN_V2, ROUTE_HOP_LEN, ROUTE_HOP_LEN_SPLIT, SHARE_KIND_AMOUNT, SHARE_KIND_BPS,
+    SWAP_DATA_LEN, SWAP_DATA_LEN_3HOP, SWAP_DATA_LEN_3HOP_V2, SWAP_DATA_LEN_V2,
+};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+use std::fmt;
+
+use crate::pools::{FlashLoanKeys, PoolKeys, INSTRUCTIONS_SYSVAR_ID};
+
+/// 指令首字节
+pub const OPCODE_2HOP: u8 = 4;
+pub const OPCODE_3HOP: u8 = 5;
+pub const OPCODE_ROUTE: u8 = 6;
+/// v2 指令：u64 min_profit + u16 min_profit_bps
+pub const OPCODE_2HOP_V2: u8 = 7;
+pub const OPCODE_3HOP_V2: u8 = 8;
+pub const OPCODE_ROUTE_V2: u8 = 9;
+/// 反向2hop：token -> base -> token，利润以 token 计
+pub const OPCODE_2HOP_REVERSE: u8 = 10;
+pub const OPCODE_2HOP_REVERSE_V2: u8 = 11;
+
+/// header 中基础资产账户的下标
+pub const BASE_ACCOUNT_INDEX: u8 = 2;
+
+pub const WSOL_MINT: Pubkey = Pubkey::from_str_const("So11111111111111111111111111111111111111112");
+pub const TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const MEMO_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey::from_str_const("11111111111111111111111111111111");
+
+/// header 中第 `k` 个中间 token（从0开始）的 token 账户下标：8, 11, 14, ...
+pub fn token_account_index(k: usize) -> u8 {
+    (8 + 3 * k) as u8
+}
+
+/// 一个中间 token 的 mint / token_program / 用户 token 账户
+#[derive(Clone, Debug)]
+pub struct TokenKeys {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub account: Pubkey,
+}
+
+/// header 账户：payer、基础资产、公共程序以及按 (mint, program, account) 排列的中间 token
+///
+/// 基础资产是路由的起点和终点，利润以它计算；可以是任意 SPL Token / Token-2022 mint（WSOL、USDC、JitoSOL ...）
+#[derive(Clone, Debug)]
+pub struct RouteHeader {
+    pub payer: Pubkey,
+    pub base_mint: Pubkey,
+    pub base_account: Pubkey,
+    /// 基础资产的 token 程序，写入 header [3]
+    pub base_token_program: Pubkey,
+    pub tokens: Vec<TokenKeys>,
+}
+
+impl RouteHeader {
+    /// 基础资产为 SPL Token mint 的 header，Token-2022 基础资产使用 `base_token_program`
+    pub fn new(payer: Pubkey, base_mint: Pubkey, base_account: Pubkey, tokens: Vec<TokenKeys>) -> Self {
+        Self {
+            payer,
+            base_mint,
+            base_account,
+            base_token_program: TOKEN_PROGRAM_ID,
+            tokens,
+        }
+    }
+
+    /// 基础资产的 token 程序（默认 SPL Token）
+    pub fn base_token_program(mut self, token_program: Pubkey) -> Self {
+        self.base_token_program = token_program;
+        self
+    }
+
+    /// 以 WSOL 为基础资产的 header
+    pub fn wsol(payer: Pubkey, wsol_account: Pubkey, tokens: Vec<TokenKeys>) -> Self {
+        Self::new(payer, WSOL_MINT, wsol_account, tokens)
+    }
+
+    /// header 账户数量：6 个固定账户 + 每个中间 token 3 个
+    pub fn account_count(&self) -> usize {
+        6 + 3 * self.tokens.len()
+    }
+
+    pub fn metas(&self) -> Vec<AccountMeta> {
+        let mut metas = Vec::with_capacity(self.account_count());
+        metas.push(AccountMeta::new(self.payer, true)); // [0] payer
+        metas.push(AccountMeta::new_readonly(self.base_mint, false)); // [1] base_mint
+        metas.push(AccountMeta::new(self.base_account, false)); // [2] base_token_account
+        metas.push(AccountMeta::new_readonly(self.base_token_program, false)); // [3] base_token_program
+        metas.push(AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false)); // [4] token_program_2022
+        metas.push(AccountMeta::new_readonly(MEMO_PROGRAM_ID, false)); // [5] memo_program
+        for token in &self.tokens {
+            metas.push(AccountMeta::new_readonly(token.mint, false));
+            metas.push(AccountMeta::new_readonly(token.token_program, false));
+            metas.push(AccountMeta::new(token.account, false));
+        }
+        metas
+    }
+}
+
+/// 路由中的一跳
+#[derive(Clone, Debug)]
+pub struct Hop {
+    pub pool: PoolKeys,
+    /// 写入指令的方向字节：2hop/3hop 中为 is_base_pool_0 / is_mid_zero_to_one，
+    /// N-hop 中为“输入 token 是否为池的 token0”
+    pub direction: bool,
+    pub input_index: u8,
+    pub output_index: u8,
+    /// 最小输出，精确输出的跳不使用
+    pub min_out: u64,
+    /// 精确输出数量，该跳的输入数量为最大输入（仅 CPMM / Raydium AMM v4 / CLMM）。
+    /// 链上与 min_out 共用同一个槽位，因此两者不能同时设置
+    pub exact_out: Option<u64>,
+    /// 拆分路由中该子swap分到的输入，同一腿的最后一跳总是使用剩余部分
+    pub share: Option<Share>,
+}
+
+/// 拆分路由中一个子swap的份额
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Share {
+    /// 该腿输入的 bps
+    Bps(u16),
+    /// 固定数量，超出该腿剩余输入时截断
+    Amount(u64),
+}
+
+impl Hop {
+    /// N-hop 路由中的一跳，`input_index` / `output_index` 为 header 中的 token 账户下标
+    pub fn new(pool: PoolKeys, input_index: u8, output_index: u8, is_zero_to_one: bool) -> Self {
+        Self {
+            pool,
+            direction: is_zero_to_one,
+            input_index,
+            output_index,
+            min_out: 0,
+            exact_out: None,
+            share: None,
+        }
+    }
+
+    pub fn min_out(mut self, min_out: u64) -> Self {
+        self.min_out = min_out;
+        self
+    }
+
+    /// 精确输出 `amount_out`，该跳收到的输入数量作为最大输入，未花完的输入留在输入账户（仅 CPMM / Raydium AMM v4 / CLMM）。
+    /// 精确输出的跳没有最小输出，`min_out` 被清零；`amount_out` 为0时程序返回 `InvalidTradeAmount`
+    pub fn exact_out(mut self, amount_out: u64) -> Self {
+        self.exact_out = Some(amount_out);
+        self.min_out = 0;
+        self
+    }
+
+    /// 写入指令的方向字节：bit0 为方向，bit1 为精确输出
+    fn direction_byte(&self) -> u8 {
+        if self.exact_out.is_some() {
+            self.direction as u8 | DIRECTION_EXACT_OUT
+        } else {
+            self.direction as u8
+        }
+    }
+
+    /// 该跳在指令 min_outs 中的槽位：精确输出的跳为输出数量，其余为最小输出
+    fn min_out_slot(&self) -> u64 {
+        self.exact_out.unwrap_or(self.min_out)
+    }
+
+    /// N-hop 拆分路由：输入和输出账户相同的相邻跳组成一条腿，该跳分到腿输入的 `bps`
+    pub fn share_bps(mut self, bps: u16) -> Self {
+        self.share = Some(Share::Bps(bps));
+        self
+    }
+
+    /// N-hop 拆分路由：该跳分到固定的 `amount`
+    pub fn share_amount(mut self, amount: u64) -> Self {
+        self.share = Some(Share::Amount(amount));
+        self
+    }
+}
+
+/// 路由设置与路由的跳不匹配
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteError {
+    /// 路由没有任何跳
+    EmptyRoute,
+    /// 中间池方向只适用于 3hop
+    NotThreeHop,
+    /// min_outs 的数量与跳数不一致
+    MinOutCountMismatch { hops: usize, min_outs: usize },
+    /// 跳的下标超出路由的跳数
+    HopOutOfRange { hop: usize, hops: usize },
+    /// header 账户数量与路由类型不符：2hop 需要 1 个中间 token，3hop 需要 2 个
+    HeaderAccountCount { expected: usize, got: usize },
+    /// 精确输出的跳与 min_out 共用同一个槽位，不能再设置最小输出
+    MinOutOnExactOutHop { hop: usize },
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::EmptyRoute => write!(f, "route has no hops"),
+            RouteError::NotThreeHop => write!(f, "mid_zero_to_one only applies to 3-hop routes"),
+            RouteError::MinOutCountMismatch { hops, min_outs } => {
+                write!(f, "expected one min_out per hop ({hops}), got {min_outs}")
+            }
+            RouteError::HopOutOfRange { hop, hops } => write!(f, "hop {hop} out of range for a {hops}-hop route"),
+            RouteError::HeaderAccountCount { expected, got } => {
+                write!(f, "expected {expected} header accounts for this route, got {got}")
+            }
+            RouteError::MinOutOnExactOutHop { hop } => {
+                write!(f, "hop {hop} is exact-out, its min_out slot carries the exact output amount")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RouteKind {
+    Direct,
+    Direct3,
+    Reverse,
+    Generic,
+}
+
+/// 路由构建器
+#[derive(Clone, Debug)]
+pub struct Route {
+    kind: RouteKind,
+    hops: Vec<Hop>,
+    amount_in: u64,
+    pump_base_amount_out: u64,
+    min_profit: u64,
+    min_profit_bps: u16,
+    tip: Option<Tip>,
+    flash_loan: Option<FlashLoanKeys>,
+    wrap_lamports: Option<u64>,
+    flags: u8,
+}
+
+/// 从利润中支付的小费
+#[derive(Clone, Copy, Debug)]
+struct Tip {
+    account: Pubkey,
+    lamports: u64,
+    bps: u16,
+}
+
+impl Route {
+    /// base -> token -> base，对应 opcode 4 和 9 个 header 账户
+    pub fn two_hop(buy: PoolKeys, sell: PoolKeys) -> Self {
+        let token = token_account_index(0);
+        Self::with_hops(
+            RouteKind::Direct,
+            vec![
+                Hop::new(buy, BASE_ACCOUNT_INDEX, token, false),
+                Hop::new(sell, token, BASE_ACCOUNT_INDEX, false),
+            ],
+        )
+    }
+
+    /// token -> base -> token，对应 opcode 10 和 9 个 header 账户。
+    /// amount_in、min_profit、闪电贷均以 token 计，利润按 header 中 token 账户的余额校验
+    pub fn two_hop_reverse(sell: PoolKeys, buy: PoolKeys) -> Self {
+        let token = token_account_index(0);
+        Self::with_hops(
+            RouteKind::Reverse,
+            vec![
+                Hop::new(sell, token, BASE_ACCOUNT_INDEX, false),
+                Hop::new(buy, BASE_ACCOUNT_INDEX, token, false),
+            ],
+        )
+    }
+
+    /// base -> token1 -> token2 -> base，对应 opcode 5 和 12 个 header 账户
+    pub fn three_hop(buy: PoolKeys, mid: PoolKeys, sell: PoolKeys) -> Self {
+        let token1 = token_account_index(0);
+        let token2 = token_account_index(1);
+        Self::with_hops(
+            RouteKind::Direct3,
+            vec![
+                Hop::new(buy, BASE_ACCOUNT_INDEX, token1, false),
+                Hop::new(mid, token1, token2, false),
+                Hop::new(sell, token2, BASE_ACCOUNT_INDEX, false),
+            ],
+        )
+    }
+
+    /// 任意跳数的通用路由，对应 opcode 6
+    pub fn n_hop(hops: Vec<Hop>) -> Self {
+        Self::with_hops(RouteKind::Generic, hops)
+    }
+
+    fn with_hops(kind: RouteKind, hops: Vec<Hop>) -> Self {
+        Self {
+            kind,
+            hops,
+            amount_in: 0,
+            pump_base_amount_out: 0,
+            min_profit: 0,
+            min_profit_bps: 0,
+            tip: None,
+            flash_loan: None,
+            wrap_lamports: None,
+            flags: 0,
+        }
+    }
+
+    pub fn amount_in(mut self, amount_in: u64) -> Self {
+        self.amount_in = amount_in;
+        self
+    }
+
+    /// Pump 买入的 base_amount_out，花费的 quote 不超过该跳的输入金额。
+    /// 为0（默认）时改用 buy_exact_quote_in，精确花费该跳的输入金额。
+    /// 路由中有多个 Pump 买入跳时必须为0，否则返回 AmbiguousPumpBaseAmountOut
+    pub fn pump_base_amount_out(mut self, pump_base_amount_out: u64) -> Self {
+        self.pump_base_amount_out = pump_base_amount_out;
+        self
+    }
+
+    /// 利润下限（基础资产的最小单位）
+    pub fn min_profit(mut self, min_profit: u64) -> Self {
+        self.min_profit = min_profit;
+        self
+    }
+
+    /// 相对 amount_in 的利润下限（bps），与 min_profit 取较严格者
+    pub fn min_profit_bps(mut self, min_profit_bps: u16) -> Self {
+        self.min_profit_bps = min_profit_bps;
+        self
+    }
+
+    /// 利润校验通过后从 payer 的 SOL 向 `account` 支付 `lamports + profit * bps / 10000`，
+    /// 不超过利润本身。小费以 lamports 计，基础资产不是 WSOL 时程序返回 `TipRequiresWsolBase`，
+    /// 反向路由的利润以 token 计，程序返回 `TipNotSupportedOnReverse`
+    pub fn tip(mut self, account: Pubkey, lamports: u64, bps: u16) -> Self {
+        self.tip = Some(Tip { account, lamports, bps });
+        self.set_flag(FLAG_TIP, true);
+        self
+    }
+
+    /// 通过 Solend 闪电贷借入 amount_in：`instructions` 在套利指令之前加入顶层 flash_borrow、
+    /// 之后加入 flash_repay。程序在执行前确认还款指令存在（否则返回 `FlashLoanNotRepaid`），
+    /// 并按 reserve 的 flash_loan_fee_wad 计算手续费，利润按扣除手续费后的余额校验
+    pub fn flash_loan(mut self, keys: FlashLoanKeys) -> Self {
+        self.flash_loan = Some(keys);
+        self.set_flag(FLAG_FLASH_LOAN, true);
+        self
+    }
+
+    /// 第一跳之前从 payer 向基础资产账户（须为 WSOL 账户）转入 `lamports` 并 SyncNative，
+    /// 包装的本金不计入利润
+    pub fn wrap_sol(mut self, lamports: u64) -> Self {
+        self.wrap_lamports = Some(lamports);
+        self.set_flag(FLAG_WRAP_SOL, true);
+        self
+    }
+
+    /// 利润校验之后关闭基础资产账户（须为 WSOL 账户），本金、利润和租金以 SOL 退回 payer。
+    /// 账户被关闭，下一笔交易前需要重新创建
+    pub fn unwrap_sol(mut self, unwrap: bool) -> Self {
+        self.set_flag(FLAG_UNWRAP_SOL, unwrap);
+        self
+    }
+
+    /// min_profit 超出 u32 或设置了 bps 时使用 v2 指令，否则沿用 v1 布局
+    fn is_v2(&self) -> bool {
+        self.min_profit > u32::MAX as u64 || self.min_profit_bps != 0
+    }
+
+    fn push_min_profit(&self, data: &mut Vec<u8>) {
+        if self.is_v2() {
+            data.extend_from_slice(&self.min_profit.to_le_bytes());
+            data.extend_from_slice(&self.min_profit_bps.to_le_bytes());
+        } else {
+            data.extend_from_slice(&(self.min_profit as u32).to_le_bytes());
+        }
+    }
+
+    /// 固定部分之后的可选参数：小费参数、包装金额
+    fn push_optional(&self, data: &mut Vec<u8>) {
+        if let Some(tip) = &self.tip {
+            data.extend_from_slice(&tip.lamports.to_le_bytes());
+            data.extend_from_slice(&tip.bps.to_le_bytes());
+        }
+        if let Some(wrap_lamports) = self.wrap_lamports {
+            data.extend_from_slice(&wrap_lamports.to_le_bytes());
+        }
+    }
+
+    /// 模拟模式：不做利润校验，通过 return data 返回盈亏和每跳成交（见 `SimulationResult`）。
+    /// 返回数据最多记录 `SIMULATION_MAX_HOPS` 跳，跳数更多的路由返回 `SimulationTooManyHops`
+    pub fn simulate(mut self, simulate: bool) -> Self {
+        self.set_flag(FLAG_SIMULATE, simulate);
+        self
+    }
+
+    pub fn validate_accounts(mut self, validate: bool) -> Self {
+        self.set_flag(FLAG_VALIDATE_ACCOUNTS, validate);
+        self
+    }
+
+    /// 默认每跳只卖出上一跳收到的数量，中间 token 账户原有的余额保持不动；
+    /// 设置后连同原有余额一起卖出
+    pub fn include_existing_balance(mut self, include: bool) -> Self {
+        self.set_flag(FLAG_INCLUDE_EXISTING_BALANCE, include);
+        self
+    }
+
+    /// 2hop/3hop：基础资产是否为第一个池 / 最后一个池的 token0
+    /// （正向路由为买入池 / 卖出池，反向2hop为卖出池 / 买入池）
+    pub fn base_pool_0(mut self, first: bool, last: bool) -> Result<Self, RouteError> {
+        let last_hop = self.hops.len().checked_sub(1).ok_or(RouteError::EmptyRoute)?;
+        self.hops[0].direction = first;
+        self.hops[last_hop].direction = last;
+        Ok(self)
+    }
+
+    /// 3hop：中间池是否为 token0 -> token1 方向
+    pub fn mid_zero_to_one(mut self, zero_to_one: bool) -> Result<Self, RouteError> {
+        if self.kind != RouteKind::Direct3 {
+            return Err(RouteError::NotThreeHop);
+        }
+        self.hops[1].direction = zero_to_one;
+        Ok(self)
+    }
+
+    /// 第 `hop` 跳（从0开始）改为精确输出 `amount_out`，见 [`Hop::exact_out`]。
+    /// 该跳已设置最小输出时返回 `MinOutOnExactOutHop`
+    pub fn exact_out(mut self, hop: usize, amount_out: u64) -> Result<Self, RouteError> {
+        let hops = self.hops.len();
+        let target = self.hops.get_mut(hop).ok_or(RouteError::HopOutOfRange { hop, hops })?;
+        if target.min_out != 0 {
+            return Err(RouteError::MinOutOnExactOutHop { hop });
+        }
+        target.exact_out = Some(amount_out);
+        Ok(self)
+    }
+
+    /// 每跳的最小输出，顺序与跳一致。精确输出的跳只能传0，否则返回 `MinOutOnExactOutHop`
+    pub fn min_outs(mut self, min_outs: &[u64]) -> Result<Self, RouteError> {
+        if min_outs.len() != self.hops.len() {
+            return Err(RouteError::MinOutCountMismatch { hops: self.hops.len(), min_outs: min_outs.len() });
+        }
+        if let Some(hop) = (0..self.hops.len()).find(|&i| self.hops[i].exact_out.is_some() && min_outs[i] != 0) {
+            return Err(RouteError::MinOutOnExactOutHop { hop });
+        }
+        for (hop, min_out) in self.hops.iter_mut().zip(min_outs) {
+            hop.min_out = *min_out;
+        }
+        Ok(self)
+    }
+
+    /// 第 `i` 跳是否以基础资产为输入，与程序中按跳确定账户数量的方式一致
+    fn is_base_input(&self, i: usize) -> bool {
+        match self.kind {
+            RouteKind::Direct | RouteKind::Direct3 => i == 0,
+            RouteKind::Reverse => i == 1,
+            RouteKind::Generic => self.hops[i].input_index == BASE_ACCOUNT_INDEX,
+        }
+    }
+
+    fn set_flag(&mut self, flag: u8, enabled: bool) {
+        if enabled {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+
+    /// 指令数据（含首字节 opcode）
+    pub fn data(&self, header: &RouteHeader) -> Vec<u8> {
+        let mut data = Vec::with_capacity(1 + ROUTE_HEADER_LEN_V2 + self.hops.len() * (ROUTE_HOP_LEN + 8));
+        let is_v2 = self.is_v2();
+
+        match self.kind {
+            RouteKind::Direct | RouteKind::Direct3 | RouteKind::Reverse => {
+                let opcode = match (self.kind, is_v2) {
+                    (RouteKind::Direct, false) => OPCODE_2HOP,
+                    (RouteKind::Direct, true) => OPCODE_2HOP_V2,
+                    (RouteKind::Reverse, false) => OPCODE_2HOP_REVERSE,
+                    (RouteKind::Reverse, true) => OPCODE_2HOP_REVERSE_V2,
+                    (_, false) => OPCODE_3HOP,
+                    (_, true) => OPCODE_3HOP_V2,
+                };
+                data.push(opcode);
+                data.extend(self.hops.iter().map(|hop| hop.pool.pool_type()));
+                data.extend(self.hops.iter().map(Hop::direction_byte));
+                data.push(self.flags);
+                data.extend_from_slice(&self.amount_in.to_le_bytes());
+                data.extend_from_slice(&self.pump_base_amount_out.to_le_bytes());
+                self.push_min_profit(&mut data);
+                debug_assert_eq!(
+                    data.len() - 1,
+                    match (self.kind, is_v2) {
+                        (RouteKind::Direct | RouteKind::Reverse, false) => SWAP_DATA_LEN,
+                        (RouteKind::Direct | RouteKind::Reverse, true) => SWAP_DATA_LEN_V2,
+                        (_, false) => SWAP_DATA_LEN_3HOP,
+                        (_, true) => SWAP_DATA_LEN_3HOP_V2,
+                    }
+                );
+                self.push_optional(&mut data);
+            }
+            RouteKind::Generic => {
+                // 任意一跳设置了份额时使用拆分布局
+                let is_split = self.hops.iter().any(|hop| hop.share.is_some());
+                data.push(if is_v2 { OPCODE_ROUTE_V2 } else { OPCODE_ROUTE });
+                data.push(self.hops.len() as u8);
+                data.push(header.account_count() as u8);
+                data.push(if is_split { self.flags | FLAG_SPLIT } else { self.flags });
+                data.extend_from_slice(&self.amount_in.to_le_bytes());
+                data.extend_from_slice(&self.pump_base_amount_out.to_le_bytes());
+                self.push_min_profit(&mut data);
+                debug_assert_eq!(data.len() - 1, if is_v2 { ROUTE_HEADER_LEN_V2 } else { ROUTE_HEADER_LEN });
+                self.push_optional(&mut data);
+                for hop in &self.hops {
+                    let start = data.len();
+                    data.push(hop.pool.pool_type());
+                    data.push(hop.direction_byte());
+                    data.push(hop.input_index);
+                    data.push(hop.output_index);
+                    if is_split {
+                        let (kind, share) = match hop.share {
+                            Some(Share::Amount(amount)) => (SHARE_KIND_AMOUNT, amount),
+                            Some(Share::Bps(bps)) => (SHARE_KIND_BPS, bps as u64),
+                            None => (SHARE_KIND_BPS, 0),
+                        };
+                        data.push(kind);
+                        data.extend_from_slice(&share.to_le_bytes());
+                    }
+                    debug_assert_eq!(data.len() - start, if is_split { ROUTE_HOP_LEN_SPLIT } else { ROUTE_HOP_LEN });
+                }
+            }
+        }
+
+        // 可选的每跳 min_out（精确输出的跳为输出数量），全为0且没有精确输出的跳时省略
+        if self.hops.iter().any(|hop| hop.min_out_slot() != 0 || hop.exact_out.is_some()) {
+            for hop in &self.hops {
+                data.extend_from_slice(&hop.min_out_slot().to_le_bytes());
+            }
+        }
+
+        data
+    }
+
+    /// 账户列表：header 账户在前，设置了小费时紧跟 tip 账户，设置了闪电贷时再紧跟 reserve 和
+    /// instructions sysvar，随后按跳的顺序排列每个池的账户，
+    /// 设置了小费或包装 SOL 时最后追加 System Program。
+    /// header 的中间 token 数量与 2hop / 3hop 不符时返回 `HeaderAccountCount`
+    pub fn accounts(&self, header: &RouteHeader) -> Result<Vec<AccountMeta>, RouteError> {
+        let expected = match self.kind {
+            RouteKind::Direct | RouteKind::Reverse => Some(HEADER_ACCOUNT_COUNT),
+            RouteKind::Direct3 => Some(HEADER_ACCOUNT_COUNT_3HOP),
+            RouteKind::Generic => None,
+        };
+        if let Some(expected) = expected.filter(|expected| *expected != header.account_count()) {
+            return Err(RouteError::HeaderAccountCount { expected, got: header.account_count() });
+        }
+
+        let mut metas = header.metas();
+        if let Some(tip) = &self.tip {
+            metas.push(AccountMeta::new(tip.account, false));
+        }
+        if let Some(keys) = &self.flash_loan {
+            metas.push(AccountMeta::new_readonly(keys.reserve, false));
+            metas.push(AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false));
+        }
+        for (i, hop) in self.hops.iter().enumerate() {
+            match &hop.pool {
+                // Pump.fun 卖出跳不需要解包用的临时 WSOL 账户
+                PoolKeys::PumpFun(keys) if !self.is_base_input(i) => metas.extend(keys.sell_metas()),
+                pool => pool.append_metas(&mut metas),
+            }
+        }
+        if self.tip.is_some() || self.wrap_lamports.is_some() {
+            metas.push(AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false));
+        }
+        Ok(metas)
+    }
+
+    /// 套利指令本身；设置了闪电贷时还需要前后的借还款指令，使用 `instructions`
+    pub fn instruction(&self, program_id: Pubkey, header: &RouteHeader) -> Result<Instruction, RouteError> {
+        Ok(Instruction {
+            program_id,
+            accounts: self.accounts(header)?,
+            data: self.data(header),
+        })
+    }
+
+    /// 交易中连续的指令：设置了闪电贷时为 [flash_borrow, 套利指令, flash_repay]，否则只有套利指令。
+    /// `first_index` 是第一条指令在交易中的下标（前面有 ComputeBudget 等指令时不为0），
+    /// 还款指令据此引用借款指令
+    pub fn instructions(
+        &self,
+        program_id: Pubkey,
+        header: &RouteHeader,
+        first_index: u8,
+    ) -> Result<Vec<Instruction>, RouteError> {
+        let instruction = self.instruction(program_id, header)?;
+        let Some(keys) = &self.flash_loan else {
+            return Ok(vec![instruction]);
+        };
+
+        // 反向路由借入的是 token，其余路由借入基础资产
+        let (liquidity, token_program) = match self.kind {
+            RouteKind::Reverse => (header.tokens[0].account, header.tokens[0].token_program),
+            _ => (header.base_account, header.base_token_program),
+        };
+        Ok(vec![
+            keys.borrow_instruction(self.amount_in, liquidity, token_program),
+            instruction,
+            keys.repay_instruction(self.amount_in, liquidity, header.payer, token_program, first_index),
+        ])
+    }
+}
@@ -0,0 +1,50 @@
+//! 解码模拟模式（`Route::simulate(true)`）通过 return data 返回的结构化结果，
+//! 布局见 `my_pinocchio_cpi::simulation`。
+
+use my_pinocchio_cpi::simulation::{SIMULATION_HEADER_LEN, SIMULATION_HOP_LEN, SIMULATION_VERSION};
+
+/// 单跳的实际成交
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HopResult {
+    pub amount_in: u64,
+    /// 输出 token 账户余额的增量
+    pub amount_out: u64,
+    pub compute_units: u64,
+}
+
+/// 模拟结果：盈亏（基础资产）和每跳成交
+///
+/// 模拟模式下不做利润校验，亏损的路由同样返回结果，`pnl` 为负
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulationResult {
+    pub pnl: i64,
+    pub hops: Vec<HopResult>,
+}
+
+impl SimulationResult {
+    /// 解码 return data，版本不匹配或长度不足时返回 None
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let header = data.get(..SIMULATION_HEADER_LEN)?;
+        if header[0] != SIMULATION_VERSION {
+            return None;
+        }
+
+        let hop_count = header[1] as usize;
+        let pnl = read_u64(header, 2) as i64;
+        let hops = data[SIMULATION_HEADER_LEN..]
+            .get(..hop_count * SIMULATION_HOP_LEN)?
+            .chunks_exact(SIMULATION_HOP_LEN)
+            .map(|hop| HopResult {
+                amount_in: read_u64(hop, 0),
+                amount_out: read_u64(hop, 8),
+                compute_units: read_u64(hop, 16),
+            })
+            .collect();
+
+        Some(Self { pnl, hops })
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
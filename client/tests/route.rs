@@ -0,0 +1,465 @@
+//! 指令数据和账户顺序的字节布局测试，并用程序侧的解析函数读回，不需要 SBF 产物：
+//!
+//! ```text
+//! cargo test -p my-pinocchio-cpi-client
+//! ```
+
+use my_pinocchio_cpi::utils::{
+    get_route_hop, parse_instruction_data, parse_route_data, DIRECTION_EXACT_OUT, FLAG_FLASH_LOAN, FLAG_SIMULATE,
+    FLAG_SPLIT, FLAG_TIP, FLAG_WRAP_SOL, HEADER_ACCOUNT_COUNT, HEADER_ACCOUNT_COUNT_3HOP, PUMP_FUN_SELL_ACCOUNT_COUNT,
+    ROUTE_HEADER_LEN, ROUTE_HEADER_LEN_V2, ROUTE_HOP_LEN, ROUTE_HOP_LEN_SPLIT, SHARE_KIND_AMOUNT, SHARE_KIND_BPS,
+    SWAP_DATA_LEN, SWAP_DATA_LEN_3HOP, SWAP_DATA_LEN_3HOP_V2, SWAP_DATA_LEN_V2,
+};
+use my_pinocchio_cpi_client::{
+    token_account_index, CpmmKeys, FlashLoanKeys, Hop, PoolKeys, PumpFunKeys, RaydiumKeys, Route, RouteError, RouteHeader,
+    TokenKeys, BASE_ACCOUNT_INDEX, INSTRUCTIONS_SYSVAR_ID, MEMO_PROGRAM_ID, OPCODE_2HOP, OPCODE_2HOP_REVERSE, OPCODE_2HOP_REVERSE_V2,
+    OPCODE_2HOP_V2, OPCODE_3HOP, OPCODE_3HOP_V2, OPCODE_ROUTE, OPCODE_ROUTE_V2, SOLEND_PROGRAM_ID,
+    SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+};
+use solana_instruction::AccountMeta;
+use solana_pubkey::Pubkey;
+
+const AMOUNT_IN: u64 = 1_000_000;
+const PUMP_BASE_AMOUNT_OUT: u64 = 990_000;
+const MIN_PROFIT: u64 = 5_000;
+
+const CPMM: u8 = 0;
+const RAYDIUM: u8 = 4;
+
+fn cpmm() -> PoolKeys {
+    PoolKeys::Cpmm(CpmmKeys {
+        program: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        amm_config: Pubkey::new_unique(),
+        observation_state: Pubkey::new_unique(),
+        pool_state: Pubkey::new_unique(),
+        token_0_vault: Pubkey::new_unique(),
+        token_1_vault: Pubkey::new_unique(),
+    })
+}
+
+fn raydium() -> PoolKeys {
+    PoolKeys::Raydium(RaydiumKeys {
+        program: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        amm: Pubkey::new_unique(),
+        pool_coin_token_account: Pubkey::new_unique(),
+        pool_pc_token_account: Pubkey::new_unique(),
+    })
+}
+
+fn pump_fun() -> PoolKeys {
+    PoolKeys::PumpFun(PumpFunKeys {
+        program: Pubkey::new_unique(),
+        global: Pubkey::new_unique(),
+        fee_recipient: Pubkey::new_unique(),
+        bonding_curve: Pubkey::new_unique(),
+        associated_bonding_curve: Pubkey::new_unique(),
+        creator_vault: Pubkey::new_unique(),
+        event_authority: Pubkey::new_unique(),
+        global_volume_accumulator: Pubkey::new_unique(),
+        user_volume_accumulator: Pubkey::new_unique(),
+        fee_config: Pubkey::new_unique(),
+        fee_program: Pubkey::new_unique(),
+        system_program: Pubkey::new_unique(),
+        user_sol_account: Pubkey::new_unique(),
+    })
+}
+
+fn flash_loan_keys() -> FlashLoanKeys {
+    FlashLoanKeys {
+        reserve: Pubkey::new_unique(),
+        reserve_liquidity_supply: Pubkey::new_unique(),
+        reserve_liquidity_fee_receiver: Pubkey::new_unique(),
+        host_fee_receiver: Pubkey::new_unique(),
+        lending_market: Pubkey::new_unique(),
+        lending_market_authority: Pubkey::new_unique(),
+    }
+}
+
+fn header(token_count: usize) -> RouteHeader {
+    let tokens = (0..token_count)
+        .map(|_| TokenKeys {
+            mint: Pubkey::new_unique(),
+            token_program: TOKEN_PROGRAM_ID,
+            account: Pubkey::new_unique(),
+        })
+        .collect();
+    RouteHeader::new(Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), tokens)
+}
+
+/// 除 opcode 外的固定部分：pool_type / 方向字节、flags、amount_in、pump_base_amount_out、v1 的 u32 min_profit
+fn fixed_part(pool_types: &[u8], directions: &[u8], flags: u8) -> Vec<u8> {
+    let mut data = [pool_types, directions, &[flags]].concat();
+    data.extend_from_slice(&AMOUNT_IN.to_le_bytes());
+    data.extend_from_slice(&PUMP_BASE_AMOUNT_OUT.to_le_bytes());
+    data.extend_from_slice(&(MIN_PROFIT as u32).to_le_bytes());
+    data
+}
+
+fn u64s(values: &[u64]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+#[test]
+fn two_hop_layout() {
+    let route = Route::two_hop(cpmm(), raydium())
+        .base_pool_0(true, false)
+        .unwrap()
+        .amount_in(AMOUNT_IN)
+        .pump_base_amount_out(PUMP_BASE_AMOUNT_OUT)
+        .min_profit(MIN_PROFIT)
+        .simulate(true);
+    let data = route.data(&header(1));
+
+    assert_eq!(data[0], OPCODE_2HOP);
+    assert_eq!(data[1..], fixed_part(&[CPMM, RAYDIUM], &[1, 0], FLAG_SIMULATE));
+    assert_eq!(data.len(), 1 + SWAP_DATA_LEN);
+
+    let params = parse_instruction_data(&data[1..], false, false).unwrap();
+    assert_eq!((params.buy, params.sell), (CPMM, RAYDIUM));
+    assert!(params.is_base_pool_0_buy && !params.is_base_pool_0_sell);
+    assert!(params.is_simulate);
+    assert_eq!(params.amount_in, AMOUNT_IN);
+    assert_eq!(params.pump_base_amount_out, PUMP_BASE_AMOUNT_OUT);
+    assert_eq!(params.min_profit, MIN_PROFIT);
+}
+
+#[test]
+fn three_hop_layout() {
+    let route = Route::three_hop(cpmm(), raydium(), cpmm())
+        .base_pool_0(false, true)
+        .unwrap()
+        .mid_zero_to_one(true)
+        .unwrap()
+        .exact_out(0, 42)
+        .unwrap()
+        .amount_in(AMOUNT_IN)
+        .pump_base_amount_out(PUMP_BASE_AMOUNT_OUT)
+        .min_profit(MIN_PROFIT);
+    let data = route.data(&header(2));
+
+    assert_eq!(data[0], OPCODE_3HOP);
+    // 精确输出的跳在 min_out 槽位写入输出数量，所有跳的 min_out 紧跟在固定部分之后
+    let mut expected = fixed_part(&[CPMM, RAYDIUM, CPMM], &[DIRECTION_EXACT_OUT, 1, 1], 0);
+    expected.extend(u64s(&[42, 0, 0]));
+    assert_eq!(data[1..], expected);
+    assert_eq!(data.len(), 1 + SWAP_DATA_LEN_3HOP + 3 * 8);
+
+    let params = parse_instruction_data(&data[1..], true, false).unwrap();
+    assert_eq!((params.buy, params.mid, params.sell), (CPMM, Some(RAYDIUM), CPMM));
+    assert_eq!(params.is_mid_zero_to_one, Some(true));
+    assert!(params.buy_exact_out && !params.mid_exact_out && !params.sell_exact_out);
+    assert_eq!(params.buy_min_out, 42);
+}
+
+#[test]
+fn reverse_two_hop_uses_its_own_opcodes() {
+    let route = Route::two_hop_reverse(cpmm(), raydium()).amount_in(AMOUNT_IN);
+    let data = route.data(&header(1));
+    assert_eq!(data[0], OPCODE_2HOP_REVERSE);
+    // 第一个池是卖出池
+    assert_eq!(data[1..3], [CPMM, RAYDIUM]);
+
+    let data = route.min_profit_bps(10).data(&header(1));
+    assert_eq!(data[0], OPCODE_2HOP_REVERSE_V2);
+    assert_eq!(data.len(), 1 + SWAP_DATA_LEN_V2);
+}
+
+#[test]
+fn v2_is_selected_by_min_profit_above_u32_or_bps() {
+    let large = u32::MAX as u64 + 1;
+    let routes = [
+        (Route::two_hop(cpmm(), cpmm()), header(1), OPCODE_2HOP, OPCODE_2HOP_V2, SWAP_DATA_LEN, SWAP_DATA_LEN_V2),
+        (
+            Route::three_hop(cpmm(), cpmm(), cpmm()),
+            header(2),
+            OPCODE_3HOP,
+            OPCODE_3HOP_V2,
+            SWAP_DATA_LEN_3HOP,
+            SWAP_DATA_LEN_3HOP_V2,
+        ),
+    ];
+
+    for (route, header, v1, v2, v1_len, v2_len) in routes {
+        let data = route.clone().min_profit(u32::MAX as u64).data(&header);
+        assert_eq!((data[0], data.len()), (v1, 1 + v1_len));
+        assert_eq!(data[v1_len - 3..], u32::MAX.to_le_bytes());
+
+        // v2：u64 min_profit + u16 min_profit_bps
+        let data = route.clone().min_profit(large).data(&header);
+        assert_eq!((data[0], data.len()), (v2, 1 + v2_len));
+        assert_eq!(data[v2_len - 9..v2_len - 1], large.to_le_bytes());
+        assert_eq!(data[v2_len - 1..], [0, 0]);
+
+        let data = route.min_profit_bps(250).data(&header);
+        assert_eq!((data[0], data.len()), (v2, 1 + v2_len));
+        assert_eq!(data[v2_len - 1..], 250u16.to_le_bytes());
+    }
+
+    let header = header(1);
+    let token = token_account_index(0);
+    let route = Route::n_hop(vec![
+        Hop::new(cpmm(), BASE_ACCOUNT_INDEX, token, true),
+        Hop::new(cpmm(), token, BASE_ACCOUNT_INDEX, false),
+    ]);
+    let data = route.clone().data(&header);
+    assert_eq!((data[0], data.len()), (OPCODE_ROUTE, 1 + ROUTE_HEADER_LEN + 2 * ROUTE_HOP_LEN));
+    let data = route.min_profit(large).data(&header);
+    assert_eq!((data[0], data.len()), (OPCODE_ROUTE_V2, 1 + ROUTE_HEADER_LEN_V2 + 2 * ROUTE_HOP_LEN));
+    let params = parse_route_data(&data[1..], true).unwrap();
+    assert_eq!(params.min_profit, large);
+}
+
+#[test]
+fn optional_data_follows_the_fixed_part_in_order() {
+    let tip_account = Pubkey::new_unique();
+    let route = Route::two_hop(cpmm(), cpmm())
+        .amount_in(AMOUNT_IN)
+        .pump_base_amount_out(PUMP_BASE_AMOUNT_OUT)
+        .min_profit(MIN_PROFIT)
+        .tip(tip_account, 1_000, 300)
+        .wrap_sol(2_000)
+        .flash_loan(flash_loan_keys());
+    let data = route.data(&header(1));
+
+    // 小费参数 (u64 + u16) -> 包装金额；闪电贷手续费从 reserve 读取，不在指令数据中
+    let mut expected = fixed_part(&[CPMM, CPMM], &[0, 0], FLAG_TIP | FLAG_WRAP_SOL | FLAG_FLASH_LOAN);
+    expected.extend(1_000u64.to_le_bytes());
+    expected.extend(300u16.to_le_bytes());
+    expected.extend(u64s(&[2_000]));
+    assert_eq!(data[1..], expected);
+
+    let params = parse_instruction_data(&data[1..], false, false).unwrap();
+    assert_eq!((params.tip_lamports, params.tip_bps), (1_000, 300));
+    assert_eq!(params.wrap_lamports, 2_000);
+    assert!(params.has_flash_loan);
+}
+
+#[test]
+fn min_outs_trail_the_data_only_when_set() {
+    let route = Route::two_hop(cpmm(), cpmm()).amount_in(AMOUNT_IN).wrap_sol(2_000);
+    let without = route.clone().min_outs(&[0, 0]).unwrap().data(&header(1));
+    assert_eq!(without.len(), 1 + SWAP_DATA_LEN + 8);
+
+    // min_out 在可选参数之后
+    let with = route.min_outs(&[7, 9]).unwrap().data(&header(1));
+    assert_eq!(with[..without.len()], without);
+    assert_eq!(with[without.len()..], u64s(&[7, 9]));
+    let params = parse_instruction_data(&with[1..], false, false).unwrap();
+    assert_eq!((params.buy_min_out, params.sell_min_out), (7, 9));
+
+    // N-hop 中在所有跳之后
+    let header = header(2);
+    let (token1, token2) = (token_account_index(0), token_account_index(1));
+    let data = Route::n_hop(vec![
+        Hop::new(cpmm(), BASE_ACCOUNT_INDEX, token1, true),
+        Hop::new(cpmm(), token1, token2, false).min_out(5),
+        Hop::new(cpmm(), token2, BASE_ACCOUNT_INDEX, true),
+    ])
+    .data(&header);
+    let hops_end = 1 + ROUTE_HEADER_LEN + 3 * ROUTE_HOP_LEN;
+    assert_eq!(data[hops_end..], u64s(&[0, 5, 0]));
+    let params = parse_route_data(&data[1..], false).unwrap();
+    assert_eq!(params.min_outs, u64s(&[0, 5, 0]));
+
+    // 精确输出的跳总是写出 min_outs，即使输出数量为0（程序以 InvalidTradeAmount 拒绝）
+    let route = Route::two_hop(cpmm(), cpmm()).amount_in(AMOUNT_IN).wrap_sol(2_000);
+    let data = route.clone().exact_out(0, 0).unwrap().data(&header);
+    assert_eq!(data[without.len()..], u64s(&[0, 0]));
+    let data = route.exact_out(0, 500).unwrap().min_outs(&[0, 9]).unwrap().data(&header);
+    assert_eq!(data[without.len()..], u64s(&[500, 9]));
+}
+
+#[test]
+fn n_hop_layout() {
+    let header = header(2);
+    let (token1, token2) = (token_account_index(0), token_account_index(1));
+    let route = Route::n_hop(vec![
+        Hop::new(cpmm(), BASE_ACCOUNT_INDEX, token1, true),
+        Hop::new(raydium(), token1, token2, false).exact_out(11),
+        Hop::new(cpmm(), token2, BASE_ACCOUNT_INDEX, false),
+    ])
+    .amount_in(AMOUNT_IN)
+    .pump_base_amount_out(PUMP_BASE_AMOUNT_OUT)
+    .min_profit(MIN_PROFIT)
+    .simulate(true);
+    let data = route.data(&header);
+
+    let mut expected = vec![OPCODE_ROUTE, 3, header.account_count() as u8, FLAG_SIMULATE];
+    expected.extend(AMOUNT_IN.to_le_bytes());
+    expected.extend(PUMP_BASE_AMOUNT_OUT.to_le_bytes());
+    expected.extend((MIN_PROFIT as u32).to_le_bytes());
+    expected.extend([CPMM, 1, BASE_ACCOUNT_INDEX, token1]);
+    expected.extend([RAYDIUM, DIRECTION_EXACT_OUT, token1, token2]);
+    expected.extend([CPMM, 0, token2, BASE_ACCOUNT_INDEX]);
+    expected.extend(u64s(&[0, 11, 0]));
+    assert_eq!(data, expected);
+
+    let params = parse_route_data(&data[1..], false).unwrap();
+    assert_eq!((params.hop_count, params.header_count), (3, HEADER_ACCOUNT_COUNT_3HOP));
+    let hop = get_route_hop(params.hops, params.hop_len, 1);
+    assert_eq!((hop.pool_type, hop.is_zero_to_one, hop.is_exact_out), (RAYDIUM, false, true));
+    assert_eq!((hop.input_index, hop.output_index), (token1 as usize, token2 as usize));
+}
+
+#[test]
+fn n_hop_split_layout() {
+    let header = header(1);
+    let token = token_account_index(0);
+    let route = Route::n_hop(vec![
+        Hop::new(cpmm(), BASE_ACCOUNT_INDEX, token, true).share_bps(4_000),
+        Hop::new(raydium(), BASE_ACCOUNT_INDEX, token, true).share_amount(300),
+        Hop::new(cpmm(), BASE_ACCOUNT_INDEX, token, false),
+        Hop::new(cpmm(), token, BASE_ACCOUNT_INDEX, false),
+    ]);
+    let data = route.data(&header);
+
+    assert_eq!(data[3], FLAG_SPLIT);
+    assert_eq!(data.len(), 1 + ROUTE_HEADER_LEN + 4 * ROUTE_HOP_LEN_SPLIT);
+    let hop = |i: usize| &data[1 + ROUTE_HEADER_LEN + i * ROUTE_HOP_LEN_SPLIT..][..ROUTE_HOP_LEN_SPLIT];
+    assert_eq!(hop(0)[4], SHARE_KIND_BPS);
+    assert_eq!(hop(0)[5..], 4_000u64.to_le_bytes());
+    assert_eq!(hop(1)[4], SHARE_KIND_AMOUNT);
+    assert_eq!(hop(1)[5..], 300u64.to_le_bytes());
+    // 未设置份额的跳编码为 bps 0
+    assert_eq!(hop(2)[4..], [SHARE_KIND_BPS, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    let params = parse_route_data(&data[1..], false).unwrap();
+    assert!(params.is_split);
+    assert_eq!(params.hop_len, ROUTE_HOP_LEN_SPLIT);
+}
+
+#[test]
+fn accounts_are_header_then_tip_then_pools_then_system_program() {
+    let header = header(1);
+    let (buy, sell) = (cpmm(), raydium());
+    let tip_account = Pubkey::new_unique();
+    let route = Route::two_hop(buy.clone(), sell.clone()).tip(tip_account, 1_000, 0);
+    let accounts = route.accounts(&header).unwrap();
+
+    let token = &header.tokens[0];
+    let expected_header = [
+        AccountMeta::new(header.payer, true),
+        AccountMeta::new_readonly(header.base_mint, false),
+        AccountMeta::new(header.base_account, false),
+        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+        AccountMeta::new_readonly(MEMO_PROGRAM_ID, false),
+        AccountMeta::new_readonly(token.mint, false),
+        AccountMeta::new_readonly(token.token_program, false),
+        AccountMeta::new(token.account, false),
+    ];
+    assert_eq!(accounts[..HEADER_ACCOUNT_COUNT], expected_header);
+
+    // tip 账户紧跟 header，System Program 在所有池账户之后
+    assert_eq!(accounts[HEADER_ACCOUNT_COUNT], AccountMeta::new(tip_account, false));
+    let mut pools = Vec::new();
+    buy.append_metas(&mut pools);
+    sell.append_metas(&mut pools);
+    assert_eq!(accounts[HEADER_ACCOUNT_COUNT + 1..accounts.len() - 1], pools);
+    assert_eq!(accounts.last(), Some(&AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false)));
+
+    // 包装 SOL 不改变 tip 账户的位置
+    let accounts = route.clone().wrap_sol(1).accounts(&header).unwrap();
+    assert_eq!(accounts[HEADER_ACCOUNT_COUNT], AccountMeta::new(tip_account, false));
+    assert_eq!(accounts.len(), HEADER_ACCOUNT_COUNT + 1 + pools.len() + 1);
+
+    // 只包装 SOL 时只追加 System Program，都没有时以最后一个池的账户结尾
+    let accounts = Route::two_hop(buy.clone(), sell.clone()).wrap_sol(1).accounts(&header).unwrap();
+    assert_eq!(accounts.len(), HEADER_ACCOUNT_COUNT + pools.len() + 1);
+    assert_eq!(accounts.last(), Some(&AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false)));
+    let accounts = Route::two_hop(buy.clone(), sell.clone()).accounts(&header).unwrap();
+    assert_eq!(accounts[HEADER_ACCOUNT_COUNT..], pools);
+
+    // 闪电贷的 reserve 和 instructions sysvar 在 tip 账户之后、池账户之前
+    let keys = flash_loan_keys();
+    let accounts = route.clone().flash_loan(keys.clone()).accounts(&header).unwrap();
+    assert_eq!(accounts[HEADER_ACCOUNT_COUNT], AccountMeta::new(tip_account, false));
+    assert_eq!(
+        accounts[HEADER_ACCOUNT_COUNT + 1..HEADER_ACCOUNT_COUNT + 3],
+        [AccountMeta::new_readonly(keys.reserve, false), AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false)]
+    );
+    assert_eq!(accounts[HEADER_ACCOUNT_COUNT + 3..accounts.len() - 1], pools);
+
+    // Token-2022 基础资产：[3] 为基础资产的 token 程序
+    let header = header.base_token_program(TOKEN_2022_PROGRAM_ID);
+    let accounts = Route::two_hop(buy, sell).accounts(&header).unwrap();
+    assert_eq!(accounts[3], AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false));
+}
+
+#[test]
+fn builder_rejects_settings_that_do_not_fit_the_route() {
+    assert_eq!(Route::n_hop(vec![]).base_pool_0(true, true).unwrap_err(), RouteError::EmptyRoute);
+    assert_eq!(Route::two_hop(cpmm(), cpmm()).mid_zero_to_one(true).unwrap_err(), RouteError::NotThreeHop);
+    assert_eq!(
+        Route::two_hop(cpmm(), cpmm()).min_outs(&[1, 2, 3]).unwrap_err(),
+        RouteError::MinOutCountMismatch { hops: 2, min_outs: 3 }
+    );
+    assert_eq!(
+        Route::two_hop(cpmm(), cpmm()).exact_out(2, 1).unwrap_err(),
+        RouteError::HopOutOfRange { hop: 2, hops: 2 }
+    );
+    // 精确输出和最小输出共用一个槽位，后设置的不能覆盖先设置的
+    assert_eq!(
+        Route::two_hop(cpmm(), cpmm()).exact_out(1, 500).unwrap().min_outs(&[0, 7]).unwrap_err(),
+        RouteError::MinOutOnExactOutHop { hop: 1 }
+    );
+    assert_eq!(
+        Route::two_hop(cpmm(), cpmm()).min_outs(&[0, 7]).unwrap().exact_out(1, 500).unwrap_err(),
+        RouteError::MinOutOnExactOutHop { hop: 1 }
+    );
+    assert_eq!(
+        Route::two_hop(cpmm(), cpmm()).accounts(&header(2)).unwrap_err(),
+        RouteError::HeaderAccountCount { expected: HEADER_ACCOUNT_COUNT, got: HEADER_ACCOUNT_COUNT_3HOP }
+    );
+    assert_eq!(
+        Route::three_hop(cpmm(), cpmm(), cpmm()).instruction(Pubkey::new_unique(), &header(1)).unwrap_err(),
+        RouteError::HeaderAccountCount { expected: HEADER_ACCOUNT_COUNT_3HOP, got: HEADER_ACCOUNT_COUNT }
+    );
+}
+
+#[test]
+fn flash_loan_wraps_the_route_in_solend_borrow_and_repay() {
+    assert_eq!(SOLEND_PROGRAM_ID.to_bytes(), my_pinocchio_cpi::constants::SOLEND_PROGRAM_ID);
+
+    let header = header(1);
+    let route = Route::two_hop(cpmm(), cpmm()).amount_in(AMOUNT_IN).flash_loan(flash_loan_keys());
+    let program_id = Pubkey::new_unique();
+    let instructions = route.instructions(program_id, &header, 2).unwrap();
+
+    let program_ids: Vec<_> = instructions.iter().map(|instruction| instruction.program_id).collect();
+    assert_eq!(program_ids, [SOLEND_PROGRAM_ID, program_id, SOLEND_PROGRAM_ID]);
+    // 借入到基础资产账户，还款引用借款指令在交易中的下标
+    assert_eq!(instructions[0].accounts[1].pubkey, header.base_account);
+    assert_eq!(instructions[2].accounts[0].pubkey, header.base_account);
+    assert_eq!(instructions[2].data.last(), Some(&2));
+}
+
+#[test]
+fn pump_fun_sell_hops_omit_the_scratch_account() {
+    let (buy, sell) = (pump_fun(), pump_fun());
+    let PoolKeys::PumpFun(buy_keys) = &buy else { unreachable!() };
+    let PoolKeys::PumpFun(sell_keys) = &sell else { unreachable!() };
+    let buy_metas = buy_keys.metas().to_vec();
+    let sell_metas = sell_keys.sell_metas().to_vec();
+    assert_eq!(sell_metas.len(), PUMP_FUN_SELL_ACCOUNT_COUNT);
+    assert!(!sell_metas.iter().any(|meta| meta.pubkey == sell_keys.user_sol_account));
+
+    // 2hop：买入跳带临时账户，卖出跳不带
+    let header = header(1);
+    let accounts = Route::two_hop(buy.clone(), sell.clone()).accounts(&header).unwrap();
+    assert_eq!(accounts[HEADER_ACCOUNT_COUNT..], [buy_metas.clone(), sell_metas.clone()].concat());
+
+    // 反向路由先卖后买，N-hop 按输入是否为基础资产区分
+    let accounts = Route::two_hop_reverse(sell.clone(), buy.clone()).accounts(&header).unwrap();
+    assert_eq!(accounts[HEADER_ACCOUNT_COUNT..], [sell_metas.clone(), buy_metas.clone()].concat());
+    let token = token_account_index(0);
+    let accounts = Route::n_hop(vec![
+        Hop::new(buy, BASE_ACCOUNT_INDEX, token, true),
+        Hop::new(sell, token, BASE_ACCOUNT_INDEX, true),
+    ])
+    .accounts(&header)
+    .unwrap();
+    assert_eq!(accounts[HEADER_ACCOUNT_COUNT..], [buy_metas, sell_metas].concat());
+}
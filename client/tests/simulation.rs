@@ -0,0 +1,51 @@
+//! 用程序侧的 `encode` 生成 return data，验证客户端 `SimulationResult::decode` 的布局一致，
+//! 不需要 SBF 产物：
+//!
+//! ```text
+//! cargo test -p my-pinocchio-cpi-client
+//! ```
+
+use core::mem::MaybeUninit;
+use my_pinocchio_cpi::simulation::{encode, encode_hop, SIMULATION_MAX_HOPS, SIMULATION_MAX_LEN};
+use my_pinocchio_cpi_client::{HopResult, SimulationResult};
+
+fn encoded(pnl: i64, hops: &[HopResult]) -> Vec<u8> {
+    let hops: Vec<_> = hops.iter().map(|hop| encode_hop(hop.amount_in, hop.amount_out, hop.compute_units)).collect();
+    let mut buffer = [MaybeUninit::uninit(); SIMULATION_MAX_LEN];
+    encode(&mut buffer, pnl, &hops).to_vec()
+}
+
+fn hop(i: u64) -> HopResult {
+    HopResult { amount_in: 1_000 * i, amount_out: u64::MAX - i, compute_units: 20_000 + i }
+}
+
+#[test]
+fn decode_round_trips_encode() {
+    for pnl in [0, 12_345, -12_345, i64::MIN, i64::MAX] {
+        for hop_count in [0, 1, 3, SIMULATION_MAX_HOPS] {
+            let hops: Vec<_> = (0..hop_count as u64).map(hop).collect();
+            let result = SimulationResult::decode(&encoded(pnl, &hops));
+            assert_eq!(result, Some(SimulationResult { pnl, hops }), "pnl {pnl} hops {hop_count}");
+        }
+    }
+}
+
+#[test]
+fn hops_beyond_the_limit_are_not_recorded() {
+    let hops: Vec<_> = (0..SIMULATION_MAX_HOPS as u64 + 2).map(hop).collect();
+    let result = SimulationResult::decode(&encoded(1, &hops)).unwrap();
+    assert_eq!(result.hops, hops[..SIMULATION_MAX_HOPS]);
+}
+
+#[test]
+fn decode_rejects_wrong_version_and_short_data() {
+    let data = encoded(1, &[hop(1), hop(2)]);
+
+    let mut wrong_version = data.clone();
+    wrong_version[0] ^= 0xff;
+    assert_eq!(SimulationResult::decode(&wrong_version), None);
+
+    for len in [0, 1, 9, data.len() - 1] {
+        assert_eq!(SimulationResult::decode(&data[..len]), None, "len {len}");
+    }
+}
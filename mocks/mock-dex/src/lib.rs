@@ -0,0 +1,678 @@
+//! 集成测试用的 DEX mock 程序。
+//!
+//! 同一个 ELF 以多个 program id 部署，program id 的最后一个字节就是池类型
+//! (0 CPMM, 1 DLMM, 2 DAMMv2, 3 Pump, 4 Raydium, 5 CLMM, 6 Whirlpool, 7 Raydium + OpenBook,
+//! 8 Pump.fun 联合曲线, 9 DAMM v1, 10 DBC, 11 LaunchLab)。
+//! 每个 mock 按 `src/cpi/` 中适配器发出的格式校验 discriminator、数据长度、
+//! 账户数量和账户顺序，然后按恒定乘积公式通过 SPL Token Transfer 在用户账户和
+//! vault 之间转账。CPMM swap_base_output、Raydium swap_base_out 和 CLMM is_base_input = false
+//! 按精确输出处理。
+//!
+//! vault 的 owner 必须是 `[VAULT_AUTHORITY_SEED]` 推导出的 PDA，该 PDA 放在各 DEX
+//! 本来就有的 pool / authority 账户位置上（CPMM authority、DLMM lb_pair、
+//! DAMMv2 pool_authority、Pump pool、Raydium authority、CLMM pool_state、
+//! Whirlpool whirlpool、Pump.fun bonding_curve、DAMM v1 pool、DBC pool_authority、
+//! LaunchLab authority）。Pump.fun 联合曲线的 SOL 储备就是
+//! bonding_curve 自身的 lamports，该账户由 mock 程序持有。
+
+use pinocchio::{
+    account_info::AccountInfo,
+    cpi::invoke_signed,
+    instruction::{AccountMeta, Instruction, Seed, Signer},
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    ProgramResult,
+};
+
+#[cfg(not(feature = "no-entrypoint"))]
+pinocchio::entrypoint!(process_instruction);
+
+/// vault owner PDA 的种子
+pub const VAULT_AUTHORITY_SEED: &[u8] = b"vault";
+
+/// mock program id 的公共前缀，最后一个字节为池类型
+pub const MOCK_PROGRAM_ID_PREFIX: u8 = 0xd3;
+
+/// 用户 / vault token 账户的 mint 与指令中传入的 mint 不一致（通常意味着 vault 下标错了）
+pub const ERROR_MINT_MISMATCH: u32 = 100;
+/// vault owner PDA 不在预期位置
+pub const ERROR_VAULT_AUTHORITY_MISMATCH: u32 = 101;
+/// 输出低于 minimum_amount_out，或精确输出需要的输入超过最大输入
+pub const ERROR_SLIPPAGE: u32 = 102;
+/// Pump.fun 首次买入时由 user 支付的 user_volume_accumulator 租金
+pub const PUMP_FUN_USER_VOLUME_ACCUMULATOR_RENT: u64 = 1_000_000;
+
+pub const CPMM_DISCRIMINATOR: [u8; 8] = [143, 190, 90, 218, 196, 30, 51, 222];
+pub const CPMM_SWAP_BASE_OUTPUT_DISCRIMINATOR: [u8; 8] = [55, 217, 98, 86, 163, 74, 180, 173];
+pub const DLMM_DISCRIMINATOR: [u8; 8] = [65, 75, 63, 76, 235, 91, 91, 136];
+pub const DAMMV1_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+pub const DAMMV2_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+pub const DBC_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+pub const PUMP_BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const PUMP_SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+pub const PUMP_BUY_EXACT_QUOTE_IN_DISCRIMINATOR: [u8; 8] = [198, 46, 21, 82, 180, 217, 232, 112];
+pub const RAYDIUM_DISCRIMINATOR: u8 = 9;
+pub const RAYDIUM_SWAP_BASE_OUT_DISCRIMINATOR: u8 = 11;
+pub const PUMP_FUN_BUY_EXACT_SOL_IN_DISCRIMINATOR: [u8; 8] = [56, 252, 116, 8, 158, 223, 205, 95];
+pub const LAUNCHLAB_BUY_EXACT_IN_DISCRIMINATOR: [u8; 8] = [250, 234, 13, 123, 213, 156, 19, 236];
+pub const LAUNCHLAB_SELL_EXACT_IN_DISCRIMINATOR: [u8; 8] = [149, 39, 222, 155, 211, 124, 152, 26];
+pub const SWAP_V2_DISCRIMINATOR: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+
+/// 池类型对应的 mock program id
+pub const fn mock_program_id(pool_type: u8) -> Pubkey {
+    let mut id = [MOCK_PROGRAM_ID_PREFIX; 32];
+    id[31] = pool_type;
+    id
+}
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    match program_id[31] {
+        0 => process_cpmm(program_id, accounts, instruction_data),
+        1 => process_dlmm(program_id, accounts, instruction_data),
+        2 => process_dammv2(program_id, accounts, instruction_data),
+        3 => process_pump(program_id, accounts, instruction_data),
+        4 => process_raydium(program_id, accounts, instruction_data, false),
+        5 => process_clmm(program_id, accounts, instruction_data),
+        6 => process_whirlpool(program_id, accounts, instruction_data),
+        7 => process_raydium(program_id, accounts, instruction_data, true),
+        8 => process_pump_fun(program_id, accounts, instruction_data),
+        9 => process_dammv1(program_id, accounts, instruction_data),
+        10 => process_dbc(program_id, accounts, instruction_data),
+        11 => process_launchlab(program_id, accounts, instruction_data),
+        _ => Err(ProgramError::IncorrectProgramId),
+    }
+}
+
+/// 一次兑换涉及的账户
+struct SwapAccounts<'a> {
+    user: &'a AccountInfo,
+    vault_authority: &'a AccountInfo,
+    user_in: &'a AccountInfo,
+    user_out: &'a AccountInfo,
+    vault_in: &'a AccountInfo,
+    vault_out: &'a AccountInfo,
+}
+
+// CPMM swap_base_input / swap_base_output: 13 个账户
+fn process_cpmm(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let exact_out = data.get(..8) == Some(&CPMM_SWAP_BASE_OUTPUT_DISCRIMINATOR[..]);
+    let discriminator = if exact_out { &CPMM_SWAP_BASE_OUTPUT_DISCRIMINATOR } else { &CPMM_DISCRIMINATOR };
+    check_layout(accounts, 13, data, 24, discriminator)?;
+    check_mint(&accounts[4], &accounts[10])?; // input_token_account / input_token_mint
+    check_mint(&accounts[6], &accounts[10])?; // input_vault
+    check_mint(&accounts[5], &accounts[11])?; // output_token_account / output_token_mint
+    check_mint(&accounts[7], &accounts[11])?; // output_vault
+
+    let swap_accounts = SwapAccounts {
+        user: &accounts[0],
+        vault_authority: &accounts[1],
+        user_in: &accounts[4],
+        user_out: &accounts[5],
+        vault_in: &accounts[6],
+        vault_out: &accounts[7],
+    };
+    if exact_out {
+        // max_amount_in, amount_out
+        swap_exact_out(program_id, swap_accounts, read_u64(data, 16), read_u64(data, 8))
+    } else {
+        swap_exact_in(program_id, swap_accounts, read_u64(data, 8), read_u64(data, 16))
+    }
+}
+
+// DLMM swap: 19 个账户，方向由用户输入账户的 mint 决定
+fn process_dlmm(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    check_layout(accounts, 19, data, 28, &DLMM_DISCRIMINATOR)?;
+    check_mint(&accounts[2], &accounts[6])?; // reserve_x / token_x_mint
+    check_mint(&accounts[3], &accounts[7])?; // reserve_y / token_y_mint
+
+    let x_to_y = token_mint(&accounts[4])? == *accounts[6].key();
+    let (vault_in, vault_out) = if x_to_y {
+        (&accounts[2], &accounts[3])
+    } else {
+        (&accounts[3], &accounts[2])
+    };
+
+    swap_exact_in(
+        program_id,
+        SwapAccounts {
+            user: &accounts[10],
+            vault_authority: &accounts[0],
+            user_in: &accounts[4],
+            user_out: &accounts[5],
+            vault_in,
+            vault_out,
+        },
+        read_u64(data, 8),
+        read_u64(data, 16),
+    )
+}
+
+// DAMMv2 swap: 14 个账户，方向由用户输入账户的 mint 决定
+fn process_dammv2(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    check_layout(accounts, 14, data, 24, &DAMMV2_DISCRIMINATOR)?;
+    check_mint(&accounts[4], &accounts[6])?; // token_a_vault / token_a_mint
+    check_mint(&accounts[5], &accounts[7])?; // token_b_vault / token_b_mint
+
+    let a_to_b = token_mint(&accounts[2])? == *accounts[6].key();
+    let (vault_in, vault_out) = if a_to_b {
+        (&accounts[4], &accounts[5])
+    } else {
+        (&accounts[5], &accounts[4])
+    };
+
+    swap_exact_in(
+        program_id,
+        SwapAccounts {
+            user: &accounts[8],
+            vault_authority: &accounts[0],
+            user_in: &accounts[2],
+            user_out: &accounts[3],
+            vault_in,
+            vault_out,
+        },
+        read_u64(data, 8),
+        read_u64(data, 16),
+    )
+}
+
+// Pump AMM buy (exact base out) / buy_exact_quote_in: 23 个账户；sell (exact base in): 21 个账户
+// （sell 不带 global_volume_accumulator / user_volume_accumulator）
+fn process_pump(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.len() != 24 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let expected_accounts = if data[..8] == PUMP_SELL_DISCRIMINATOR { 21 } else { 23 };
+    if accounts.len() != expected_accounts {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    check_mint(&accounts[5], &accounts[3])?; // user_base_token_account / base_mint
+    check_mint(&accounts[7], &accounts[3])?; // pool_base_token_account
+    check_mint(&accounts[6], &accounts[4])?; // user_quote_token_account / quote_mint
+    check_mint(&accounts[8], &accounts[4])?; // pool_quote_token_account
+
+    let user = &accounts[1];
+    let vault_authority = &accounts[0];
+    let (user_base, user_quote) = (&accounts[5], &accounts[6]);
+    let (base_vault, quote_vault) = (&accounts[7], &accounts[8]);
+
+    if data[..8] == PUMP_BUY_DISCRIMINATOR {
+        // base_amount_out, max_quote_amount_in
+        swap_exact_out(
+            program_id,
+            SwapAccounts {
+                user,
+                vault_authority,
+                user_in: user_quote,
+                user_out: user_base,
+                vault_in: quote_vault,
+                vault_out: base_vault,
+            },
+            read_u64(data, 8),
+            read_u64(data, 16),
+        )
+    } else if data[..8] == PUMP_BUY_EXACT_QUOTE_IN_DISCRIMINATOR {
+        swap_exact_in(
+            program_id,
+            SwapAccounts {
+                user,
+                vault_authority,
+                user_in: user_quote,
+                user_out: user_base,
+                vault_in: quote_vault,
+                vault_out: base_vault,
+            },
+            read_u64(data, 8),
+            read_u64(data, 16),
+        )
+    } else if data[..8] == PUMP_SELL_DISCRIMINATOR {
+        swap_exact_in(
+            program_id,
+            SwapAccounts {
+                user,
+                vault_authority,
+                user_in: user_base,
+                user_out: user_quote,
+                vault_in: base_vault,
+                vault_out: quote_vault,
+            },
+            read_u64(data, 8),
+            read_u64(data, 16),
+        )
+    } else {
+        Err(ProgramError::InvalidInstructionData)
+    }
+}
+
+// Raydium AMM v4 swap_base_in / swap_base_out: 17 个账户，方向由用户源账户的 mint 决定。
+// `openbook` 时要求传入真实的 open orders，而不是用 AMM id 占位
+fn process_raydium(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8], openbook: bool) -> ProgramResult {
+    if accounts.len() != 17 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() != 17 || (data[0] != RAYDIUM_DISCRIMINATOR && data[0] != RAYDIUM_SWAP_BASE_OUT_DISCRIMINATOR) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if openbook && accounts[3].key() == accounts[1].key() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let (pool_coin, pool_pc) = (&accounts[4], &accounts[5]);
+    let source_mint = token_mint(&accounts[14])?;
+    let (vault_in, vault_out) = if source_mint == token_mint(pool_coin)? {
+        (pool_coin, pool_pc)
+    } else if source_mint == token_mint(pool_pc)? {
+        (pool_pc, pool_coin)
+    } else {
+        return Err(ProgramError::Custom(ERROR_MINT_MISMATCH));
+    };
+    if token_mint(&accounts[15])? != token_mint(vault_out)? {
+        return Err(ProgramError::Custom(ERROR_MINT_MISMATCH));
+    }
+
+    let swap_accounts = SwapAccounts {
+        user: &accounts[16],
+        vault_authority: &accounts[2],
+        user_in: &accounts[14],
+        user_out: &accounts[15],
+        vault_in,
+        vault_out,
+    };
+    if data[0] == RAYDIUM_SWAP_BASE_OUT_DISCRIMINATOR {
+        swap_exact_out(program_id, swap_accounts, read_u64(data, 9), read_u64(data, 1))
+    } else {
+        swap_exact_in(program_id, swap_accounts, read_u64(data, 1), read_u64(data, 9))
+    }
+}
+
+// Pump.fun buy / buy_exact_sol_in: 16 个账户，sell: 14 个账户。SOL 在用户和 bonding_curve 的
+// lamports 之间转移，token 在用户和 associated_bonding_curve 之间转移。
+// 买入时 user_volume_accumulator 还没有 lamports 则由用户支付租金，模拟 init_if_needed
+fn process_pump_fun(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let is_sell = data.get(..8) == Some(&PUMP_SELL_DISCRIMINATOR[..]);
+    if accounts.len() != if is_sell { 14 } else { 16 } {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() != 24 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mint = &accounts[2];
+    let bonding_curve = &accounts[3];
+    let curve_vault = &accounts[4];
+    let user_token = &accounts[5];
+    let user = &accounts[6];
+    check_user(user)?;
+    let bump = check_vault_authority(program_id, bonding_curve)?;
+    check_mint(curve_vault, mint)?;
+    check_mint(user_token, mint)?;
+
+    let reserve_sol = bonding_curve.lamports() as u128;
+    let reserve_token = token_amount(curve_vault)? as u128;
+
+    if is_sell {
+        let amount_in = read_u64(data, 8);
+        let sol_out = (amount_in as u128 * reserve_sol / (reserve_token + amount_in as u128)) as u64;
+        if sol_out < read_u64(data, 16) {
+            return Err(ProgramError::Custom(ERROR_SLIPPAGE));
+        }
+
+        transfer(user_token, curve_vault, user, amount_in, None)?;
+        *bonding_curve.try_borrow_mut_lamports()? -= sol_out;
+        *user.try_borrow_mut_lamports()? += sol_out;
+        return Ok(());
+    }
+
+    let (sol_in, token_out) = if data[..8] == PUMP_BUY_DISCRIMINATOR {
+        let token_out = read_u64(data, 8);
+        if token_out as u128 >= reserve_token {
+            return Err(ProgramError::Custom(ERROR_SLIPPAGE));
+        }
+        let sol_in = (token_out as u128 * reserve_sol).div_ceil(reserve_token - token_out as u128) as u64;
+        if sol_in > read_u64(data, 16) {
+            return Err(ProgramError::Custom(ERROR_SLIPPAGE));
+        }
+        (sol_in, token_out)
+    } else if data[..8] == PUMP_FUN_BUY_EXACT_SOL_IN_DISCRIMINATOR {
+        let sol_in = read_u64(data, 8);
+        let token_out = (sol_in as u128 * reserve_token / (reserve_sol + sol_in as u128)) as u64;
+        if token_out < read_u64(data, 16) {
+            return Err(ProgramError::Custom(ERROR_SLIPPAGE));
+        }
+        (sol_in, token_out)
+    } else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+
+    let user_volume_accumulator = &accounts[13];
+    if user_volume_accumulator.lamports() == 0 {
+        system_transfer(user, user_volume_accumulator, PUMP_FUN_USER_VOLUME_ACCUMULATOR_RENT)?;
+    }
+    system_transfer(user, bonding_curve, sol_in)?;
+    transfer(curve_vault, user_token, bonding_curve, token_out, Some(bump))
+}
+
+// DAMM v1 swap: 15 个账户，方向由用户源账户的 mint 决定，protocol_token_fee 必须是输入 token 的手续费账户
+fn process_dammv1(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    check_layout(accounts, 15, data, 24, &DAMMV1_DISCRIMINATOR)?;
+
+    let (a_token_vault, b_token_vault) = (&accounts[5], &accounts[6]);
+    let source_mint = token_mint(&accounts[1])?;
+    let (vault_in, vault_out) = if source_mint == token_mint(a_token_vault)? {
+        (a_token_vault, b_token_vault)
+    } else {
+        (b_token_vault, a_token_vault)
+    };
+    if token_mint(&accounts[11])? != source_mint {
+        return Err(ProgramError::Custom(ERROR_MINT_MISMATCH));
+    }
+
+    swap_exact_in(
+        program_id,
+        SwapAccounts {
+            user: &accounts[12],
+            vault_authority: &accounts[0],
+            user_in: &accounts[1],
+            user_out: &accounts[2],
+            vault_in,
+            vault_out,
+        },
+        read_u64(data, 8),
+        read_u64(data, 16),
+    )
+}
+
+// DBC swap: 15 个账户，方向由用户输入账户的 mint 决定
+fn process_dbc(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    check_layout(accounts, 15, data, 24, &DBC_DISCRIMINATOR)?;
+
+    let (base_vault, quote_vault) = (&accounts[5], &accounts[6]);
+    check_mint(base_vault, &accounts[7])?;
+    check_mint(quote_vault, &accounts[8])?;
+    let (vault_in, vault_out) = if token_mint(&accounts[3])? == *accounts[7].key() {
+        (base_vault, quote_vault)
+    } else {
+        (quote_vault, base_vault)
+    };
+
+    swap_exact_in(
+        program_id,
+        SwapAccounts {
+            user: &accounts[9],
+            vault_authority: &accounts[0],
+            user_in: &accounts[3],
+            user_out: &accounts[4],
+            vault_in,
+            vault_out,
+        },
+        read_u64(data, 8),
+        read_u64(data, 16),
+    )
+}
+
+// LaunchLab buy_exact_in (quote -> base) / sell_exact_in (base -> quote): 15 个账户
+fn process_launchlab(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let is_sell = data.get(..8) == Some(&LAUNCHLAB_SELL_EXACT_IN_DISCRIMINATOR[..]);
+    let discriminator = if is_sell { &LAUNCHLAB_SELL_EXACT_IN_DISCRIMINATOR } else { &LAUNCHLAB_BUY_EXACT_IN_DISCRIMINATOR };
+    check_layout(accounts, 15, data, 32, discriminator)?;
+
+    let (user_base, user_quote) = (&accounts[5], &accounts[6]);
+    let (base_vault, quote_vault) = (&accounts[7], &accounts[8]);
+    check_mint(user_base, &accounts[9])?;
+    check_mint(user_quote, &accounts[10])?;
+
+    let (user_in, user_out, vault_in, vault_out) = if is_sell {
+        (user_base, user_quote, base_vault, quote_vault)
+    } else {
+        (user_quote, user_base, quote_vault, base_vault)
+    };
+
+    swap_exact_in(
+        program_id,
+        SwapAccounts {
+            user: &accounts[0],
+            vault_authority: &accounts[1],
+            user_in,
+            user_out,
+            vault_in,
+            vault_out,
+        },
+        read_u64(data, 8),
+        read_u64(data, 16),
+    )
+}
+
+// CLMM swap_v2: 17 个账户，is_base_input = false 时 amount 为精确输出、other_amount_threshold 为最大输入
+fn process_clmm(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    check_layout(accounts, 17, data, 41, &SWAP_V2_DISCRIMINATOR)?;
+    if data[40] > 1 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    check_mint(&accounts[3], &accounts[11])?; // input_token_account / input_vault_mint
+    check_mint(&accounts[5], &accounts[11])?; // input_vault
+    check_mint(&accounts[4], &accounts[12])?; // output_token_account / output_vault_mint
+    check_mint(&accounts[6], &accounts[12])?; // output_vault
+
+    let swap_accounts = SwapAccounts {
+        user: &accounts[0],
+        vault_authority: &accounts[2],
+        user_in: &accounts[3],
+        user_out: &accounts[4],
+        vault_in: &accounts[5],
+        vault_out: &accounts[6],
+    };
+    if data[40] == 1 {
+        swap_exact_in(program_id, swap_accounts, read_u64(data, 8), read_u64(data, 16))
+    } else {
+        swap_exact_out(program_id, swap_accounts, read_u64(data, 8), read_u64(data, 16))
+    }
+}
+
+// Whirlpool swapV2: 15 个账户，方向由 aToB 决定，只支持 amountSpecifiedIsInput = true
+fn process_whirlpool(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    check_layout(accounts, 15, data, 43, &SWAP_V2_DISCRIMINATOR)?;
+    if data[40] != 1 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    check_mint(&accounts[7], &accounts[5])?; // tokenOwnerAccountA / tokenMintA
+    check_mint(&accounts[8], &accounts[5])?; // tokenVaultA
+    check_mint(&accounts[9], &accounts[6])?; // tokenOwnerAccountB / tokenMintB
+    check_mint(&accounts[10], &accounts[6])?; // tokenVaultB
+
+    let (owner_a, vault_a, owner_b, vault_b) = (&accounts[7], &accounts[8], &accounts[9], &accounts[10]);
+    let (user_in, user_out, vault_in, vault_out) = if data[41] == 1 {
+        (owner_a, owner_b, vault_a, vault_b)
+    } else {
+        (owner_b, owner_a, vault_b, vault_a)
+    };
+
+    swap_exact_in(
+        program_id,
+        SwapAccounts {
+            user: &accounts[3],
+            vault_authority: &accounts[4],
+            user_in,
+            user_out,
+            vault_in,
+            vault_out,
+        },
+        read_u64(data, 8),
+        read_u64(data, 16),
+    )
+}
+
+/// 按恒定乘积公式兑换：`out = in * reserve_out / (reserve_in + in)`
+fn swap_exact_in(
+    program_id: &Pubkey,
+    accounts: SwapAccounts,
+    amount_in: u64,
+    min_out: u64,
+) -> ProgramResult {
+    check_user(accounts.user)?;
+    let bump = check_vault_authority(program_id, accounts.vault_authority)?;
+    if token_mint(accounts.user_in)? != token_mint(accounts.vault_in)?
+        || token_mint(accounts.user_out)? != token_mint(accounts.vault_out)?
+    {
+        return Err(ProgramError::Custom(ERROR_MINT_MISMATCH));
+    }
+
+    let reserve_in = token_amount(accounts.vault_in)? as u128;
+    let reserve_out = token_amount(accounts.vault_out)? as u128;
+    let amount_out = (amount_in as u128 * reserve_out / (reserve_in + amount_in as u128)) as u64;
+    if amount_out < min_out {
+        return Err(ProgramError::Custom(ERROR_SLIPPAGE));
+    }
+
+    transfer(accounts.user_in, accounts.vault_in, accounts.user, amount_in, None)?;
+    transfer(
+        accounts.vault_out,
+        accounts.user_out,
+        accounts.vault_authority,
+        amount_out,
+        Some(bump),
+    )
+}
+
+/// 按恒定乘积公式买到精确的 `amount_out`：`in = ceil(out * reserve_in / (reserve_out - out))`
+fn swap_exact_out(
+    program_id: &Pubkey,
+    accounts: SwapAccounts,
+    amount_out: u64,
+    max_in: u64,
+) -> ProgramResult {
+    check_user(accounts.user)?;
+    let bump = check_vault_authority(program_id, accounts.vault_authority)?;
+    if token_mint(accounts.user_in)? != token_mint(accounts.vault_in)?
+        || token_mint(accounts.user_out)? != token_mint(accounts.vault_out)?
+    {
+        return Err(ProgramError::Custom(ERROR_MINT_MISMATCH));
+    }
+
+    let reserve_in = token_amount(accounts.vault_in)? as u128;
+    let reserve_out = token_amount(accounts.vault_out)? as u128;
+    if amount_out as u128 >= reserve_out {
+        return Err(ProgramError::Custom(ERROR_SLIPPAGE));
+    }
+    let amount_in = (amount_out as u128 * reserve_in).div_ceil(reserve_out - amount_out as u128) as u64;
+    if amount_in > max_in {
+        return Err(ProgramError::Custom(ERROR_SLIPPAGE));
+    }
+
+    transfer(accounts.user_in, accounts.vault_in, accounts.user, amount_in, None)?;
+    transfer(
+        accounts.vault_out,
+        accounts.user_out,
+        accounts.vault_authority,
+        amount_out,
+        Some(bump),
+    )
+}
+
+/// SPL Token Transfer (指令 3)，token program 取自源账户的 owner
+fn transfer(
+    from: &AccountInfo,
+    to: &AccountInfo,
+    authority: &AccountInfo,
+    amount: u64,
+    vault_bump: Option<u8>,
+) -> ProgramResult {
+    let mut data = [3u8; 9];
+    data[1..].copy_from_slice(&amount.to_le_bytes());
+
+    let account_metas = [
+        AccountMeta::writable(from.key()),
+        AccountMeta::writable(to.key()),
+        AccountMeta::readonly_signer(authority.key()),
+    ];
+    let instruction = Instruction {
+        program_id: from.owner(),
+        accounts: &account_metas,
+        data: &data,
+    };
+
+    match vault_bump {
+        Some(bump) => {
+            let bump = [bump];
+            let seeds = [Seed::from(VAULT_AUTHORITY_SEED), Seed::from(&bump)];
+            invoke_signed::<3>(&instruction, &[from, to, authority], &[Signer::from(&seeds)])
+        }
+        None => invoke_signed::<3>(&instruction, &[from, to, authority], &[]),
+    }
+}
+
+/// System Program Transfer (指令 2)
+fn system_transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let mut data = [0u8; 12];
+    data[0] = 2;
+    data[4..].copy_from_slice(&lamports.to_le_bytes());
+
+    let account_metas = [AccountMeta::writable_signer(from.key()), AccountMeta::writable(to.key())];
+    let instruction = Instruction {
+        program_id: &[0; 32],
+        accounts: &account_metas,
+        data: &data,
+    };
+
+    invoke_signed::<2>(&instruction, &[from, to], &[])
+}
+
+fn check_layout(
+    accounts: &[AccountInfo],
+    account_count: usize,
+    data: &[u8],
+    data_len: usize,
+    discriminator: &[u8; 8],
+) -> ProgramResult {
+    if accounts.len() != account_count {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() != data_len || data[..8] != *discriminator {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+fn check_user(user: &AccountInfo) -> ProgramResult {
+    if !user.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+fn check_vault_authority(program_id: &Pubkey, vault_authority: &AccountInfo) -> Result<u8, ProgramError> {
+    let (expected, bump) = find_program_address(&[VAULT_AUTHORITY_SEED], program_id);
+    if *vault_authority.key() != expected {
+        return Err(ProgramError::Custom(ERROR_VAULT_AUTHORITY_MISMATCH));
+    }
+    Ok(bump)
+}
+
+fn check_mint(token_account: &AccountInfo, mint: &AccountInfo) -> ProgramResult {
+    if token_mint(token_account)? != *mint.key() {
+        return Err(ProgramError::Custom(ERROR_MINT_MISMATCH));
+    }
+    Ok(())
+}
+
+fn token_mint(token_account: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    let data = token_account.try_borrow_data()?;
+    if data.len() < 72 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(data[..32].try_into().unwrap())
+}
+
+fn token_amount(token_account: &AccountInfo) -> Result<u64, ProgramError> {
+    let data = token_account.try_borrow_data()?;
+    if data.len() < 72 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(read_u64(&data, 64))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
@@ -0,0 +1,227 @@
+//! 集成测试用的闪电贷 mock 程序，指令格式与客户端 `FlashLoanKeys` 生成的一致
+//! （Solend 的 flash_borrow / flash_repay）。
+//!
+//! 与 Solend 一样只接受交易顶层的借还款：通过 CPI 借款返回 `ERROR_CPI_BORROW`，
+//! 还款指令携带的 borrow_instruction_index 必须在 instructions sysvar 中指向同一程序、
+//! 同一金额的借款指令。
+//!
+//! 程序部署在 Solend 的 program id 上。reserve 账户由本程序拥有，长度与 Solend 的
+//! `Reserve` 相同：手续费率读取 Solend 布局中的 flash_loan_fee_wad / host_fee_percentage，
+//! 未还本金记录在 liquidity_borrowed_amount_wads 的位置。reserve_liquidity_supply 的
+//! owner 是 `[LENDING_AUTHORITY_SEED]` 推导出的 PDA，该 PDA 放在
+//! lending_market_authority 的位置上。还款时按 Solend 的规则额外收取手续费，
+//! 全部转入 reserve_liquidity_fee_receiver（不拆分 host fee）。
+
+use pinocchio::{
+    account_info::AccountInfo,
+    cpi::invoke_signed,
+    instruction::{AccountMeta, Instruction, Seed, Signer},
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::instructions::Instructions,
+    ProgramResult,
+};
+
+#[cfg(not(feature = "no-entrypoint"))]
+pinocchio::entrypoint!(process_instruction);
+
+/// Solend Program ID (So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo)
+pub const LENDING_PROGRAM_ID: Pubkey = [
+    6, 155, 139, 152, 90, 171, 83, 42, 69, 9, 13, 232, 85, 127, 205, 220,
+    190, 108, 183, 239, 199, 58, 10, 101, 176, 111, 146, 3, 93, 183, 62, 236,
+];
+
+/// reserve supply owner PDA 的种子
+pub const LENDING_AUTHORITY_SEED: &[u8] = b"lending";
+
+/// Solend `Reserve` 的打包长度
+pub const RESERVE_LEN: usize = 619;
+/// 未还本金（Solend 中 liquidity_borrowed_amount_wads 的位置）
+const RESERVE_OUTSTANDING_OFFSET: usize = 179;
+/// config.fees.flash_loan_fee_wad (u64) 和 host_fee_percentage (u8)
+pub const RESERVE_FLASH_LOAN_FEE_WAD_OFFSET: usize = 314;
+pub const RESERVE_HOST_FEE_PERCENTAGE_OFFSET: usize = 322;
+
+/// 测试使用的闪电贷费率：30 bps
+pub const FLASH_LOAN_FEE_WAD: u64 = 3_000_000_000_000_000;
+const WAD: u128 = 1_000_000_000_000_000_000;
+
+pub const FLASH_BORROW_TAG: u8 = 19;
+pub const FLASH_REPAY_TAG: u8 = 20;
+
+/// reserve 中已有未还的借款
+pub const ERROR_LOAN_OUTSTANDING: u32 = 200;
+/// 还款金额与借款金额不一致
+pub const ERROR_REPAY_MISMATCH: u32 = 201;
+/// lending_market_authority 不是预期的 PDA
+pub const ERROR_AUTHORITY_MISMATCH: u32 = 202;
+/// 借款不是交易顶层指令
+pub const ERROR_CPI_BORROW: u32 = 203;
+/// borrow_instruction_index 没有指向对应的借款指令
+pub const ERROR_BORROW_INDEX_MISMATCH: u32 = 204;
+
+/// 费率为 `FLASH_LOAN_FEE_WAD` 时 `amount` 的闪电贷手续费
+pub fn flash_loan_fee(amount: u64) -> u64 {
+    fee(amount, FLASH_LOAN_FEE_WAD, 0)
+}
+
+/// 费率为 `fee_wad` 的 reserve 数据，未还本金为0
+pub fn reserve_data(fee_wad: u64) -> Vec<u8> {
+    let mut data = vec![0; RESERVE_LEN];
+    data[RESERVE_FLASH_LOAN_FEE_WAD_OFFSET..RESERVE_FLASH_LOAN_FEE_WAD_OFFSET + 8].copy_from_slice(&fee_wad.to_le_bytes());
+    data
+}
+
+/// Solend 的手续费规则：amount * fee_wad 四舍五入，费率非0时至少1（有 host fee 时至少2）
+fn fee(amount: u64, fee_wad: u64, host_fee_percentage: u8) -> u64 {
+    if amount == 0 || fee_wad == 0 {
+        return 0;
+    }
+    let fee = ((amount as u128 * fee_wad as u128 + WAD / 2) / WAD) as u64;
+    fee.max(if host_fee_percentage > 0 { 2 } else { 1 })
+}
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    match instruction_data.first() {
+        Some(&FLASH_BORROW_TAG) => process_borrow(program_id, accounts, instruction_data),
+        Some(&FLASH_REPAY_TAG) => process_repay(program_id, accounts, instruction_data),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// flash_borrow: 7 个账户
+// [0] source_liquidity, [1] destination_liquidity, [2] reserve, [3] lending_market,
+// [4] lending_market_authority, [5] instructions_sysvar, [6] token_program
+fn process_borrow(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() != 7 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() != 9 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = read_u64(data, 1);
+
+    if stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Err(ProgramError::Custom(ERROR_CPI_BORROW));
+    }
+
+    let (authority, bump) = find_program_address(&[LENDING_AUTHORITY_SEED], program_id);
+    if *accounts[4].key() != authority {
+        return Err(ProgramError::Custom(ERROR_AUTHORITY_MISMATCH));
+    }
+
+    let reserve = &accounts[2];
+    if read_u64(&reserve.try_borrow_data()?, RESERVE_OUTSTANDING_OFFSET) != 0 {
+        return Err(ProgramError::Custom(ERROR_LOAN_OUTSTANDING));
+    }
+    write_outstanding(reserve, amount)?;
+
+    transfer(&accounts[0], &accounts[1], &accounts[4], &accounts[6], amount, Some(bump))
+}
+
+// flash_repay: 9 个账户
+// [0] source_liquidity, [1] destination_liquidity, [2] fee_receiver, [3] host_fee_receiver,
+// [4] reserve, [5] lending_market, [6] user_transfer_authority, [7] instructions_sysvar,
+// [8] token_program
+fn process_repay(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() != 9 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if data.len() != 10 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = read_u64(data, 1);
+
+    let instructions = Instructions::try_from(&accounts[7])?;
+    let borrow = instructions.load_instruction_at(data[9] as usize)?;
+    let borrow_data = borrow.get_instruction_data();
+    if borrow.get_program_id() != program_id
+        || borrow_data.len() != 9
+        || borrow_data[0] != FLASH_BORROW_TAG
+        || read_u64(borrow_data, 1) != amount
+    {
+        return Err(ProgramError::Custom(ERROR_BORROW_INDEX_MISMATCH));
+    }
+
+    let user = &accounts[6];
+    if !user.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let reserve = &accounts[4];
+    let fee = {
+        let data = reserve.try_borrow_data()?;
+        if read_u64(&data, RESERVE_OUTSTANDING_OFFSET) != amount {
+            return Err(ProgramError::Custom(ERROR_REPAY_MISMATCH));
+        }
+        fee(
+            amount,
+            read_u64(&data, RESERVE_FLASH_LOAN_FEE_WAD_OFFSET),
+            data[RESERVE_HOST_FEE_PERCENTAGE_OFFSET],
+        )
+    };
+    write_outstanding(reserve, 0)?;
+
+    transfer(&accounts[0], &accounts[1], user, &accounts[8], amount, None)?;
+    transfer(&accounts[0], &accounts[2], user, &accounts[8], fee, None)
+}
+
+fn write_outstanding(reserve: &AccountInfo, amount: u64) -> ProgramResult {
+    reserve.try_borrow_mut_data()?[RESERVE_OUTSTANDING_OFFSET..RESERVE_OUTSTANDING_OFFSET + 8]
+        .copy_from_slice(&amount.to_le_bytes());
+    Ok(())
+}
+
+/// SPL Token Transfer (指令 3)
+fn transfer(
+    from: &AccountInfo,
+    to: &AccountInfo,
+    authority: &AccountInfo,
+    token_program: &AccountInfo,
+    amount: u64,
+    authority_bump: Option<u8>,
+) -> ProgramResult {
+    let mut data = [3u8; 9];
+    data[1..].copy_from_slice(&amount.to_le_bytes());
+
+    let account_metas = [
+        AccountMeta::writable(from.key()),
+        AccountMeta::writable(to.key()),
+        AccountMeta::readonly_signer(authority.key()),
+    ];
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: &account_metas,
+        data: &data,
+    };
+
+    match authority_bump {
+        Some(bump) => {
+            let bump = [bump];
+            let seeds = [Seed::from(LENDING_AUTHORITY_SEED), Seed::from(&bump)];
+            invoke_signed::<3>(&instruction, &[from, to, authority], &[Signer::from(&seeds)])
+        }
+        None => invoke_signed::<3>(&instruction, &[from, to, authority], &[]),
+    }
+}
+
+/// 交易顶层指令的调用栈高度
+const TRANSACTION_LEVEL_STACK_HEIGHT: u64 = 1;
+
+#[cfg(target_os = "solana")]
+fn stack_height() -> u64 {
+    unsafe { pinocchio::syscalls::sol_get_stack_height() }
+}
+
+#[cfg(not(target_os = "solana"))]
+fn stack_height() -> u64 {
+    TRANSACTION_LEVEL_STACK_HEIGHT
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
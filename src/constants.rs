@@ -1,3 +1,32 @@
+use pinocchio::pubkey::Pubkey;
+
+// SPL Token Program ID (TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA)
+pub const TOKEN_PROGRAM_ID: Pubkey = [
+    6, 221, 246, 225, 215, 101, 161, 147, 217, 203, 225, 70, 206, 235, 121, 172,
+    28, 180, 133, 237, 95, 91, 55, 145, 58, 140, 245, 133, 126, 255, 0, 169
+];
+
+// Token-2022 Program ID (TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb)
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = [
+    6, 221, 246, 225, 238, 117, 143, 222, 24, 66, 93, 188, 228, 108, 205, 218,
+    182, 26, 252, 77, 131, 185, 13, 39, 254, 189, 249, 40, 216, 161, 139, 252
+];
+
+// System Program ID (11111111111111111111111111111111)
+pub const SYSTEM_PROGRAM_ID: Pubkey = [0; 32];
+
+// WSOL Mint (So11111111111111111111111111111111111111112)
+pub const WSOL_MINT: Pubkey = [
+    6, 155, 136, 87, 254, 171, 129, 132, 251, 104, 127, 99, 70, 24, 192, 53,
+    218, 196, 57, 220, 26, 235, 59, 85, 152, 160, 240, 0, 0, 0, 0, 1
+];
+
+// Solend Program ID (So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo)
+pub const SOLEND_PROGRAM_ID: Pubkey = [
+    6, 155, 139, 152, 90, 171, 83, 42, 69, 9, 13, 232, 85, 127, 205, 220,
+    190, 108, 183, 239, 199, 58, 10, 101, 176, 111, 146, 3, 93, 183, 62, 236
+];
+
 // /// 程序 ID 常量 - 使用字节数组进行快速匹配
 // /// 
 // /// 这些常量用于快速识别不同的 DEX 程序，避免字符串比较
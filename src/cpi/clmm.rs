@@ -3,13 +3,14 @@ use pinocchio::{
     ProgramResult,
 };
 use crate::error::PinocchioCpiError;
+use crate::utils::TokenSlot;
 
 const CLMM_INSTRUCTION_DATA: [u8; 41] = [
     // swap_v2 discriminator [0..8]
     43, 4, 237, 11, 26, 201, 30, 98,
     // amount placeholder [8..16] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
-    // other_amount_threshold = 0 [16..24]
+    // other_amount_threshold placeholder [16..24] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
     // sqrt_price_limit = 0 [24..40]
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -17,12 +18,33 @@ const CLMM_INSTRUCTION_DATA: [u8; 41] = [
     1,
 ];
 
+/// 🚀 构建 swap_v2 指令数据
+///
+/// `exact_out` 时 is_base_input = false：amount 为精确输出 `min_out`，
+/// other_amount_threshold 为最大输入 `trade_amount`
+#[inline(always)]
+fn clmm_instruction_data(trade_amount: u64, min_out: u64, exact_out: bool) -> [u8; 41] {
+    let (amount, other_amount_threshold) = if exact_out {
+        (min_out, trade_amount)
+    } else {
+        (trade_amount, min_out)
+    };
+
+    let mut instruction_data = CLMM_INSTRUCTION_DATA;
+    instruction_data[8..16].copy_from_slice(&amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&other_amount_threshold.to_le_bytes());
+    instruction_data[40] = !exact_out as u8;
+    instruction_data
+}
+
 pub fn execute_clmm_swap(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     clmm_accounts: &[AccountInfo],
     is_buy: bool,
-    is_wsol_token_0: bool, // WSOL是否为token_0
+    is_base_token_0: bool, // 基础资产是否为token_0
+    exact_out: bool,
 ) -> ProgramResult {
     let (
         input_token_account,
@@ -32,45 +54,45 @@ pub fn execute_clmm_swap(
         input_mint,
         output_mint,
     ) = if is_buy {
-        // 买入：WSOL -> Token
-        if is_wsol_token_0 {
+        // 买入：基础资产 -> Token
+        if is_base_token_0 {
             (
-                &header_accounts[2], // wsol_token_account
+                &header_accounts[2], // base_token_account
                 &header_accounts[8], // mint_token_account
                 5, // token_vault_0_index
                 6, // token_vault_1_index
-                &header_accounts[1], // wsol_mint
+                &header_accounts[1], // base_mint
                 &header_accounts[6], // token_mint
             )
         } else {
             (
-                &header_accounts[2], // wsol_token_account
+                &header_accounts[2], // base_token_account
                 &header_accounts[8], // mint_token_account
                 6, // token_vault_1_index
                 5, // token_vault_0_index
-                &header_accounts[1], // wsol_mint
+                &header_accounts[1], // base_mint
                 &header_accounts[6], // token_mint
             )
         }
     } else {
-        // 卖出：Token -> WSOL
-        if is_wsol_token_0 {
+        // 卖出：Token -> 基础资产
+        if is_base_token_0 {
             (
                 &header_accounts[8], // mint_token_account
-                &header_accounts[2], // wsol_token_account
+                &header_accounts[2], // base_token_account
                 6, // token_vault_1_index
                 5, // token_vault_0_index
                 &header_accounts[6], // token_mint
-                &header_accounts[1], // wsol_mint
+                &header_accounts[1], // base_mint
             )
         } else {
             (
                 &header_accounts[8], // mint_token_account
-                &header_accounts[2], // wsol_token_account
+                &header_accounts[2], // base_token_account
                 5, // token_vault_0_index
                 6, // token_vault_1_index
                 &header_accounts[6], // token_mint
-                &header_accounts[1], // wsol_mint
+                &header_accounts[1], // base_mint
             )
         }
     };
@@ -97,8 +119,7 @@ pub fn execute_clmm_swap(
     ];
 
     // 🚀 优化：预构建模板，只替换变量部分
-    let mut instruction_data = CLMM_INSTRUCTION_DATA;
-    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    let instruction_data = clmm_instruction_data(trade_amount, min_out, exact_out);
 
     let swap_instruction = Instruction {
         program_id: clmm_accounts[0].key(),
@@ -132,20 +153,22 @@ pub fn execute_clmm_swap(
 
 pub fn execute_clmm_swap_hop3(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     clmm_accounts: &[AccountInfo],
     step: u8,
-    is_wsol_token_0: bool,
+    is_base_token_0: bool,
+    exact_out: bool,
 ) -> ProgramResult {
     match step {
         1 => {
-            execute_clmm_swap(trade_amount, header_accounts, clmm_accounts, true, is_wsol_token_0)
+            execute_clmm_swap(trade_amount, min_out, header_accounts, clmm_accounts, true, is_base_token_0, exact_out)
         }
         2 => {
-            execute_clmm_swap_mid(trade_amount, header_accounts, clmm_accounts, is_wsol_token_0)
+            execute_clmm_swap_mid(trade_amount, min_out, header_accounts, clmm_accounts, is_base_token_0, exact_out)
         }
         3 => {
-            execute_clmm_swap_sell(trade_amount, header_accounts, clmm_accounts, is_wsol_token_0)
+            execute_clmm_swap_sell(trade_amount, min_out, header_accounts, clmm_accounts, is_base_token_0, exact_out)
         }
         _ => {
             Err(PinocchioCpiError::UnsupportedPoolType.into())
@@ -155,20 +178,23 @@ pub fn execute_clmm_swap_hop3(
 
 fn execute_clmm_swap_mid(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     clmm_accounts: &[AccountInfo],
     is_mid_zero_to_one: bool,
+    exact_out: bool,
 ) -> ProgramResult {
     // 中间交换：Token1 -> Token2
     // 输入：header_accounts[8] (token1_account)
     // 输出：header_accounts[11] (token2_account)
     
+    // 输入始终是Token1、输出始终是Token2，只有vault随方向变化
     let (input_vault_index, output_vault_index, input_mint, output_mint) = if is_mid_zero_to_one {
         // Token1是token0，Token2是token1
         (5, 6, &header_accounts[6], &header_accounts[9])
     } else {
         // Token1是token1，Token2是token0
-        (6, 5, &header_accounts[9], &header_accounts[6])
+        (6, 5, &header_accounts[6], &header_accounts[9])
     };
 
     let account_metas = [
@@ -191,8 +217,7 @@ fn execute_clmm_swap_mid(
         AccountMeta::new(clmm_accounts[9].key(), true, false),   // tick_array_1 (writable)
     ];
 
-    let mut instruction_data = CLMM_INSTRUCTION_DATA;
-    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    let instruction_data = clmm_instruction_data(trade_amount, min_out, exact_out);
 
     let swap_instruction = Instruction {
         program_id: clmm_accounts[0].key(),
@@ -225,19 +250,21 @@ fn execute_clmm_swap_mid(
 
 fn execute_clmm_swap_sell(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     clmm_accounts: &[AccountInfo],
-    is_wsol_token_0: bool,
+    is_base_token_0: bool,
+    exact_out: bool,
 ) -> ProgramResult {
-    // 卖出交换：Token2 -> WSOL
+    // 卖出交换：Token2 -> 基础资产
     // 输入：header_accounts[11] (token2_account)
-    // 输出：header_accounts[2] (wsol_account)
+    // 输出：header_accounts[2] (base_account)
     
-    let (input_vault_index, output_vault_index, input_mint, output_mint) = if is_wsol_token_0 {
-        // WSOL是token0，Token2是token1
+    let (input_vault_index, output_vault_index, input_mint, output_mint) = if is_base_token_0 {
+        // 基础资产是token0，Token2是token1
         (6, 5, &header_accounts[9], &header_accounts[1])
     } else {
-        // WSOL是token1，Token2是token0
+        // 基础资产是token1，Token2是token0
         (5, 6, &header_accounts[9], &header_accounts[1])
     };
 
@@ -246,7 +273,7 @@ fn execute_clmm_swap_sell(
         AccountMeta::new(clmm_accounts[2].key(), false, false),  // amm_config (readonly)
         AccountMeta::new(clmm_accounts[1].key(), true, false),   // pool_state (writable)
         AccountMeta::new(header_accounts[11].key(), true, false), // input_token_account (token2)
-        AccountMeta::new(header_accounts[2].key(), true, false), // output_token_account (wsol)
+        AccountMeta::new(header_accounts[2].key(), true, false), // output_token_account (base)
         AccountMeta::new(clmm_accounts[input_vault_index].key(), true, false), // input_vault
         AccountMeta::new(clmm_accounts[output_vault_index].key(), true, false), // output_vault
         AccountMeta::new(clmm_accounts[3].key(), true, false),   // observation_state (writable)
@@ -261,8 +288,7 @@ fn execute_clmm_swap_sell(
         AccountMeta::new(clmm_accounts[9].key(), true, false),   // tick_array_1 (writable)
     ];
 
-    let mut instruction_data = CLMM_INSTRUCTION_DATA;
-    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    let instruction_data = clmm_instruction_data(trade_amount, min_out, exact_out);
 
     let swap_instruction = Instruction {
         program_id: clmm_accounts[0].key(),
@@ -275,7 +301,7 @@ fn execute_clmm_swap_sell(
         &clmm_accounts[2],                         // amm_config
         &clmm_accounts[1],                         // pool_state
         &header_accounts[11],                      // input_token_account (token2)
-        &header_accounts[2],                       // output_token_account (wsol)
+        &header_accounts[2],                       // output_token_account (base)
         &clmm_accounts[input_vault_index],         // input_vault
         &clmm_accounts[output_vault_index],        // output_vault
         &clmm_accounts[3],                         // observation_state
@@ -293,3 +319,72 @@ fn execute_clmm_swap_sell(
     invoke::<17>(&swap_instruction, &base_accounts)
 }
 
+/// N-hop路由中的通用交换：input -> output
+/// is_zero_to_one 表示输入token是否为token_0
+#[allow(clippy::too_many_arguments)]
+pub fn execute_clmm_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    clmm_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_zero_to_one: bool,
+    exact_out: bool,
+) -> ProgramResult {
+    let (input_vault_index, output_vault_index) = if is_zero_to_one {
+        (5, 6)
+    } else {
+        (6, 5)
+    };
+
+    let account_metas = [
+        AccountMeta::new(header_accounts[0].key(), true, true),   // payer (signer)
+        AccountMeta::new(clmm_accounts[2].key(), false, false),  // amm_config (readonly)
+        AccountMeta::new(clmm_accounts[1].key(), true, false),   // pool_state (writable)
+        AccountMeta::new(input.account.key(), true, false),      // input_token_account
+        AccountMeta::new(output.account.key(), true, false),     // output_token_account
+        AccountMeta::new(clmm_accounts[input_vault_index].key(), true, false), // input_vault
+        AccountMeta::new(clmm_accounts[output_vault_index].key(), true, false), // output_vault
+        AccountMeta::new(clmm_accounts[3].key(), true, false),   // observation_state (writable)
+        AccountMeta::new(header_accounts[3].key(), false, false), // token_program (readonly)
+        AccountMeta::new(header_accounts[4].key(), false, false), // token_program_2022 (readonly)
+        AccountMeta::new(header_accounts[5].key(), false, false), // memo_program (readonly)
+        AccountMeta::new(input.mint.key(), false, false),        // input_vault_mint (readonly)
+        AccountMeta::new(output.mint.key(), false, false),       // output_vault_mint (readonly)
+        AccountMeta::new(clmm_accounts[4].key(), false, false),  // bitmap_extension (readonly)
+        AccountMeta::new(clmm_accounts[7].key(), true, false),   // tick_array_minus_1 (writable)
+        AccountMeta::new(clmm_accounts[8].key(), true, false),   // tick_array_0 (writable)
+        AccountMeta::new(clmm_accounts[9].key(), true, false),   // tick_array_1 (writable)
+    ];
+
+    let instruction_data = clmm_instruction_data(trade_amount, min_out, exact_out);
+
+    let swap_instruction = Instruction {
+        program_id: clmm_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let base_accounts = [
+        &header_accounts[0],                       // payer
+        &clmm_accounts[2],                         // amm_config
+        &clmm_accounts[1],                         // pool_state
+        input.account,                             // input_token_account
+        output.account,                            // output_token_account
+        &clmm_accounts[input_vault_index],         // input_vault
+        &clmm_accounts[output_vault_index],        // output_vault
+        &clmm_accounts[3],                         // observation_state
+        &header_accounts[3],                       // token_program
+        &header_accounts[4],                       // token_program_2022
+        &header_accounts[5],                       // memo_program
+        input.mint,                                // input_vault_mint
+        output.mint,                               // output_vault_mint
+        &clmm_accounts[4],                         // bitmap_extension
+        &clmm_accounts[7],                         // tick_array_minus_1
+        &clmm_accounts[8],                         // tick_array_0
+        &clmm_accounts[9],                         // tick_array_1
+    ];
+
+    invoke::<17>(&swap_instruction, &base_accounts)
+}
@@ -6,27 +6,41 @@ use pinocchio::{
     ProgramResult,
 };
 use crate::error::PinocchioCpiError;
+use crate::utils::TokenSlot;
 
 const CPMM_INSTRUCTION_DATA: [u8; 24] = [
     // discriminator [0..8]
     143, 190, 90, 218, 196, 30, 51, 222,
     // amount_in placeholder [8..16] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
-    // minimum_amount_out = 0 [16..24]
+    // minimum_amount_out placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// 🚀 精确输出：swap_base_output，参数位置与 swap_base_input 相同
+const CPMM_SWAP_BASE_OUTPUT_INSTRUCTION_DATA: [u8; 24] = [
+    // discriminator [0..8]
+    55, 217, 98, 86, 163, 74, 180, 173,
+    // max_amount_in placeholder [8..16] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // amount_out placeholder [16..24] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
 // const DISCRIMINATOR: [u8; 8] = [143, 190, 90, 218, 196, 30, 51, 222];
+/// `exact_out` 时使用 swap_base_output：`trade_amount` 为最大输入，`min_out` 为精确输出
 pub fn execute_cpmm_swap(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     cpmm_accounts: &[AccountInfo],
     is_buy: bool,
-    is_wsol_pool_0: bool,
+    is_base_pool_0: bool,
+    exact_out: bool,
 ) -> ProgramResult {
   
     // 🚀 优化1: 确定vault配置，避免运行时分支
-    let (wsol_vault, token_vault) = if is_wsol_pool_0 {
+    let (base_vault, token_vault) = if is_base_pool_0 {
         (&cpmm_accounts[5], &cpmm_accounts[6])
     } else {
         (&cpmm_accounts[6], &cpmm_accounts[5])
@@ -44,25 +58,25 @@ pub fn execute_cpmm_swap(
         output_token_mint,
     ) = if is_buy {
         (
-            &header_accounts[2],  // wsol_token_account
+            &header_accounts[2],  // base_token_account
             &header_accounts[8],  // mint_token_account
-            wsol_vault,
+            base_vault,
             token_vault,
             &header_accounts[3],  // token_program
             &header_accounts[7],  // token_program_for_mint
-            &header_accounts[1],  // wsol_mint
+            &header_accounts[1],  // base_mint
             &header_accounts[6],  // token_mint
         )
     } else {
         (
             &header_accounts[8],  // mint_token_account
-            &header_accounts[2],  // wsol_token_account
+            &header_accounts[2],  // base_token_account
             token_vault,
-            wsol_vault,
+            base_vault,
             &header_accounts[7],  // token_program_for_mint
             &header_accounts[3],  // token_program
             &header_accounts[6],  // token_mint
-            &header_accounts[1],  // wsol_mint
+            &header_accounts[1],  // base_mint
         )
     };
 
@@ -87,10 +101,11 @@ pub fn execute_cpmm_swap(
     // const INSTRUCTION_DATA_SIZE: usize = 24; // 8字节discriminator + 8字节amount_in + 8字节minimum_amount_out
     
     // 🚀 优化：预构建模板，只替换变量部分
-    let mut instruction_data = CPMM_INSTRUCTION_DATA;
+    let mut instruction_data = if exact_out { CPMM_SWAP_BASE_OUTPUT_INSTRUCTION_DATA } else { CPMM_INSTRUCTION_DATA };
    
     // 只替换变量部分
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     // 🚀 优化5: 构建Pinocchio指令结构
     let swap_instruction = Instruction {
@@ -123,22 +138,25 @@ pub fn execute_cpmm_swap(
 
 
 
+#[allow(clippy::needless_return)]
 pub fn execute_cpmm_swap_hop3(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     cpmm_accounts: &[AccountInfo],
     step: u8,
-    is_wsol_pool_0: bool,
+    is_base_pool_0: bool,
+    exact_out: bool,
 ) -> ProgramResult {
     match step {
         1 => {
-            return execute_cpmm_swap(trade_amount, header_accounts, cpmm_accounts, true, is_wsol_pool_0)
+            return execute_cpmm_swap(trade_amount, min_out, header_accounts, cpmm_accounts, true, is_base_pool_0, exact_out)
         }
         2 => {
-            return execute_cpmm_swap_mid(trade_amount, header_accounts, cpmm_accounts, is_wsol_pool_0)
+            return execute_cpmm_swap_mid(trade_amount, min_out, header_accounts, cpmm_accounts, is_base_pool_0, exact_out)
         }
         3 => {
-            return execute_cpmm_swap_sell(trade_amount, header_accounts, cpmm_accounts, is_wsol_pool_0)
+            return execute_cpmm_swap_sell(trade_amount, min_out, header_accounts, cpmm_accounts, is_base_pool_0, exact_out)
         }
         _ => {
             return Err(PinocchioCpiError::UnsupportedPoolType.into());
@@ -150,9 +168,11 @@ pub fn execute_cpmm_swap_hop3(
 
 fn execute_cpmm_swap_mid(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     cpmm_accounts: &[AccountInfo],
     is_mid_zero_to_one: bool,
+    exact_out: bool,
 ) -> ProgramResult {
 
     // 中间交换：Token1 -> Token2
@@ -181,8 +201,9 @@ fn execute_cpmm_swap_mid(
         AccountMeta::new(cpmm_accounts[3].key(), true, false),    // observation_state
     ];
 
-    let mut instruction_data = CPMM_INSTRUCTION_DATA;
+    let mut instruction_data = if exact_out { CPMM_SWAP_BASE_OUTPUT_INSTRUCTION_DATA } else { CPMM_INSTRUCTION_DATA };
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: cpmm_accounts[0].key(),
@@ -212,13 +233,15 @@ fn execute_cpmm_swap_mid(
 
 fn execute_cpmm_swap_sell(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     cpmm_accounts: &[AccountInfo],
     is_base_mint_on_0: bool,
+    exact_out: bool,
 ) -> ProgramResult {
-    // 卖出交换：Token2 -> WSOL
+    // 卖出交换：Token2 -> 基础资产
     // 输入：header_accounts[11] (token2_account)
-    // 输出：header_accounts[2] (wsol_account)
+    // 输出：header_accounts[2] (base_account)
     
     let (input_vault, output_vault) = if is_base_mint_on_0 {
         (&cpmm_accounts[6], &cpmm_accounts[5])  
@@ -232,18 +255,19 @@ fn execute_cpmm_swap_sell(
         AccountMeta::new(cpmm_accounts[2].key(), false, false),   // amm_config
         AccountMeta::new(cpmm_accounts[4].key(), true, false),    // pool_state
         AccountMeta::new(header_accounts[11].key(), true, false), // input_token_account (token2)
-        AccountMeta::new(header_accounts[2].key(), true, false),  // output_token_account (wsol)
+        AccountMeta::new(header_accounts[2].key(), true, false),  // output_token_account (base)
         AccountMeta::new(input_vault.key(), true, false),         // input_vault
         AccountMeta::new(output_vault.key(), true, false),        // output_vault
         AccountMeta::new(header_accounts[10].key(), false, false), // token2_program
-        AccountMeta::new(header_accounts[3].key(), false, false), // wsol_program
+        AccountMeta::new(header_accounts[3].key(), false, false), // base_program
         AccountMeta::new(header_accounts[9].key(), false, false), // token2_mint
-        AccountMeta::new(header_accounts[1].key(), false, false), // wsol_mint
+        AccountMeta::new(header_accounts[1].key(), false, false), // base_mint
         AccountMeta::new(cpmm_accounts[3].key(), true, false),    // observation_state
     ];
 
-    let mut instruction_data = CPMM_INSTRUCTION_DATA;
+    let mut instruction_data = if exact_out { CPMM_SWAP_BASE_OUTPUT_INSTRUCTION_DATA } else { CPMM_INSTRUCTION_DATA };
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: cpmm_accounts[0].key(),
@@ -268,4 +292,69 @@ fn execute_cpmm_swap_sell(
     ];
 
     invoke::<13>(&swap_instruction, &account_infos)
-}
\ No newline at end of file
+}
+
+
+/// N-hop路由中的通用交换：input -> output
+/// is_zero_to_one 表示输入token是否为池的token_0
+#[allow(clippy::too_many_arguments)]
+pub fn execute_cpmm_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    cpmm_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_zero_to_one: bool,
+    exact_out: bool,
+) -> ProgramResult {
+    let (input_vault, output_vault) = if is_zero_to_one {
+        (&cpmm_accounts[5], &cpmm_accounts[6])
+    } else {
+        (&cpmm_accounts[6], &cpmm_accounts[5])
+    };
+
+    let account_metas = [
+        AccountMeta::new(header_accounts[0].key(), true, true),   // payer
+        AccountMeta::new(cpmm_accounts[1].key(), false, false),   // authority
+        AccountMeta::new(cpmm_accounts[2].key(), false, false),   // amm_config
+        AccountMeta::new(cpmm_accounts[4].key(), true, false),    // pool_state
+        AccountMeta::new(input.account.key(), true, false),       // input_token_account
+        AccountMeta::new(output.account.key(), true, false),      // output_token_account
+        AccountMeta::new(input_vault.key(), true, false),         // input_vault
+        AccountMeta::new(output_vault.key(), true, false),        // output_vault
+        AccountMeta::new(input.program.key(), false, false),      // input_token_program
+        AccountMeta::new(output.program.key(), false, false),     // output_token_program
+        AccountMeta::new(input.mint.key(), false, false),         // input_token_mint
+        AccountMeta::new(output.mint.key(), false, false),        // output_token_mint
+        AccountMeta::new(cpmm_accounts[3].key(), true, false),    // observation_state
+    ];
+
+    let mut instruction_data = if exact_out { CPMM_SWAP_BASE_OUTPUT_INSTRUCTION_DATA } else { CPMM_INSTRUCTION_DATA };
+    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
+
+    let swap_instruction = Instruction {
+        program_id: cpmm_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        &header_accounts[0],
+        &cpmm_accounts[1],
+        &cpmm_accounts[2],
+        &cpmm_accounts[4],
+        input.account,
+        output.account,
+        input_vault,
+        output_vault,
+        input.program,
+        output.program,
+        input.mint,
+        output.mint,
+        &cpmm_accounts[3],
+    ];
+
+    invoke::<13>(&swap_instruction, &account_infos)
+}
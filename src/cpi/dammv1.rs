@@ -0,0 +1,190 @@
+use pinocchio::{
+    account_info::AccountInfo, cpi::invoke, instruction::AccountMeta, instruction::Instruction,
+    ProgramResult,
+};
+use crate::error::PinocchioCpiError;
+use crate::utils::TokenSlot;
+
+const DAMMV1_INSTRUCTION_DATA: [u8; 24] = [
+    // swap discriminator [0..8]
+    248, 198, 158, 145, 225, 117, 135, 200,
+    // in_amount placeholder [8..16] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // minimum_out_amount placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// 🚀 Meteora Dynamic AMM (DAMM v1, pool type 9)，池中资产存放在 Meteora vault 中。
+/// 账户顺序：[0] program, [1] pool, [2] a_vault, [3] b_vault, [4] a_token_vault,
+/// [5] b_token_vault, [6] a_vault_lp_mint, [7] b_vault_lp_mint, [8] a_vault_lp,
+/// [9] b_vault_lp, [10] protocol_token_a_fee, [11] protocol_token_b_fee, [12] vault_program
+pub fn execute_dammv1_swap(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dammv1_accounts: &[AccountInfo],
+    is_buy: bool,
+    is_base_token_a: bool, // 基础资产是否为token_a
+) -> ProgramResult {
+    let (user_source_token, user_destination_token, token_program) = if is_buy {
+        (
+            &header_accounts[2], // base_token_account
+            &header_accounts[8], // mint_token_account
+            &header_accounts[3], // token_program
+        )
+    } else {
+        (
+            &header_accounts[8], // mint_token_account
+            &header_accounts[2], // base_token_account
+            &header_accounts[7], // token_program_for_mint
+        )
+    };
+
+    // 输入为token_a时手续费记入protocol_token_a_fee
+    invoke_dammv1_swap(
+        trade_amount,
+        min_out,
+        header_accounts,
+        dammv1_accounts,
+        user_source_token,
+        user_destination_token,
+        token_program,
+        is_buy == is_base_token_a,
+    )
+}
+
+pub fn execute_dammv1_swap_hop3(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dammv1_accounts: &[AccountInfo],
+    step: u8,
+    is_base_token_a: bool,
+) -> ProgramResult {
+    match step {
+        1 => {
+            execute_dammv1_swap(trade_amount, min_out, header_accounts, dammv1_accounts, true, is_base_token_a)
+        }
+        2 => {
+            // 中间交换：Token1 -> Token2，is_base_token_a 此时为 is_mid_zero_to_one
+            invoke_dammv1_swap(
+                trade_amount,
+                min_out,
+                header_accounts,
+                dammv1_accounts,
+                &header_accounts[8],  // token1_account
+                &header_accounts[11], // token2_account
+                &header_accounts[7],  // token1_program
+                is_base_token_a,
+            )
+        }
+        3 => {
+            // 卖出交换：Token2 -> 基础资产
+            invoke_dammv1_swap(
+                trade_amount,
+                min_out,
+                header_accounts,
+                dammv1_accounts,
+                &header_accounts[11], // token2_account
+                &header_accounts[2],  // base_account
+                &header_accounts[10], // token2_program
+                !is_base_token_a,
+            )
+        }
+        _ => {
+            Err(PinocchioCpiError::UnsupportedPoolType.into())
+        }
+    }
+}
+
+/// N-hop路由中的通用交换：input -> output
+/// is_zero_to_one 表示输入token是否为token_a
+pub fn execute_dammv1_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dammv1_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_zero_to_one: bool,
+) -> ProgramResult {
+    invoke_dammv1_swap(
+        trade_amount,
+        min_out,
+        header_accounts,
+        dammv1_accounts,
+        input.account,
+        output.account,
+        input.program,
+        is_zero_to_one,
+    )
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn invoke_dammv1_swap(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dammv1_accounts: &[AccountInfo],
+    user_source_token: &AccountInfo,
+    user_destination_token: &AccountInfo,
+    token_program: &AccountInfo,
+    is_a_to_b: bool,
+) -> ProgramResult {
+    let protocol_token_fee = if is_a_to_b {
+        &dammv1_accounts[10] // protocol_token_a_fee
+    } else {
+        &dammv1_accounts[11] // protocol_token_b_fee
+    };
+
+    // 构建账户列表 (15个账户)
+    let account_metas = [
+        AccountMeta::writable(dammv1_accounts[1].key()),      // pool
+        AccountMeta::writable(user_source_token.key()),       // user_source_token
+        AccountMeta::writable(user_destination_token.key()),  // user_destination_token
+        AccountMeta::writable(dammv1_accounts[2].key()),      // a_vault
+        AccountMeta::writable(dammv1_accounts[3].key()),      // b_vault
+        AccountMeta::writable(dammv1_accounts[4].key()),      // a_token_vault
+        AccountMeta::writable(dammv1_accounts[5].key()),      // b_token_vault
+        AccountMeta::writable(dammv1_accounts[6].key()),      // a_vault_lp_mint
+        AccountMeta::writable(dammv1_accounts[7].key()),      // b_vault_lp_mint
+        AccountMeta::writable(dammv1_accounts[8].key()),      // a_vault_lp
+        AccountMeta::writable(dammv1_accounts[9].key()),      // b_vault_lp
+        AccountMeta::writable(protocol_token_fee.key()),      // protocol_token_fee
+        AccountMeta::readonly_signer(header_accounts[0].key()), // user (signer)
+        AccountMeta::readonly(dammv1_accounts[12].key()),     // vault_program
+        AccountMeta::readonly(token_program.key()),           // token_program
+    ];
+
+    // 🚀 优化：预构建模板，只替换变量部分
+    let mut instruction_data = DAMMV1_INSTRUCTION_DATA;
+    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
+
+    let swap_instruction = Instruction {
+        program_id: dammv1_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        &dammv1_accounts[1],    // pool
+        user_source_token,      // user_source_token
+        user_destination_token, // user_destination_token
+        &dammv1_accounts[2],    // a_vault
+        &dammv1_accounts[3],    // b_vault
+        &dammv1_accounts[4],    // a_token_vault
+        &dammv1_accounts[5],    // b_token_vault
+        &dammv1_accounts[6],    // a_vault_lp_mint
+        &dammv1_accounts[7],    // b_vault_lp_mint
+        &dammv1_accounts[8],    // a_vault_lp
+        &dammv1_accounts[9],    // b_vault_lp
+        protocol_token_fee,     // protocol_token_fee
+        &header_accounts[0],    // user
+        &dammv1_accounts[12],   // vault_program
+        token_program,          // token_program
+    ];
+
+    invoke::<15>(&swap_instruction, &account_infos)
+}
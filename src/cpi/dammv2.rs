@@ -3,22 +3,24 @@ use pinocchio::{
     ProgramResult,
 };
 use crate::error::PinocchioCpiError;
+use crate::utils::TokenSlot;
 
 const DAMMV2_INSTRUCTION_DATA: [u8; 24] = [
     // swap discriminator [0..8]
     248, 198, 158, 145, 225, 117, 135, 200,
     // amount_in placeholder [8..16] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
-    // minimum_amount_out = 0 [16..24]
+    // minimum_amount_out placeholder [16..24] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
 pub fn execute_dammv2_swap(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     dammv2_accounts: &[AccountInfo],
     is_buy: bool,
-    is_wsol_token_a: bool, // WSOL是否为token_a
+    is_base_token_a: bool, // 基础资产是否为token_a
 ) -> ProgramResult {
     let (
         token_a_mint,
@@ -26,9 +28,9 @@ pub fn execute_dammv2_swap(
         token_a_program,
         token_b_program,
 
-    ) = if is_wsol_token_a {
+    ) = if is_base_token_a {
         (
-            &header_accounts[1], // wsol_mint
+            &header_accounts[1], // base_mint
             &header_accounts[6], // token_mint
             &header_accounts[3], // token_program
             &header_accounts[7], // token_program_for_mint
@@ -36,7 +38,7 @@ pub fn execute_dammv2_swap(
     } else {
         (
             &header_accounts[6], // token_mint
-            &header_accounts[1], // wsol_mint
+            &header_accounts[1], // base_mint
             &header_accounts[7], // token_program_for_mint
             &header_accounts[3], // token_program
         )
@@ -44,13 +46,13 @@ pub fn execute_dammv2_swap(
 
     let (user_token_in, user_token_out) = if is_buy {
         (
-            &header_accounts[2], // wsol_token_account
+            &header_accounts[2], // base_token_account
             &header_accounts[8], // mint_token_account
         )
     } else {
         (
             &header_accounts[8], // mint_token_account
-            &header_accounts[2], // wsol_token_account
+            &header_accounts[2], // base_token_account
         )
     };
 
@@ -76,6 +78,7 @@ pub fn execute_dammv2_swap(
     // 🚀 优化：预构建模板，只替换变量部分
     let mut instruction_data = DAMMV2_INSTRUCTION_DATA;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: dammv2_accounts[0].key(),
@@ -105,20 +108,21 @@ pub fn execute_dammv2_swap(
 
 pub fn execute_dammv2_swap_hop3(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     dammv2_accounts: &[AccountInfo],
     step: u8,
-    is_wsol_token_a: bool,
+    is_base_token_a: bool,
 ) -> ProgramResult {
     match step {
         1 => {
-            execute_dammv2_swap(trade_amount, header_accounts, dammv2_accounts, true, is_wsol_token_a)
+            execute_dammv2_swap(trade_amount, min_out, header_accounts, dammv2_accounts, true, is_base_token_a)
         }
         2 => {
-            execute_dammv2_swap_mid(trade_amount, header_accounts, dammv2_accounts, is_wsol_token_a)
+            execute_dammv2_swap_mid(trade_amount, min_out, header_accounts, dammv2_accounts, is_base_token_a)
         }
         3 => {
-            execute_dammv2_swap_sell(trade_amount, header_accounts, dammv2_accounts, is_wsol_token_a)
+            execute_dammv2_swap_sell(trade_amount, min_out, header_accounts, dammv2_accounts, is_base_token_a)
         }
         _ => {
             Err(PinocchioCpiError::UnsupportedPoolType.into())
@@ -128,6 +132,7 @@ pub fn execute_dammv2_swap_hop3(
 
 fn execute_dammv2_swap_mid(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     dammv2_accounts: &[AccountInfo],
     is_mid_zero_to_one: bool,
@@ -164,6 +169,7 @@ fn execute_dammv2_swap_mid(
 
     let mut instruction_data = DAMMV2_INSTRUCTION_DATA;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: dammv2_accounts[0].key(),
@@ -193,20 +199,21 @@ fn execute_dammv2_swap_mid(
 
 fn execute_dammv2_swap_sell(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     dammv2_accounts: &[AccountInfo],
-    is_wsol_token_a: bool,
+    is_base_token_a: bool,
 ) -> ProgramResult {
-    // 卖出交换：Token2 -> WSOL
+    // 卖出交换：Token2 -> 基础资产
     // 输入：header_accounts[11] (token2_account)
-    // 输出：header_accounts[2] (wsol_account)
+    // 输出：header_accounts[2] (base_account)
     
-    // is_wsol_token_a表示WSOL是否为tokenA
-    let (token_a_mint, token_b_mint, token_a_program, token_b_program) = if is_wsol_token_a {
-        // WSOL是tokenA，Token2是tokenB
+    // is_base_token_a表示基础资产是否为tokenA
+    let (token_a_mint, token_b_mint, token_a_program, token_b_program) = if is_base_token_a {
+        // 基础资产是tokenA，Token2是tokenB
         (&header_accounts[1], &header_accounts[9], &header_accounts[3], &header_accounts[10])
     } else {
-        // WSOL是tokenB，Token2是tokenA
+        // 基础资产是tokenB，Token2是tokenA
         (&header_accounts[9], &header_accounts[1], &header_accounts[10], &header_accounts[3])
     };
 
@@ -214,7 +221,7 @@ fn execute_dammv2_swap_sell(
         AccountMeta::new(dammv2_accounts[2].key(), false, false), // pool_authority
         AccountMeta::new(dammv2_accounts[3].key(), true, false),  // pool
         AccountMeta::new(header_accounts[11].key(), true, false), // user_token_in (token2)
-        AccountMeta::new(header_accounts[2].key(), true, false),  // user_token_out (wsol)
+        AccountMeta::new(header_accounts[2].key(), true, false),  // user_token_out (base)
         AccountMeta::new(dammv2_accounts[4].key(), true, false),  // token_a_vault
         AccountMeta::new(dammv2_accounts[5].key(), true, false),  // token_b_vault
         AccountMeta::new(token_a_mint.key(), false, false),       // token_a_mint
@@ -229,6 +236,7 @@ fn execute_dammv2_swap_sell(
 
     let mut instruction_data = DAMMV2_INSTRUCTION_DATA;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: dammv2_accounts[0].key(),
@@ -240,7 +248,7 @@ fn execute_dammv2_swap_sell(
         &dammv2_accounts[2],      // pool_authority
         &dammv2_accounts[3],      // pool
         &header_accounts[11],     // user_token_in (token2)
-        &header_accounts[2],      // user_token_out (wsol)
+        &header_accounts[2],      // user_token_out (base)
         &dammv2_accounts[4],      // token_a_vault
         &dammv2_accounts[5],      // token_b_vault
         token_a_mint,             // token_a_mint
@@ -255,3 +263,67 @@ fn execute_dammv2_swap_sell(
 
     invoke::<14>(&swap_instruction, &account_infos)
 }
+
+/// N-hop路由中的通用交换：input -> output
+/// is_zero_to_one 表示输入token是否为tokenA
+pub fn execute_dammv2_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dammv2_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_zero_to_one: bool,
+) -> ProgramResult {
+    let (token_a, token_b) = if is_zero_to_one {
+        (input, output)
+    } else {
+        (output, input)
+    };
+
+    let account_metas = [
+        AccountMeta::new(dammv2_accounts[2].key(), false, false), // pool_authority
+        AccountMeta::new(dammv2_accounts[3].key(), true, false),  // pool
+        AccountMeta::new(input.account.key(), true, false),       // user_token_in
+        AccountMeta::new(output.account.key(), true, false),      // user_token_out
+        AccountMeta::new(dammv2_accounts[4].key(), true, false),  // token_a_vault
+        AccountMeta::new(dammv2_accounts[5].key(), true, false),  // token_b_vault
+        AccountMeta::new(token_a.mint.key(), false, false),       // token_a_mint
+        AccountMeta::new(token_b.mint.key(), false, false),       // token_b_mint
+        AccountMeta::new(header_accounts[0].key(), true, true),   // payer
+        AccountMeta::new(token_a.program.key(), false, false),    // token_a_program
+        AccountMeta::new(token_b.program.key(), false, false),    // token_b_program
+        AccountMeta::new(dammv2_accounts[0].key(), false, false), // referral_token_account
+        AccountMeta::new(dammv2_accounts[1].key(), false, false), // event_authority
+        AccountMeta::new(dammv2_accounts[0].key(), false, false), // program
+    ];
+
+    let mut instruction_data = DAMMV2_INSTRUCTION_DATA;
+    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
+
+    let swap_instruction = Instruction {
+        program_id: dammv2_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        &dammv2_accounts[2],      // pool_authority
+        &dammv2_accounts[3],      // pool
+        input.account,            // user_token_in
+        output.account,           // user_token_out
+        &dammv2_accounts[4],      // token_a_vault
+        &dammv2_accounts[5],      // token_b_vault
+        token_a.mint,             // token_a_mint
+        token_b.mint,             // token_b_mint
+        &header_accounts[0],      // payer
+        token_a.program,          // token_a_program
+        token_b.program,          // token_b_program
+        &dammv2_accounts[0],      // referral_token_account
+        &dammv2_accounts[1],      // event_authority
+        &dammv2_accounts[0],      // program
+    ];
+
+    invoke::<14>(&swap_instruction, &account_infos)
+}
@@ -0,0 +1,147 @@
+use pinocchio::{
+    account_info::AccountInfo, cpi::invoke, instruction::AccountMeta, instruction::Instruction,
+    ProgramResult,
+};
+use crate::error::PinocchioCpiError;
+use crate::utils::{self, TokenSlot};
+
+const DBC_INSTRUCTION_DATA: [u8; 24] = [
+    // swap discriminator [0..8]
+    248, 198, 158, 145, 225, 117, 135, 200,
+    // amount_in placeholder [8..16] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // minimum_amount_out placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// 🚀 Meteora Dynamic Bonding Curve (DBC, pool type 10)，base 为发行的 token，quote 为报价资产。
+/// 账户顺序：[0] program, [1] event_authority, [2] pool_authority, [3] config, [4] pool,
+/// [5] base_vault, [6] quote_vault
+pub fn execute_dbc_swap(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dbc_accounts: &[AccountInfo],
+    is_buy: bool,
+    is_base_token_base: bool, // 基础资产是否为DBC的base mint
+) -> ProgramResult {
+    let base = utils::get_token_slot(header_accounts, 2);
+    let token = utils::get_token_slot(header_accounts, 8);
+
+    if is_buy {
+        invoke_dbc_swap(trade_amount, min_out, header_accounts, dbc_accounts, &base, &token, is_base_token_base)
+    } else {
+        invoke_dbc_swap(trade_amount, min_out, header_accounts, dbc_accounts, &token, &base, !is_base_token_base)
+    }
+}
+
+pub fn execute_dbc_swap_hop3(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dbc_accounts: &[AccountInfo],
+    step: u8,
+    is_base_token_base: bool,
+) -> ProgramResult {
+    match step {
+        1 => {
+            execute_dbc_swap(trade_amount, min_out, header_accounts, dbc_accounts, true, is_base_token_base)
+        }
+        2 => {
+            // 中间交换：Token1 -> Token2，is_base_token_base 此时为 is_mid_zero_to_one
+            let token1 = utils::get_token_slot(header_accounts, 8);
+            let token2 = utils::get_token_slot(header_accounts, 11);
+            invoke_dbc_swap(trade_amount, min_out, header_accounts, dbc_accounts, &token1, &token2, is_base_token_base)
+        }
+        3 => {
+            // 卖出交换：Token2 -> 基础资产
+            let token2 = utils::get_token_slot(header_accounts, 11);
+            let base = utils::get_token_slot(header_accounts, 2);
+            invoke_dbc_swap(trade_amount, min_out, header_accounts, dbc_accounts, &token2, &base, !is_base_token_base)
+        }
+        _ => {
+            Err(PinocchioCpiError::UnsupportedPoolType.into())
+        }
+    }
+}
+
+/// N-hop路由中的通用交换：input -> output
+/// is_zero_to_one 表示输入token是否为DBC的base mint（卖出发行的token）
+pub fn execute_dbc_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dbc_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_zero_to_one: bool,
+) -> ProgramResult {
+    invoke_dbc_swap(trade_amount, min_out, header_accounts, dbc_accounts, input, output, is_zero_to_one)
+}
+
+#[inline(always)]
+fn invoke_dbc_swap(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dbc_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_base_to_quote: bool,
+) -> ProgramResult {
+    let (base, quote) = if is_base_to_quote {
+        (input, output)
+    } else {
+        (output, input)
+    };
+
+    // 构建账户列表 (15个账户)
+    let account_metas = [
+        AccountMeta::readonly(dbc_accounts[2].key()),          // pool_authority
+        AccountMeta::readonly(dbc_accounts[3].key()),          // config
+        AccountMeta::writable(dbc_accounts[4].key()),          // pool
+        AccountMeta::writable(input.account.key()),            // input_token_account
+        AccountMeta::writable(output.account.key()),           // output_token_account
+        AccountMeta::writable(dbc_accounts[5].key()),          // base_vault
+        AccountMeta::writable(dbc_accounts[6].key()),          // quote_vault
+        AccountMeta::readonly(base.mint.key()),                // base_mint
+        AccountMeta::readonly(quote.mint.key()),               // quote_mint
+        AccountMeta::writable_signer(header_accounts[0].key()), // payer (signer)
+        AccountMeta::readonly(base.program.key()),             // token_base_program
+        AccountMeta::readonly(quote.program.key()),            // token_quote_program
+        AccountMeta::readonly(dbc_accounts[0].key()),          // referral_token_account (用program_id占位)
+        AccountMeta::readonly(dbc_accounts[1].key()),          // event_authority
+        AccountMeta::readonly(dbc_accounts[0].key()),          // program
+    ];
+
+    // 🚀 优化：预构建模板，只替换变量部分
+    let mut instruction_data = DBC_INSTRUCTION_DATA;
+    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
+
+    let swap_instruction = Instruction {
+        program_id: dbc_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        &dbc_accounts[2],     // pool_authority
+        &dbc_accounts[3],     // config
+        &dbc_accounts[4],     // pool
+        input.account,        // input_token_account
+        output.account,       // output_token_account
+        &dbc_accounts[5],     // base_vault
+        &dbc_accounts[6],     // quote_vault
+        base.mint,            // base_mint
+        quote.mint,           // quote_mint
+        &header_accounts[0],  // payer
+        base.program,         // token_base_program
+        quote.program,        // token_quote_program
+        &dbc_accounts[0],     // referral_token_account
+        &dbc_accounts[1],     // event_authority
+        &dbc_accounts[0],     // program
+    ];
+
+    invoke::<15>(&swap_instruction, &account_infos)
+}
@@ -6,27 +6,30 @@ use pinocchio::{
     ProgramResult,
 };
 use crate::error::PinocchioCpiError;
+use crate::utils::TokenSlot;
 
 const DLMM_INSTRUCTION_DATA: [u8; 28] = [
     // swap discriminator [0..8]
     65, 75, 63, 76, 235, 91, 91, 136,
     // amount_in placeholder [8..16] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
-    // minimum_amount_out = 0 [16..24]
+    // minimum_amount_out placeholder [16..24] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
     // remaining_accounts_info slices.len() = 0 [24..28]
     0, 0, 0, 0,
 ];
 
+#[allow(clippy::useless_conversion)]
 pub fn execute_dlmm_swap(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     dlmm_accounts: &[AccountInfo],
     is_buy: bool,
-    is_wsol_x_mint: bool,
+    is_base_x_mint: bool,
 ) -> ProgramResult {
  
-    let (token_x_mint, token_y_mint, token_x_program, token_y_program) = if is_wsol_x_mint {
+    let (token_x_mint, token_y_mint, token_x_program, token_y_program) = if is_base_x_mint {
         (&header_accounts[1], &header_accounts[6], &header_accounts[3], &header_accounts[7])
     } else {
         (&header_accounts[6], &header_accounts[1], &header_accounts[7], &header_accounts[3])
@@ -38,13 +41,13 @@ pub fn execute_dlmm_swap(
         user_token_out,
     ) = if is_buy {
         (
-            &header_accounts[2],  // wsol_token_account
+            &header_accounts[2],  // base_token_account
             &header_accounts[8],  // mint_token_account
         )
     } else {
         (
             &header_accounts[8],  // mint_token_account
-            &header_accounts[2],  // wsol_token_account
+            &header_accounts[2],  // base_token_account
         )
     };
 
@@ -77,6 +80,7 @@ pub fn execute_dlmm_swap(
     // 🚀 优化：预构建模板，只替换变量部分
     let mut instruction_data = DLMM_INSTRUCTION_DATA;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
 
     // 🚀 优化5: 构建Pinocchio指令结构
@@ -115,23 +119,25 @@ pub fn execute_dlmm_swap(
 }
 
 
+#[allow(clippy::needless_return)]
 pub fn execute_dlmm_swap_hop3(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     dlmm_accounts: &[AccountInfo],
     step: u8,
-    is_wsol_x_mint: bool,
+    is_base_x_mint: bool,
 ) -> ProgramResult {
  
     match step {
         1 => {
-            return execute_dlmm_swap(trade_amount, header_accounts, dlmm_accounts, true, is_wsol_x_mint)
+            return execute_dlmm_swap(trade_amount, min_out, header_accounts, dlmm_accounts, true, is_base_x_mint)
         }
         2 => {
-            return execute_dlmm_swap_mid(trade_amount, header_accounts, dlmm_accounts, is_wsol_x_mint)
+            return execute_dlmm_swap_mid(trade_amount, min_out, header_accounts, dlmm_accounts, is_base_x_mint)
         }
         3 => {
-            return execute_dlmm_swap_sell(trade_amount, header_accounts, dlmm_accounts, is_wsol_x_mint)
+            return execute_dlmm_swap_sell(trade_amount, min_out, header_accounts, dlmm_accounts, is_base_x_mint)
         }
         _ => {
             return Err(PinocchioCpiError::UnsupportedPoolType.into());
@@ -139,8 +145,10 @@ pub fn execute_dlmm_swap_hop3(
     }
 }
 
+#[allow(clippy::useless_conversion)]
 fn execute_dlmm_swap_mid(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     dlmm_accounts: &[AccountInfo],
     is_mid_zero_to_one: bool,
@@ -186,6 +194,7 @@ fn execute_dlmm_swap_mid(
 
     let mut instruction_data = DLMM_INSTRUCTION_DATA;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: dlmm_accounts[0].key(),
@@ -218,24 +227,26 @@ fn execute_dlmm_swap_mid(
     invoke::<19>(&swap_instruction, &account_infos).map_err(|e| e.into())
 }
 
+#[allow(clippy::useless_conversion)]
 fn execute_dlmm_swap_sell(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     dlmm_accounts: &[AccountInfo],
-    is_wsol_x_mint: bool,
+    is_base_x_mint: bool,
 ) -> ProgramResult {
-    // 卖出交换：Token2 -> WSOL
+    // 卖出交换：Token2 -> 基础资产
     // 输入：header_accounts[11] (token2_account)
-    // 输出：header_accounts[2] (wsol_account)
+    // 输出：header_accounts[2] (base_account)
     
-    // is_wsol_x_mint表示WSOL是否为X mint
-    // 如果WSOL是X mint，那么Token2是Y mint
-    // 如果WSOL是Y mint，那么Token2是X mint
-    let (token_x_mint, token_y_mint, token_x_program, token_y_program) = if is_wsol_x_mint {
-        // WSOL是X，Token2是Y
+    // is_base_x_mint表示基础资产是否为X mint
+    // 如果基础资产是X mint，那么Token2是Y mint
+    // 如果基础资产是Y mint，那么Token2是X mint
+    let (token_x_mint, token_y_mint, token_x_program, token_y_program) = if is_base_x_mint {
+        // 基础资产是X，Token2是Y
         (&header_accounts[1], &header_accounts[9], &header_accounts[3], &header_accounts[10])
     } else {
-        // WSOL是Y，Token2是X  
+        // 基础资产是Y，Token2是X  
         (&header_accounts[9], &header_accounts[1], &header_accounts[10], &header_accounts[3])
     };
 
@@ -247,7 +258,7 @@ fn execute_dlmm_swap_sell(
         AccountMeta::new(dlmm_accounts[4].key(), true, false),   // reserve_x(writable)
         AccountMeta::new(dlmm_accounts[5].key(), true, false),   // reserve_y(writable)
         AccountMeta::new(header_accounts[11].key(), true, false), // user_token_in (token2_account)
-        AccountMeta::new(header_accounts[2].key(), true, false),  // user_token_out (wsol_account)
+        AccountMeta::new(header_accounts[2].key(), true, false),  // user_token_out (base_account)
         AccountMeta::new(token_x_mint.key(), false, false),      // token_x_mint(readonly)
         AccountMeta::new(token_y_mint.key(), false, false),      // token_y_mint(readonly)
         AccountMeta::new(dlmm_accounts[2].key(), true, false),   // oracle(writable)
@@ -265,6 +276,7 @@ fn execute_dlmm_swap_sell(
 
     let mut instruction_data = DLMM_INSTRUCTION_DATA;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: dlmm_accounts[0].key(),
@@ -278,7 +290,7 @@ fn execute_dlmm_swap_sell(
         &dlmm_accounts[4],        // reserve_x
         &dlmm_accounts[5],        // reserve_y
         &header_accounts[11],     // user_token_in (token2)
-        &header_accounts[2],      // user_token_out (wsol)
+        &header_accounts[2],      // user_token_out (base)
         token_x_mint,             // token_x_mint
         token_y_mint,             // token_y_mint
         &dlmm_accounts[2],        // oracle
@@ -295,4 +307,80 @@ fn execute_dlmm_swap_sell(
     ];
 
     invoke::<19>(&swap_instruction, &account_infos).map_err(|e| e.into())
-}
\ No newline at end of file
+}
+
+/// N-hop路由中的通用交换：input -> output
+/// is_zero_to_one 表示输入token是否为X mint
+pub fn execute_dlmm_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    dlmm_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_zero_to_one: bool,
+) -> ProgramResult {
+    let (token_x, token_y) = if is_zero_to_one {
+        (input, output)
+    } else {
+        (output, input)
+    };
+
+    let dlmm_program_id = &dlmm_accounts[0];
+
+    let account_metas = [
+        AccountMeta::new(dlmm_accounts[3].key(), true, false),   // pool_state(writable)
+        AccountMeta::new(dlmm_program_id.key(), false, false),   // bin_array_bitmap_extension(readonly)
+        AccountMeta::new(dlmm_accounts[4].key(), true, false),   // reserve_x(writable)
+        AccountMeta::new(dlmm_accounts[5].key(), true, false),   // reserve_y(writable)
+        AccountMeta::new(input.account.key(), true, false),      // user_token_in
+        AccountMeta::new(output.account.key(), true, false),     // user_token_out
+        AccountMeta::new(token_x.mint.key(), false, false),      // token_x_mint(readonly)
+        AccountMeta::new(token_y.mint.key(), false, false),      // token_y_mint(readonly)
+        AccountMeta::new(dlmm_accounts[2].key(), true, false),   // oracle(writable)
+        AccountMeta::new(dlmm_program_id.key(), false, false),   // host_fee_in(readonly)
+        AccountMeta::new(header_accounts[0].key(), true, true),  // payer (signer)
+        AccountMeta::new(token_x.program.key(), false, false),   // token_x_program(readonly)
+        AccountMeta::new(token_y.program.key(), false, false),   // token_y_program(readonly)
+        AccountMeta::new(header_accounts[5].key(), false, false), // memo_program(readonly)
+        AccountMeta::new(dlmm_accounts[1].key(), false, false),  // event_authority(readonly)
+        AccountMeta::new(dlmm_program_id.key(), false, false),   // program id(readonly)
+        AccountMeta::new(dlmm_accounts[6].key(), true, false),   // bin_array_minus_1(writable)
+        AccountMeta::new(dlmm_accounts[7].key(), true, false),   // bin_array_0(writable)
+        AccountMeta::new(dlmm_accounts[8].key(), true, false),   // bin_array_1(writable)
+    ];
+
+    let mut instruction_data = DLMM_INSTRUCTION_DATA;
+    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
+
+    let swap_instruction = Instruction {
+        program_id: dlmm_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        &dlmm_accounts[3],        // pool_state
+        dlmm_program_id,          // bin_array_bitmap_extension
+        &dlmm_accounts[4],        // reserve_x
+        &dlmm_accounts[5],        // reserve_y
+        input.account,            // user_token_in
+        output.account,           // user_token_out
+        token_x.mint,             // token_x_mint
+        token_y.mint,             // token_y_mint
+        &dlmm_accounts[2],        // oracle
+        dlmm_program_id,          // host_fee_in
+        &header_accounts[0],      // payer
+        token_x.program,          // token_x_program
+        token_y.program,          // token_y_program
+        &header_accounts[5],      // memo_program
+        &dlmm_accounts[1],        // event_authority
+        dlmm_program_id,          // program
+        &dlmm_accounts[6],        // bin_array_minus_1
+        &dlmm_accounts[7],        // bin_array_0
+        &dlmm_accounts[8],        // bin_array_1
+    ];
+
+    invoke::<19>(&swap_instruction, &account_infos)
+}
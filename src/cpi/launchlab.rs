@@ -0,0 +1,162 @@
+use pinocchio::{
+    account_info::AccountInfo, cpi::invoke, instruction::AccountMeta, instruction::Instruction,
+    ProgramResult,
+};
+use crate::error::PinocchioCpiError;
+use crate::utils::{self, TokenSlot};
+
+const LAUNCHLAB_INSTRUCTION_DATA_BUY: [u8; 32] = [
+    // buy_exact_in discriminator [0..8]
+    250, 234, 13, 123, 213, 156, 19, 236,
+    // amount_in (quote) placeholder [8..16] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // minimum_amount_out (base) placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // share_fee_rate [24..32]
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const LAUNCHLAB_INSTRUCTION_DATA_SELL: [u8; 32] = [
+    // sell_exact_in discriminator [0..8]
+    149, 39, 222, 155, 211, 124, 152, 26,
+    // amount_in (base) placeholder [8..16] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // minimum_amount_out (quote) placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // share_fee_rate [24..32]
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// 🚀 Raydium LaunchLab 联合曲线 (pool type 11)，base 为发行的 token，quote 为报价资产，
+/// 迁移前用 buy_exact_in / sell_exact_in 交易。
+/// 账户顺序：[0] program, [1] authority, [2] global_config, [3] platform_config,
+/// [4] pool_state, [5] base_vault, [6] quote_vault, [7] event_authority
+pub fn execute_launchlab_swap(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    launchlab_accounts: &[AccountInfo],
+    is_buy: bool,
+    is_base_token_base: bool, // 基础资产是否为LaunchLab的base mint
+) -> ProgramResult {
+    let base = utils::get_token_slot(header_accounts, 2);
+    let token = utils::get_token_slot(header_accounts, 8);
+
+    if is_buy {
+        invoke_launchlab_swap(trade_amount, min_out, header_accounts, launchlab_accounts, &base, &token, is_base_token_base)
+    } else {
+        invoke_launchlab_swap(trade_amount, min_out, header_accounts, launchlab_accounts, &token, &base, !is_base_token_base)
+    }
+}
+
+pub fn execute_launchlab_swap_hop3(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    launchlab_accounts: &[AccountInfo],
+    step: u8,
+    is_base_token_base: bool,
+) -> ProgramResult {
+    match step {
+        1 => {
+            execute_launchlab_swap(trade_amount, min_out, header_accounts, launchlab_accounts, true, is_base_token_base)
+        }
+        2 => {
+            // 中间交换：Token1 -> Token2，is_base_token_base 此时为 is_mid_zero_to_one
+            let token1 = utils::get_token_slot(header_accounts, 8);
+            let token2 = utils::get_token_slot(header_accounts, 11);
+            invoke_launchlab_swap(trade_amount, min_out, header_accounts, launchlab_accounts, &token1, &token2, is_base_token_base)
+        }
+        3 => {
+            // 卖出交换：Token2 -> 基础资产
+            let token2 = utils::get_token_slot(header_accounts, 11);
+            let base = utils::get_token_slot(header_accounts, 2);
+            invoke_launchlab_swap(trade_amount, min_out, header_accounts, launchlab_accounts, &token2, &base, !is_base_token_base)
+        }
+        _ => {
+            Err(PinocchioCpiError::UnsupportedPoolType.into())
+        }
+    }
+}
+
+/// N-hop路由中的通用交换：input -> output
+/// is_zero_to_one 表示输入token是否为LaunchLab的base mint：是则sell_exact_in，否则buy_exact_in
+pub fn execute_launchlab_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    launchlab_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_zero_to_one: bool,
+) -> ProgramResult {
+    invoke_launchlab_swap(trade_amount, min_out, header_accounts, launchlab_accounts, input, output, is_zero_to_one)
+}
+
+#[inline(always)]
+fn invoke_launchlab_swap(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    launchlab_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_sell: bool,
+) -> ProgramResult {
+    // 卖出：base -> quote，买入：quote -> base
+    let (base, quote) = if is_sell {
+        (input, output)
+    } else {
+        (output, input)
+    };
+
+    // 构建账户列表 (15个账户)
+    let account_metas = [
+        AccountMeta::readonly_signer(header_accounts[0].key()), // payer (signer)
+        AccountMeta::readonly(launchlab_accounts[1].key()),     // authority
+        AccountMeta::readonly(launchlab_accounts[2].key()),     // global_config
+        AccountMeta::readonly(launchlab_accounts[3].key()),     // platform_config
+        AccountMeta::writable(launchlab_accounts[4].key()),     // pool_state
+        AccountMeta::writable(base.account.key()),              // user_base_token
+        AccountMeta::writable(quote.account.key()),             // user_quote_token
+        AccountMeta::writable(launchlab_accounts[5].key()),     // base_vault
+        AccountMeta::writable(launchlab_accounts[6].key()),     // quote_vault
+        AccountMeta::readonly(base.mint.key()),                 // base_token_mint
+        AccountMeta::readonly(quote.mint.key()),                // quote_token_mint
+        AccountMeta::readonly(base.program.key()),              // base_token_program
+        AccountMeta::readonly(quote.program.key()),             // quote_token_program
+        AccountMeta::readonly(launchlab_accounts[7].key()),     // event_authority
+        AccountMeta::readonly(launchlab_accounts[0].key()),     // program
+    ];
+
+    // 🚀 优化：预构建模板，只替换变量部分
+    let mut instruction_data = if is_sell { LAUNCHLAB_INSTRUCTION_DATA_SELL } else { LAUNCHLAB_INSTRUCTION_DATA_BUY };
+    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
+
+    let swap_instruction = Instruction {
+        program_id: launchlab_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        &header_accounts[0],     // payer
+        &launchlab_accounts[1],  // authority
+        &launchlab_accounts[2],  // global_config
+        &launchlab_accounts[3],  // platform_config
+        &launchlab_accounts[4],  // pool_state
+        base.account,            // user_base_token
+        quote.account,           // user_quote_token
+        &launchlab_accounts[5],  // base_vault
+        &launchlab_accounts[6],  // quote_vault
+        base.mint,               // base_token_mint
+        quote.mint,              // quote_token_mint
+        base.program,            // base_token_program
+        quote.program,           // quote_token_program
+        &launchlab_accounts[7],  // event_authority
+        &launchlab_accounts[0],  // program
+    ];
+
+    invoke::<15>(&swap_instruction, &account_infos)
+}
@@ -6,7 +6,13 @@
 pub mod cpmm;
 pub mod dlmm;
 pub mod raydium;
+pub mod launchlab;
 pub mod pump;
+pub mod pump_fun;
+pub mod dammv1;
 pub mod dammv2;
+pub mod dbc;
 pub mod clmm;
-pub mod whirlpool;
\ No newline at end of file
+pub mod whirlpool;
+pub mod tip;
+pub mod native_sol;
\ No newline at end of file
@@ -0,0 +1,141 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::AccountMeta,
+    instruction::Instruction,
+    cpi::invoke,
+    ProgramResult,
+};
+use crate::constants::SYSTEM_PROGRAM_ID;
+
+const SYSTEM_TRANSFER_INSTRUCTION_DATA: [u8; 12] = [
+    // transfer discriminator [0..4]
+    2, 0, 0, 0,
+    // lamports placeholder [4..12] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// SPL Token SyncNative
+const SYNC_NATIVE_INSTRUCTION_DATA: [u8; 1] = [17];
+
+/// SPL Token CloseAccount
+const CLOSE_ACCOUNT_INSTRUCTION_DATA: [u8; 1] = [9];
+
+const TOKEN_TRANSFER_INSTRUCTION_DATA: [u8; 9] = [
+    // transfer discriminator [0..1]
+    3,
+    // amount placeholder [1..9] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// 🚀 System Program Transfer：从签名者 `from` 向 `to` 转账 `lamports`
+///
+/// program_id 固定为 System Program，不使用调用方传入的账户；
+/// System Program 必须出现在指令账户列表中（客户端追加在最后）。
+pub fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let account_metas = [
+        AccountMeta::writable_signer(from.key()), // from
+        AccountMeta::writable(to.key()),          // to
+    ];
+
+    let mut instruction_data = SYSTEM_TRANSFER_INSTRUCTION_DATA;
+    instruction_data[4..12].copy_from_slice(&lamports.to_le_bytes());
+
+    let instruction = Instruction {
+        program_id: &SYSTEM_PROGRAM_ID,
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    invoke::<2>(&instruction, &[from, to])
+}
+
+/// 🚀 第一跳之前把 payer 的 `lamports` 转入 WSOL 账户 header_accounts[2] 并 SyncNative
+///
+/// 在读取初始余额之前调用，包装的 SOL 不计入利润。
+pub fn wrap_sol(lamports: u64, header_accounts: &[AccountInfo]) -> ProgramResult {
+    let payer = &header_accounts[0];
+    let base_account = &header_accounts[2];
+    let token_program = &header_accounts[3];
+
+    if lamports != 0 {
+        transfer_lamports(payer, base_account, lamports)?;
+    }
+
+    let account_metas = [
+        AccountMeta::writable(base_account.key()), // account
+    ];
+
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: &account_metas,
+        data: &SYNC_NATIVE_INSTRUCTION_DATA,
+    };
+
+    invoke::<1>(&instruction, &[base_account])
+}
+
+/// 🚀 利润校验之后关闭 WSOL 账户 header_accounts[2]，全部 lamports（本金 + 利润 + 租金）退回 payer
+///
+/// SPL Token 无法部分解包，因此解包会关闭基础资产账户，下一笔交易前需要重新创建
+/// （例如在 bundle 中使用幂等的 CreateAssociatedTokenAccount）。
+pub fn unwrap_sol(header_accounts: &[AccountInfo]) -> ProgramResult {
+    let payer = &header_accounts[0];
+    let base_account = &header_accounts[2];
+    let token_program = &header_accounts[3];
+
+    let account_metas = [
+        AccountMeta::writable(base_account.key()), // account
+        AccountMeta::writable(payer.key()),        // destination
+        AccountMeta::readonly_signer(payer.key()), // owner
+    ];
+
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: &account_metas,
+        data: &CLOSE_ACCOUNT_INSTRUCTION_DATA,
+    };
+
+    invoke::<3>(&instruction, &[base_account, payer, payer])
+}
+
+/// 🚀 从 WSOL 账户 header_accounts[2] 解包 `lamports` 给 payer，供以原生 SOL 计价的 CPI 使用
+///
+/// SPL Token 无法部分解包：先把 `lamports` WSOL 转入 payer 持有的原生 WSOL 临时账户
+/// `scratch_account`，再关闭该账户，其全部 lamports（含租金）退回 payer。
+/// 临时账户关闭后不能再用，同一笔交易中每次解包需要单独的临时账户。
+pub fn unwrap_sol_via(lamports: u64, scratch_account: &AccountInfo, header_accounts: &[AccountInfo]) -> ProgramResult {
+    let payer = &header_accounts[0];
+    let base_account = &header_accounts[2];
+    let token_program = &header_accounts[3];
+
+    let account_metas = [
+        AccountMeta::writable(base_account.key()),    // source
+        AccountMeta::writable(scratch_account.key()), // destination
+        AccountMeta::readonly_signer(payer.key()),    // owner
+    ];
+
+    let mut instruction_data = TOKEN_TRANSFER_INSTRUCTION_DATA;
+    instruction_data[1..9].copy_from_slice(&lamports.to_le_bytes());
+
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    invoke::<3>(&instruction, &[base_account, scratch_account, payer])?;
+
+    let account_metas = [
+        AccountMeta::writable(scratch_account.key()), // account
+        AccountMeta::writable(payer.key()),           // destination
+        AccountMeta::readonly_signer(payer.key()),    // owner
+    ];
+
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: &account_metas,
+        data: &CLOSE_ACCOUNT_INSTRUCTION_DATA,
+    };
+
+    invoke::<3>(&instruction, &[scratch_account, payer, payer])
+}
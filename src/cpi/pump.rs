@@ -3,16 +3,27 @@ use pinocchio::{
     ProgramResult
 };
 use crate::error::PinocchioCpiError;
+use crate::utils::TokenSlot;
 
 // const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
 // const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+// const BUY_EXACT_QUOTE_IN_DISCRIMINATOR: [u8; 8] = [198, 46, 21, 82, 180, 217, 232, 112];
 const PUMP_INSTRUCTION_DATA_BUY: [u8; 24] = [
     // buy discriminator [0..8]
     102, 6, 61, 18, 1, 218, 235, 234,
     // base_amount_out placeholder [8..16] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
-    // max_quote_amount_in = u64::MAX [16..24]
-    255, 255, 255, 255, 255, 255, 255, 255,
+    // max_quote_amount_in placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const PUMP_INSTRUCTION_DATA_BUY_EXACT_QUOTE_IN: [u8; 24] = [
+    // buy_exact_quote_in discriminator [0..8]
+    198, 46, 21, 82, 180, 217, 232, 112,
+    // spendable_quote_in placeholder [8..16] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // min_base_amount_out placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
 const PUMP_INSTRUCTION_DATA_SELL: [u8; 24] = [
@@ -20,20 +31,21 @@ const PUMP_INSTRUCTION_DATA_SELL: [u8; 24] = [
     51, 230, 133, 164, 1, 127, 131, 173,
     // base_amount_in placeholder [8..16] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
-    // min_quote_amount_out = 0 [16..24]
+    // min_quote_amount_out placeholder [16..24] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
 
 pub fn execute_pump_swap(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     pump_accounts: &[AccountInfo],
     is_buy: bool,
-    is_wsol_base: bool, // WSOL是否为base mint
+    is_base_asset_base: bool, // 基础资产是否为base mint
     pump_base_amount_out: u64,
 ) -> ProgramResult {
-    // 根据WSOL位置确定base/quote mint和program
+    // 根据基础资产位置确定base/quote mint和program
     let (
         base_mint,
         quote_mint,
@@ -41,30 +53,31 @@ pub fn execute_pump_swap(
         quote_token_program,
         user_base_token_account,
         user_quote_token_account,
-    ) = if is_wsol_base {
+    ) = if is_base_asset_base {
         (
-            &header_accounts[1], // wsol_mint
+            &header_accounts[1], // base_asset_mint
             &header_accounts[6], // token_mint
             &header_accounts[3], // token_program
             &header_accounts[7], // token_program_for_mint
-            &header_accounts[2], // wsol_token_account
+            &header_accounts[2], // base_asset_account
             &header_accounts[8], // mint_token_account
         )
     } else {
         (
             &header_accounts[6], // token_mint
-            &header_accounts[1], // wsol_mint
+            &header_accounts[1], // base_asset_mint
             &header_accounts[7], // token_program_for_mint
             &header_accounts[3], // token_program
             &header_accounts[8], // mint_token_account
-            &header_accounts[2], // wsol_token_account
+            &header_accounts[2], // base_asset_account
         )
     };
     if is_buy {
-        //wosl->token
-        if is_wsol_base {
+        //基础资产->token
+        if is_base_asset_base {
             pump_sell(
                 trade_amount,
+                min_out,
                 header_accounts,
                 pump_accounts,
                 base_mint,
@@ -75,8 +88,9 @@ pub fn execute_pump_swap(
                 quote_token_program,
             )
         } else {
-            // let trade_amount = pump_base_amount_out;
             pump_buy(
+                trade_amount,
+                min_out,
                 pump_base_amount_out,
                 header_accounts,
                 pump_accounts,
@@ -89,10 +103,13 @@ pub fn execute_pump_swap(
             )
         }
     } else {
-        //token->wosl
-        if is_wsol_base { // token->wsol 但是wsol是base情况走不通。不止base out amt 草泥马
+        //token->基础资产
+        if is_base_asset_base {
+            // 基础资产是base，token是quote，所以是buy操作（用quote买base）
             pump_buy(
                 trade_amount,
+                min_out,
+                0, // 用全部token精确买入基础资产 (buy_exact_quote_in)
                 header_accounts,
                 pump_accounts,
                 base_mint,
@@ -105,6 +122,7 @@ pub fn execute_pump_swap(
         } else {
             pump_sell(
                 trade_amount,
+                min_out,
                 header_accounts,
                 pump_accounts,
                 base_mint,
@@ -118,8 +136,11 @@ pub fn execute_pump_swap(
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::useless_conversion)]
 fn pump_buy(
     trade_amount: u64,
+    min_out: u64,
+    base_amount_out: u64,
     header_accounts: &[AccountInfo],
     pump_accounts: &[AccountInfo],
     base_mint: &AccountInfo,
@@ -155,10 +176,19 @@ fn pump_buy(
         AccountMeta::readonly(pump_accounts[14].key()), // fee_config
         AccountMeta::readonly(pump_accounts[15].key()), // fee_program
     ];
-    // 🚀 优化：预构建模板，只替换变量部分
-    let mut instruction_data = PUMP_INSTRUCTION_DATA_BUY;
-    
-    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes()); 
+    // 🚀 base_amount_out为0时用buy_exact_quote_in精确花费trade_amount，
+    // 否则按base_amount_out买入，且花费的quote不超过trade_amount
+    let instruction_data = if base_amount_out == 0 {
+        let mut data = PUMP_INSTRUCTION_DATA_BUY_EXACT_QUOTE_IN;
+        data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+        data[16..24].copy_from_slice(&min_out.to_le_bytes());
+        data
+    } else {
+        let mut data = PUMP_INSTRUCTION_DATA_BUY;
+        data[8..16].copy_from_slice(&base_amount_out.to_le_bytes());
+        data[16..24].copy_from_slice(&trade_amount.to_le_bytes());
+        data
+    };
 
     let swap_instruction = Instruction {
         program_id: pump_accounts[0].key(),
@@ -193,8 +223,10 @@ fn pump_buy(
     invoke::<23>(&swap_instruction, &account_infos).map_err(|e| e.into())
 }
 
+#[allow(clippy::too_many_arguments, clippy::useless_conversion)]
 fn pump_sell(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     pump_accounts: &[AccountInfo],
     base_mint: &AccountInfo,
@@ -232,6 +264,7 @@ fn pump_sell(
     // 🚀 优化：预构建模板，只替换变量部分
     let mut instruction_data = PUMP_INSTRUCTION_DATA_SELL;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: pump_accounts[0].key(),
@@ -267,23 +300,73 @@ fn pump_sell(
 }
 
 
+/// N-hop路由中的通用交换：input -> output
+/// is_zero_to_one 表示输入token是否为base mint：是则sell，否则用quote买base
+#[allow(clippy::too_many_arguments)]
+pub fn execute_pump_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    pump_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_zero_to_one: bool,
+    pump_base_amount_out: u64,
+) -> ProgramResult {
+    let (base, quote) = if is_zero_to_one {
+        (input, output)
+    } else {
+        (output, input)
+    };
+
+    if is_zero_to_one {
+        pump_sell(
+            trade_amount,
+            min_out,
+            header_accounts,
+            pump_accounts,
+            base.mint,
+            quote.mint,
+            base.account,
+            quote.account,
+            base.program,
+            quote.program,
+        )
+    } else {
+        pump_buy(
+            trade_amount,
+            min_out,
+            pump_base_amount_out,
+            header_accounts,
+            pump_accounts,
+            base.mint,
+            quote.mint,
+            base.account,
+            quote.account,
+            base.program,
+            quote.program,
+        )
+    }
+}
+
 pub fn execute_pump_swap_hop3(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     pump_accounts: &[AccountInfo],
     step: u8,
-    is_wsol_base: bool,
+    is_base_asset_base: bool,
     pump_base_amount_out: u64,
 ) -> ProgramResult {
     match step {
         1 => {
-            execute_pump_swap(trade_amount, header_accounts, pump_accounts, true, is_wsol_base, pump_base_amount_out)
+            execute_pump_swap(trade_amount, min_out, header_accounts, pump_accounts, true, is_base_asset_base, pump_base_amount_out)
         }
         2 => {
-            Err(PinocchioCpiError::PumpNotSupported.into())
+            execute_pump_swap_mid(trade_amount, min_out, header_accounts, pump_accounts, is_base_asset_base, pump_base_amount_out)
         }
         3 => {
-            execute_pump_swap_sell(trade_amount, header_accounts, pump_accounts, is_wsol_base, pump_base_amount_out)
+            execute_pump_swap_sell(trade_amount, min_out, header_accounts, pump_accounts, is_base_asset_base, pump_base_amount_out)
         }
         _ => {
             Err(PinocchioCpiError::UnsupportedPoolType.into())
@@ -291,18 +374,63 @@ pub fn execute_pump_swap_hop3(
     }
 }
 
+fn execute_pump_swap_mid(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    pump_accounts: &[AccountInfo],
+    is_mid_zero_to_one: bool,
+    pump_base_amount_out: u64,
+) -> ProgramResult {
+    // 中间交换：Token1 -> Token2
+    // 输入：header_accounts[8] (token1_account)
+    // 输出：header_accounts[11] (token2_account)
+
+    if is_mid_zero_to_one {
+        // Token1是base，Token2是quote，所以是sell操作（卖base得quote）
+        pump_sell_3hop(
+            trade_amount,
+            min_out,
+            header_accounts,
+            pump_accounts,
+            &header_accounts[6],  // token1_mint (base)
+            &header_accounts[9],  // token2_mint (quote)
+            &header_accounts[8],  // token1_account
+            &header_accounts[11], // token2_account
+            &header_accounts[7],  // token1_program
+            &header_accounts[10], // token2_program
+        )
+    } else {
+        // Token1是quote，Token2是base，所以是buy操作（用quote买base）
+        pump_buy_3hop(
+            trade_amount,
+            min_out,
+            pump_base_amount_out,
+            header_accounts,
+            pump_accounts,
+            &header_accounts[9],  // token2_mint (base)
+            &header_accounts[6],  // token1_mint (quote)
+            &header_accounts[11], // token2_account
+            &header_accounts[8],  // token1_account
+            &header_accounts[10], // token2_program
+            &header_accounts[7],  // token1_program
+        )
+    }
+}
+
 fn execute_pump_swap_sell(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     pump_accounts: &[AccountInfo],
-    is_wsol_base: bool,
+    is_base_asset_base: bool,
     _pump_base_amount_out: u64,
 ) -> ProgramResult {
-    // 3hop卖出交换：Token2 -> WSOL
+    // 3hop卖出交换：Token2 -> 基础资产
     // 输入：header_accounts[11] (token2_account)
-    // 输出：header_accounts[2] (wsol_account)
+    // 输出：header_accounts[2] (base_asset_account)
     
-    // 根据WSOL位置确定base/quote mint和program
+    // 根据基础资产位置确定base/quote mint和program
     let (
         base_mint,
         quote_mint,
@@ -310,31 +438,33 @@ fn execute_pump_swap_sell(
         quote_token_program,
         user_base_token_account,
         user_quote_token_account,
-    ) = if is_wsol_base {
+    ) = if is_base_asset_base {
         (
-            &header_accounts[1],  // wsol_mint
+            &header_accounts[1],  // base_asset_mint
             &header_accounts[9],  // token2_mint (注意：3hop中token2在位置9)
             &header_accounts[3],  // token_program
             &header_accounts[10], // token2_program (注意：3hop中token2_program在位置10)
-            &header_accounts[2],  // wsol_token_account
+            &header_accounts[2],  // base_asset_account
             &header_accounts[11], // token2_account (注意：3hop中token2_account在位置11)
         )
     } else {
         (
             &header_accounts[9],  // token2_mint
-            &header_accounts[1],  // wsol_mint
+            &header_accounts[1],  // base_asset_mint
             &header_accounts[10], // token2_program
             &header_accounts[3],  // token_program
             &header_accounts[11], // token2_account
-            &header_accounts[2],  // wsol_token_account
+            &header_accounts[2],  // base_asset_account
         )
     };
 
-    // Token2 -> WSOL 的逻辑
-    if is_wsol_base {
-        // WSOL是base，Token2是quote，所以是buy操作（用quote买base）
+    // Token2 -> 基础资产 的逻辑
+    if is_base_asset_base {
+        // 基础资产是base，Token2是quote，所以是buy操作（用quote买base）
         pump_buy_3hop(
             trade_amount,
+            min_out,
+            0, // 用全部token精确买入基础资产 (buy_exact_quote_in)
             header_accounts,
             pump_accounts,
             base_mint,
@@ -345,9 +475,10 @@ fn execute_pump_swap_sell(
             quote_token_program,
         )
     } else {
-        // WSOL是quote，Token2是base，所以是sell操作（卖base得quote）
+        // 基础资产是quote，Token2是base，所以是sell操作（卖base得quote）
         pump_sell_3hop(
             trade_amount,
+            min_out,
             header_accounts,
             pump_accounts,
             base_mint,
@@ -360,8 +491,11 @@ fn execute_pump_swap_sell(
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::useless_conversion)]
 fn pump_buy_3hop(
     trade_amount: u64,
+    min_out: u64,
+    base_amount_out: u64,
     header_accounts: &[AccountInfo],
     pump_accounts: &[AccountInfo],
     base_mint: &AccountInfo,
@@ -398,8 +532,19 @@ fn pump_buy_3hop(
         AccountMeta::readonly(pump_accounts[15].key()), // fee_program
     ];
 
-    let mut instruction_data = PUMP_INSTRUCTION_DATA_BUY;
-    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes()); 
+    // 🚀 base_amount_out为0时用buy_exact_quote_in精确花费trade_amount，
+    // 否则按base_amount_out买入，且花费的quote不超过trade_amount
+    let instruction_data = if base_amount_out == 0 {
+        let mut data = PUMP_INSTRUCTION_DATA_BUY_EXACT_QUOTE_IN;
+        data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+        data[16..24].copy_from_slice(&min_out.to_le_bytes());
+        data
+    } else {
+        let mut data = PUMP_INSTRUCTION_DATA_BUY;
+        data[8..16].copy_from_slice(&base_amount_out.to_le_bytes());
+        data[16..24].copy_from_slice(&trade_amount.to_le_bytes());
+        data
+    };
 
     let swap_instruction = Instruction {
         program_id: pump_accounts[0].key(),
@@ -436,8 +581,10 @@ fn pump_buy_3hop(
     invoke::<23>(&swap_instruction, &account_infos).map_err(|e| e.into())
 }
 
+#[allow(clippy::too_many_arguments, clippy::useless_conversion)]
 fn pump_sell_3hop(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     pump_accounts: &[AccountInfo],
     base_mint: &AccountInfo,
@@ -474,6 +621,7 @@ fn pump_sell_3hop(
 
     let mut instruction_data = PUMP_INSTRUCTION_DATA_SELL;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: pump_accounts[0].key(),
@@ -0,0 +1,302 @@
+use pinocchio::{
+    account_info::AccountInfo, cpi::invoke, instruction::AccountMeta, instruction::Instruction,
+    ProgramResult
+};
+use crate::cpi::native_sol;
+use crate::error::PinocchioCpiError;
+use crate::utils::TokenSlot;
+
+// Pump.fun 联合曲线（迁移前的 token），报价资产为原生 SOL
+const PUMP_FUN_INSTRUCTION_DATA_BUY: [u8; 24] = [
+    // buy discriminator [0..8]
+    102, 6, 61, 18, 1, 218, 235, 234,
+    // amount (token) placeholder [8..16] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // max_sol_cost placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const PUMP_FUN_INSTRUCTION_DATA_BUY_EXACT_SOL_IN: [u8; 24] = [
+    // buy_exact_sol_in discriminator [0..8]
+    56, 252, 116, 8, 158, 223, 205, 95,
+    // spendable_sol_in placeholder [8..16] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // min_tokens_out placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const PUMP_FUN_INSTRUCTION_DATA_SELL: [u8; 24] = [
+    // sell discriminator [0..8]
+    51, 230, 133, 164, 1, 127, 131, 173,
+    // amount (token) placeholder [8..16] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // min_sol_output placeholder [16..24] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// 🚀 Pump.fun 联合曲线 (pool type 8)，基础资产 header_accounts[2] 必须是 WSOL。
+///
+/// 联合曲线直接收付 payer 的 lamports：买入前通过临时账户从 header_accounts[2] 解包
+/// `trade_amount`，买入后把未花完的 SOL 包装回去；卖出后把收到的 SOL 包装进 header_accounts[2]。
+/// 账户顺序：[0] program, [1] global, [2] fee_recipient, [3] bonding_curve,
+/// [4] associated_bonding_curve, [5] creator_vault, [6] event_authority,
+/// [7] global_volume_accumulator, [8] user_volume_accumulator, [9] fee_config,
+/// [10] fee_program, [11] system_program, [12] 解包用的临时 WSOL 账户（仅买入跳传入，买入时关闭）
+///
+/// 卖出跳只有前 PUMP_FUN_SELL_ACCOUNT_COUNT 个账户。临时账户关闭后不能再用，
+/// 因此每条路由最多一个 Pump.fun 买入跳（见 `check_pump_fun_buy_count`）。
+///
+/// 联合曲线没有 token0 / token1 之分，方向只由 `is_buy` 决定
+pub fn execute_pump_fun_swap(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    pump_fun_accounts: &[AccountInfo],
+    is_buy: bool,
+    pump_base_amount_out: u64,
+) -> ProgramResult {
+    if is_buy {
+        // SOL -> token
+        pump_fun_buy(
+            trade_amount,
+            min_out,
+            pump_base_amount_out,
+            header_accounts,
+            pump_fun_accounts,
+            &header_accounts[6], // token_mint
+            &header_accounts[7], // token_program
+            &header_accounts[8], // token_account
+        )
+    } else {
+        // token -> SOL
+        pump_fun_sell(
+            trade_amount,
+            min_out,
+            header_accounts,
+            pump_fun_accounts,
+            &header_accounts[6], // token_mint
+            &header_accounts[7], // token_program
+            &header_accounts[8], // token_account
+        )
+    }
+}
+
+pub fn execute_pump_fun_swap_hop3(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    pump_fun_accounts: &[AccountInfo],
+    step: u8,
+    pump_base_amount_out: u64,
+) -> ProgramResult {
+    match step {
+        1 => {
+            execute_pump_fun_swap(trade_amount, min_out, header_accounts, pump_fun_accounts, true, pump_base_amount_out)
+        }
+        // 联合曲线的报价资产固定为 SOL，无法用于 Token1 -> Token2
+        2 => {
+            Err(PinocchioCpiError::PumpNotSupported.into())
+        }
+        3 => {
+            pump_fun_sell(
+                trade_amount,
+                min_out,
+                header_accounts,
+                pump_fun_accounts,
+                &header_accounts[9],  // token2_mint
+                &header_accounts[10], // token2_program
+                &header_accounts[11], // token2_account
+            )
+        }
+        _ => {
+            Err(PinocchioCpiError::UnsupportedPoolType.into())
+        }
+    }
+}
+
+/// N-hop路由中的通用交换：输入为基础资产时买入，输出为基础资产时卖出
+pub fn execute_pump_fun_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    pump_fun_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    pump_base_amount_out: u64,
+) -> ProgramResult {
+    let base_account = header_accounts[2].key();
+
+    if input.account.key() == base_account {
+        pump_fun_buy(
+            trade_amount,
+            min_out,
+            pump_base_amount_out,
+            header_accounts,
+            pump_fun_accounts,
+            output.mint,
+            output.program,
+            output.account,
+        )
+    } else if output.account.key() == base_account {
+        pump_fun_sell(
+            trade_amount,
+            min_out,
+            header_accounts,
+            pump_fun_accounts,
+            input.mint,
+            input.program,
+            input.account,
+        )
+    } else {
+        Err(PinocchioCpiError::PumpNotSupported.into())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn pump_fun_buy(
+    trade_amount: u64,
+    min_out: u64,
+    token_amount_out: u64,
+    header_accounts: &[AccountInfo],
+    pump_fun_accounts: &[AccountInfo],
+    mint: &AccountInfo,
+    token_program: &AccountInfo,
+    user_token_account: &AccountInfo,
+) -> ProgramResult {
+    let user = &header_accounts[0];
+
+    // 🚀 解包 trade_amount 给 payer，联合曲线从 payer 的 lamports 扣款
+    native_sol::unwrap_sol_via(trade_amount, &pump_fun_accounts[12], header_accounts)?;
+    let lamports_before = user.lamports();
+    let accumulator_lamports_before = pump_fun_accounts[8].lamports();
+
+    // 买入：buy / buy_exact_sol_in (16个账户)
+    let account_metas = [
+        AccountMeta::readonly(pump_fun_accounts[1].key()),  // global
+        AccountMeta::writable(pump_fun_accounts[2].key()),  // fee_recipient
+        AccountMeta::readonly(mint.key()),                  // mint
+        AccountMeta::writable(pump_fun_accounts[3].key()),  // bonding_curve
+        AccountMeta::writable(pump_fun_accounts[4].key()),  // associated_bonding_curve
+        AccountMeta::writable(user_token_account.key()),    // associated_user
+        AccountMeta::writable_signer(user.key()),           // user (signer)
+        AccountMeta::readonly(pump_fun_accounts[11].key()), // system_program
+        AccountMeta::readonly(token_program.key()),         // token_program
+        AccountMeta::writable(pump_fun_accounts[5].key()),  // creator_vault
+        AccountMeta::readonly(pump_fun_accounts[6].key()),  // event_authority
+        AccountMeta::readonly(pump_fun_accounts[0].key()),  // program
+        AccountMeta::writable(pump_fun_accounts[7].key()),  // global_volume_accumulator
+        AccountMeta::writable(pump_fun_accounts[8].key()),  // user_volume_accumulator
+        AccountMeta::readonly(pump_fun_accounts[9].key()),  // fee_config
+        AccountMeta::readonly(pump_fun_accounts[10].key()), // fee_program
+    ];
+
+    // 🚀 token_amount_out为0时用buy_exact_sol_in精确花费trade_amount，
+    // 否则按token_amount_out买入，且花费的SOL不超过trade_amount
+    let instruction_data = if token_amount_out == 0 {
+        let mut data = PUMP_FUN_INSTRUCTION_DATA_BUY_EXACT_SOL_IN;
+        data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+        data[16..24].copy_from_slice(&min_out.to_le_bytes());
+        data
+    } else {
+        let mut data = PUMP_FUN_INSTRUCTION_DATA_BUY;
+        data[8..16].copy_from_slice(&token_amount_out.to_le_bytes());
+        data[16..24].copy_from_slice(&trade_amount.to_le_bytes());
+        data
+    };
+
+    let swap_instruction = Instruction {
+        program_id: pump_fun_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        &pump_fun_accounts[1],  // global
+        &pump_fun_accounts[2],  // fee_recipient
+        mint,                   // mint
+        &pump_fun_accounts[3],  // bonding_curve
+        &pump_fun_accounts[4],  // associated_bonding_curve
+        user_token_account,     // associated_user
+        user,                   // user
+        &pump_fun_accounts[11], // system_program
+        token_program,          // token_program
+        &pump_fun_accounts[5],  // creator_vault
+        &pump_fun_accounts[6],  // event_authority
+        &pump_fun_accounts[0],  // program
+        &pump_fun_accounts[7],  // global_volume_accumulator
+        &pump_fun_accounts[8],  // user_volume_accumulator
+        &pump_fun_accounts[9],  // fee_config
+        &pump_fun_accounts[10], // fee_program
+    ];
+
+    invoke::<16>(&swap_instruction, &account_infos)?;
+
+    // 🚀 未花完的SOL包装回基础资产账户，首次买入时 payer 为 user_volume_accumulator 支付的租金不算花费
+    let rent = pump_fun_accounts[8].lamports().saturating_sub(accumulator_lamports_before);
+    let spent = lamports_before.saturating_sub(user.lamports()).saturating_sub(rent);
+    native_sol::wrap_sol(trade_amount.saturating_sub(spent), header_accounts)
+}
+
+fn pump_fun_sell(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    pump_fun_accounts: &[AccountInfo],
+    mint: &AccountInfo,
+    token_program: &AccountInfo,
+    user_token_account: &AccountInfo,
+) -> ProgramResult {
+    let user = &header_accounts[0];
+    let lamports_before = user.lamports();
+
+    // 卖出：sell (14个账户)
+    let account_metas = [
+        AccountMeta::readonly(pump_fun_accounts[1].key()),  // global
+        AccountMeta::writable(pump_fun_accounts[2].key()),  // fee_recipient
+        AccountMeta::readonly(mint.key()),                  // mint
+        AccountMeta::writable(pump_fun_accounts[3].key()),  // bonding_curve
+        AccountMeta::writable(pump_fun_accounts[4].key()),  // associated_bonding_curve
+        AccountMeta::writable(user_token_account.key()),    // associated_user
+        AccountMeta::writable_signer(user.key()),           // user (signer)
+        AccountMeta::readonly(pump_fun_accounts[11].key()), // system_program
+        AccountMeta::writable(pump_fun_accounts[5].key()),  // creator_vault
+        AccountMeta::readonly(token_program.key()),         // token_program
+        AccountMeta::readonly(pump_fun_accounts[6].key()),  // event_authority
+        AccountMeta::readonly(pump_fun_accounts[0].key()),  // program
+        AccountMeta::readonly(pump_fun_accounts[9].key()),  // fee_config
+        AccountMeta::readonly(pump_fun_accounts[10].key()), // fee_program
+    ];
+
+    let mut instruction_data = PUMP_FUN_INSTRUCTION_DATA_SELL;
+    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
+
+    let swap_instruction = Instruction {
+        program_id: pump_fun_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        &pump_fun_accounts[1],  // global
+        &pump_fun_accounts[2],  // fee_recipient
+        mint,                   // mint
+        &pump_fun_accounts[3],  // bonding_curve
+        &pump_fun_accounts[4],  // associated_bonding_curve
+        user_token_account,     // associated_user
+        user,                   // user
+        &pump_fun_accounts[11], // system_program
+        &pump_fun_accounts[5],  // creator_vault
+        token_program,          // token_program
+        &pump_fun_accounts[6],  // event_authority
+        &pump_fun_accounts[0],  // program
+        &pump_fun_accounts[9],  // fee_config
+        &pump_fun_accounts[10], // fee_program
+    ];
+
+    invoke::<14>(&swap_instruction, &account_infos)?;
+
+    // 🚀 卖出所得的SOL包装进基础资产账户
+    let received = user.lamports().saturating_sub(lamports_before);
+    native_sol::wrap_sol(received, header_accounts)
+}
@@ -6,22 +6,36 @@ use pinocchio::{
     ProgramResult,
 };
 use crate::error::PinocchioCpiError;
+use crate::utils::TokenSlot;
 
 const RAYDIUM_INSTRUCTION_DATA: [u8; 17] = [
     // swap discriminator [0..1]
     9,
     // amount_in placeholder [1..9] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
-    // minimum_amount_out = 0 [9..17]
+    // minimum_amount_out placeholder [9..17] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
+/// `exact_out` 时使用 swap_base_out：`trade_amount` 为最大输入，`min_out` 为精确输出
+const RAYDIUM_SWAP_BASE_OUT_INSTRUCTION_DATA: [u8; 17] = [
+    // swap_base_out discriminator [0..1]
+    11,
+    // max_amount_in placeholder [1..9] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+    // amount_out placeholder [9..17] - 将被替换
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+#[allow(clippy::needless_borrow)]
 pub fn execute_raydium_swap(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     raydium_accounts: &[AccountInfo],
     is_buy: bool,
-    _is_wsol_pool_0: bool,
+    _is_base_pool_0: bool,
+    exact_out: bool,
 ) -> ProgramResult {
   
     let (
@@ -29,14 +43,14 @@ pub fn execute_raydium_swap(
         output_token_account,
     ) = if is_buy {
         (
-            &header_accounts[2],  // wsol_token_account
+            &header_accounts[2],  // base_token_account
             &header_accounts[8],  // mint_token_account
         
         )
     } else {
         (
             &header_accounts[8],  // mint_token_account
-            &header_accounts[2],  // wsol_token_account
+            &header_accounts[2],  // base_token_account
         )
     };
 
@@ -71,9 +85,10 @@ pub fn execute_raydium_swap(
     ];
 
     // 🚀 优化：预构建模板，只替换变量部分
-    let mut instruction_data = RAYDIUM_INSTRUCTION_DATA;
+    let mut instruction_data = if exact_out { RAYDIUM_SWAP_BASE_OUT_INSTRUCTION_DATA } else { RAYDIUM_INSTRUCTION_DATA };
       
     instruction_data[1..9].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[9..17].copy_from_slice(&min_out.to_le_bytes());
 
     // 🚀 优化5: 构建Pinocchio指令结构
     let swap_instruction = Instruction {
@@ -110,20 +125,22 @@ pub fn execute_raydium_swap(
 
 pub fn execute_raydium_swap_hop3(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     raydium_accounts: &[AccountInfo],
     step: u8,
-    is_wsol_pool_0: bool,
+    is_base_pool_0: bool,
+    exact_out: bool,
 ) -> ProgramResult {
     match step {
         1 => {
-            execute_raydium_swap(trade_amount, header_accounts, raydium_accounts, true, is_wsol_pool_0)
+            execute_raydium_swap(trade_amount, min_out, header_accounts, raydium_accounts, true, is_base_pool_0, exact_out)
         }
         2 => {
-            execute_raydium_swap_mid(trade_amount, header_accounts, raydium_accounts, is_wsol_pool_0)
+            execute_raydium_swap_mid(trade_amount, min_out, header_accounts, raydium_accounts, is_base_pool_0, exact_out)
         }
         3 => {
-            execute_raydium_swap_sell(trade_amount, header_accounts, raydium_accounts, is_wsol_pool_0)
+            execute_raydium_swap_sell(trade_amount, min_out, header_accounts, raydium_accounts, is_base_pool_0, exact_out)
         }
         _ => {
             Err(PinocchioCpiError::UnsupportedPoolType.into())
@@ -133,9 +150,11 @@ pub fn execute_raydium_swap_hop3(
 
 fn execute_raydium_swap_mid(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     raydium_accounts: &[AccountInfo],
     _is_mid_zero_to_one: bool,
+    exact_out: bool,
 ) -> ProgramResult {
     // 中间交换：Token1 -> Token2
     // 输入：header_accounts[8] (token1_account)
@@ -168,8 +187,9 @@ fn execute_raydium_swap_mid(
         AccountMeta::writable_signer(user_owner.key()),                // User Owner (signer)
     ];
 
-    let mut instruction_data = RAYDIUM_INSTRUCTION_DATA;
+    let mut instruction_data = if exact_out { RAYDIUM_SWAP_BASE_OUT_INSTRUCTION_DATA } else { RAYDIUM_INSTRUCTION_DATA };
     instruction_data[1..9].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[9..17].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: raydium_accounts[0].key(),
@@ -202,13 +222,15 @@ fn execute_raydium_swap_mid(
 
 fn execute_raydium_swap_sell(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     raydium_accounts: &[AccountInfo],
-    _is_wsol_pool_0: bool,
+    _is_base_pool_0: bool,
+    exact_out: bool,
 ) -> ProgramResult {
-    // 卖出交换：Token2 -> WSOL
+    // 卖出交换：Token2 -> 基础资产
     // 输入：header_accounts[11] (token2_account)
-    // 输出：header_accounts[2] (wsol_account)
+    // 输出：header_accounts[2] (base_account)
     
     let user_owner = &header_accounts[0];
     let token_program = &header_accounts[10];
@@ -233,12 +255,13 @@ fn execute_raydium_swap_sell(
         AccountMeta::writable(amm_account.key()),                      // Serum Pc Vault Account
         AccountMeta::writable(amm_account.key()),                      // Serum Vault Signer
         AccountMeta::writable(header_accounts[11].key()),              // User Source Token Account (token2)
-        AccountMeta::writable(header_accounts[2].key()),               // User Dest Token Account (wsol)
+        AccountMeta::writable(header_accounts[2].key()),               // User Dest Token Account (base)
         AccountMeta::writable_signer(user_owner.key()),                // User Owner (signer)
     ];
 
-    let mut instruction_data = RAYDIUM_INSTRUCTION_DATA;
+    let mut instruction_data = if exact_out { RAYDIUM_SWAP_BASE_OUT_INSTRUCTION_DATA } else { RAYDIUM_INSTRUCTION_DATA };
     instruction_data[1..9].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[9..17].copy_from_slice(&min_out.to_le_bytes());
 
     let swap_instruction = Instruction {
         program_id: raydium_accounts[0].key(),
@@ -262,9 +285,231 @@ fn execute_raydium_swap_sell(
         amm_account,              // Serum Pc Vault Account
         amm_account,              // Serum Vault Signer
         &header_accounts[11],     // User Source Token Account (token2)
-        &header_accounts[2],      // User Dest Token Account (wsol)
+        &header_accounts[2],      // User Dest Token Account (base)
         &header_accounts[0],      // User Owner
     ];
 
     invoke::<17>(&swap_instruction, &account_infos)
 }
+
+/// N-hop路由中的通用交换：input -> output
+/// Raydium AMM v4 根据用户账户自动判断方向，无需 is_zero_to_one
+#[allow(clippy::too_many_arguments)]
+pub fn execute_raydium_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    raydium_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    _is_zero_to_one: bool,
+    exact_out: bool,
+) -> ProgramResult {
+    let user_owner = &header_accounts[0];
+    let token_program = &header_accounts[3];
+    let amm_account = &raydium_accounts[2];
+    let authority = &raydium_accounts[1];
+    let pool_coin = &raydium_accounts[3];
+    let pool_pc = &raydium_accounts[4];
+
+    let account_metas = [
+        AccountMeta::readonly(token_program.key()),                    // token_program
+        AccountMeta::writable(amm_account.key()),                      // Amm Id
+        AccountMeta::readonly(authority.key()),                        // authority
+        AccountMeta::writable(amm_account.key()),                      // Amm Open Orders
+        AccountMeta::writable(pool_coin.key()),                        // Pool Coin Token Account
+        AccountMeta::writable(pool_pc.key()),                          // Pool Pc Token Account
+        AccountMeta::writable(amm_account.key()),                      // Serum Program Id
+        AccountMeta::writable(amm_account.key()),                      // Serum Market
+        AccountMeta::writable(amm_account.key()),                      // Serum Bids
+        AccountMeta::writable(amm_account.key()),                      // Serum Asks
+        AccountMeta::writable(amm_account.key()),                      // Serum Event Queue
+        AccountMeta::writable(amm_account.key()),                      // Serum Coin Vault Account
+        AccountMeta::writable(amm_account.key()),                      // Serum Pc Vault Account
+        AccountMeta::writable(amm_account.key()),                      // Serum Vault Signer
+        AccountMeta::writable(input.account.key()),                    // User Source Token Account
+        AccountMeta::writable(output.account.key()),                   // User Dest Token Account
+        AccountMeta::writable_signer(user_owner.key()),                // User Owner (signer)
+    ];
+
+    let mut instruction_data = if exact_out { RAYDIUM_SWAP_BASE_OUT_INSTRUCTION_DATA } else { RAYDIUM_INSTRUCTION_DATA };
+    instruction_data[1..9].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[9..17].copy_from_slice(&min_out.to_le_bytes());
+
+    let swap_instruction = Instruction {
+        program_id: raydium_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        token_program,            // token_program
+        amm_account,              // Amm Id
+        authority,                // authority
+        amm_account,              // Amm Open Orders
+        pool_coin,                // Pool Coin Token Account
+        pool_pc,                  // Pool Pc Token Account
+        amm_account,              // Serum Program Id
+        amm_account,              // Serum Market
+        amm_account,              // Serum Bids
+        amm_account,              // Serum Asks
+        amm_account,              // Serum Event Queue
+        amm_account,              // Serum Coin Vault Account
+        amm_account,              // Serum Pc Vault Account
+        amm_account,              // Serum Vault Signer
+        input.account,            // User Source Token Account
+        output.account,           // User Dest Token Account
+        user_owner,               // User Owner
+    ];
+
+    invoke::<17>(&swap_instruction, &account_infos)
+}
+
+/// 🚀 Raydium AMM v4 + OpenBook 模式 (pool type 7)：传入真实的 open orders / Serum 账户，
+/// 适用于仍然校验 OpenBook 市场账户的旧池。账户顺序：
+/// [0] program, [1] authority, [2] amm, [3] pool coin, [4] pool pc, [5] open orders,
+/// [6] serum program, [7] market, [8] bids, [9] asks, [10] event queue,
+/// [11] serum coin vault, [12] serum pc vault, [13] vault signer
+pub fn execute_raydium_openbook_swap(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    raydium_accounts: &[AccountInfo],
+    is_buy: bool,
+    _is_base_pool_0: bool,
+    exact_out: bool,
+) -> ProgramResult {
+    let (input_token_account, output_token_account) = if is_buy {
+        (&header_accounts[2], &header_accounts[8])
+    } else {
+        (&header_accounts[8], &header_accounts[2])
+    };
+
+    invoke_raydium_openbook_swap(
+        trade_amount,
+        min_out,
+        header_accounts,
+        raydium_accounts,
+        input_token_account,
+        output_token_account,
+        exact_out,
+    )
+}
+
+pub fn execute_raydium_openbook_swap_hop3(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    raydium_accounts: &[AccountInfo],
+    step: u8,
+    _is_base_pool_0: bool,
+    exact_out: bool,
+) -> ProgramResult {
+    // 1: 基础资产 -> Token1, 2: Token1 -> Token2, 3: Token2 -> 基础资产
+    let (input_token_account, output_token_account) = match step {
+        1 => (&header_accounts[2], &header_accounts[8]),
+        2 => (&header_accounts[8], &header_accounts[11]),
+        3 => (&header_accounts[11], &header_accounts[2]),
+        _ => return Err(PinocchioCpiError::UnsupportedPoolType.into()),
+    };
+
+    invoke_raydium_openbook_swap(
+        trade_amount,
+        min_out,
+        header_accounts,
+        raydium_accounts,
+        input_token_account,
+        output_token_account,
+        exact_out,
+    )
+}
+
+/// N-hop路由中的通用交换：input -> output，方向同样由用户账户决定
+#[allow(clippy::too_many_arguments)]
+pub fn execute_raydium_openbook_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    raydium_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    _is_zero_to_one: bool,
+    exact_out: bool,
+) -> ProgramResult {
+    invoke_raydium_openbook_swap(
+        trade_amount,
+        min_out,
+        header_accounts,
+        raydium_accounts,
+        input.account,
+        output.account,
+        exact_out,
+    )
+}
+
+#[inline(always)]
+fn invoke_raydium_openbook_swap(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    raydium_accounts: &[AccountInfo],
+    input_token_account: &AccountInfo,
+    output_token_account: &AccountInfo,
+    exact_out: bool,
+) -> ProgramResult {
+    // Raydium AMM v4 只支持 SPL Token
+    let token_program = &header_accounts[3];
+    let user_owner = &header_accounts[0];
+
+    let account_metas = [
+        AccountMeta::readonly(token_program.key()),                    // token_program
+        AccountMeta::writable(raydium_accounts[2].key()),              // Amm Id
+        AccountMeta::readonly(raydium_accounts[1].key()),              // authority
+        AccountMeta::writable(raydium_accounts[5].key()),              // Amm Open Orders
+        AccountMeta::writable(raydium_accounts[3].key()),              // Pool Coin Token Account
+        AccountMeta::writable(raydium_accounts[4].key()),              // Pool Pc Token Account
+        AccountMeta::readonly(raydium_accounts[6].key()),              // Serum Program Id
+        AccountMeta::writable(raydium_accounts[7].key()),              // Serum Market
+        AccountMeta::writable(raydium_accounts[8].key()),              // Serum Bids
+        AccountMeta::writable(raydium_accounts[9].key()),              // Serum Asks
+        AccountMeta::writable(raydium_accounts[10].key()),             // Serum Event Queue
+        AccountMeta::writable(raydium_accounts[11].key()),             // Serum Coin Vault Account
+        AccountMeta::writable(raydium_accounts[12].key()),             // Serum Pc Vault Account
+        AccountMeta::readonly(raydium_accounts[13].key()),             // Serum Vault Signer
+        AccountMeta::writable(input_token_account.key()),              // User Source Token Account
+        AccountMeta::writable(output_token_account.key()),             // User Dest Token Account
+        AccountMeta::writable_signer(user_owner.key()),                // User Owner (signer)
+    ];
+
+    let mut instruction_data = if exact_out { RAYDIUM_SWAP_BASE_OUT_INSTRUCTION_DATA } else { RAYDIUM_INSTRUCTION_DATA };
+    instruction_data[1..9].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[9..17].copy_from_slice(&min_out.to_le_bytes());
+
+    let swap_instruction = Instruction {
+        program_id: raydium_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        token_program,            // token_program
+        &raydium_accounts[2],     // Amm Id
+        &raydium_accounts[1],     // authority
+        &raydium_accounts[5],     // Amm Open Orders
+        &raydium_accounts[3],     // Pool Coin Token Account
+        &raydium_accounts[4],     // Pool Pc Token Account
+        &raydium_accounts[6],     // Serum Program Id
+        &raydium_accounts[7],     // Serum Market
+        &raydium_accounts[8],     // Serum Bids
+        &raydium_accounts[9],     // Serum Asks
+        &raydium_accounts[10],    // Serum Event Queue
+        &raydium_accounts[11],    // Serum Coin Vault Account
+        &raydium_accounts[12],    // Serum Pc Vault Account
+        &raydium_accounts[13],    // Serum Vault Signer
+        input_token_account,      // User Source Token Account
+        output_token_account,     // User Dest Token Account
+        user_owner,               // User Owner
+    ];
+
+    invoke::<17>(&swap_instruction, &account_infos)
+}
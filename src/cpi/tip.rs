@@ -0,0 +1,22 @@
+use pinocchio::{account_info::AccountInfo, ProgramResult};
+use crate::cpi::native_sol;
+use crate::utils;
+
+/// 🚀 从已实现的利润中向 tip 账户支付小费（payer 的原生 SOL）
+///
+/// 只在利润校验通过后调用，亏损的交易不会支付小费。
+/// 小费按基础资产的利润计算，入口处已由 `utils::check_tip` 保证基础资产为 WSOL，与 lamports 一一对应。
+pub fn pay_tip(
+    profit: u64,
+    tip_lamports: u64,
+    tip_bps: u16,
+    header_accounts: &[AccountInfo],
+    tip_account: &AccountInfo,
+) -> ProgramResult {
+    let lamports = utils::compute_tip(profit, tip_lamports, tip_bps);
+    if lamports == 0 {
+        return Ok(());
+    }
+
+    native_sol::transfer_lamports(&header_accounts[0], tip_account, lamports)
+}
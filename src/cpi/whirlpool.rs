@@ -3,13 +3,14 @@ use pinocchio::{
     ProgramResult,
 };
 use crate::error::PinocchioCpiError;
+use crate::utils::TokenSlot;
 
 const WHIRLPOOL_INSTRUCTION_DATA: [u8; 43] = [
     // swapV2 discriminator [0..8]
     43, 4, 237, 11, 26, 201, 30, 98,
     // amount placeholder [8..16] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
-    // otherAmountThreshold = 0 [16..24]
+    // otherAmountThreshold placeholder [16..24] - 将被替换
     0, 0, 0, 0, 0, 0, 0, 0,
     // sqrtPriceLimit = 0 [24..40]
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -23,10 +24,11 @@ const WHIRLPOOL_INSTRUCTION_DATA: [u8; 43] = [
 
 pub fn execute_whirlpool_swap(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     whirlpool_accounts: &[AccountInfo],
     is_buy: bool,
-    is_wsol_token_a: bool, // WSOL是否为token_a
+    is_base_token_a: bool, // 基础资产是否为token_a
 ) -> ProgramResult {
     let (
         token_program_a,
@@ -37,49 +39,49 @@ pub fn execute_whirlpool_swap(
         mint_b,
         a_to_b,
     ) = if is_buy {
-        // 买入：WSOL -> Token
-        if is_wsol_token_a {
+        // 买入：基础资产 -> Token
+        if is_base_token_a {
             (
                 &header_accounts[3], // token_program
                 &header_accounts[7], // token_program_for_mint
-                &header_accounts[2], // wsol_token_account
+                &header_accounts[2], // base_token_account
                 &header_accounts[8], // mint_token_account
-                &header_accounts[1], // wsol_mint
+                &header_accounts[1], // base_mint
                 &header_accounts[6], // token_mint
-                true, // a_to_b = true (WSOL is token A)
+                true, // a_to_b = true (base is token A)
             )
         } else {
             (
                 &header_accounts[7], // token_program_for_mint
                 &header_accounts[3], // token_program
                 &header_accounts[8], // mint_token_account
-                &header_accounts[2], // wsol_token_account
+                &header_accounts[2], // base_token_account
                 &header_accounts[6], // token_mint
-                &header_accounts[1], // wsol_mint
-                false, // a_to_b = false (WSOL is token B)
+                &header_accounts[1], // base_mint
+                false, // a_to_b = false (base is token B)
             )
         }
     } else {
-        // 卖出：Token -> WSOL
-        if is_wsol_token_a {
+        // 卖出：Token -> 基础资产
+        if is_base_token_a {
             (
                 &header_accounts[3], // token_program
                 &header_accounts[7], // token_program_for_mint
-                &header_accounts[2], // wsol_token_account
+                &header_accounts[2], // base_token_account
                 &header_accounts[8], // mint_token_account
-                &header_accounts[1], // wsol_mint
+                &header_accounts[1], // base_mint
                 &header_accounts[6], // token_mint
-                false, // a_to_b = false (Token B -> WSOL A)
+                false, // a_to_b = false (Token B -> base A)
             )
         } else {
             (
                 &header_accounts[7], // token_program_for_mint
                 &header_accounts[3], // token_program
                 &header_accounts[8], // mint_token_account
-                &header_accounts[2], // wsol_token_account
+                &header_accounts[2], // base_token_account
                 &header_accounts[6], // token_mint
-                &header_accounts[1], // wsol_mint
-                true, // a_to_b = true (Token A -> WSOL B)
+                &header_accounts[1], // base_mint
+                true, // a_to_b = true (Token A -> base B)
             )
         }
     };
@@ -106,6 +108,7 @@ pub fn execute_whirlpool_swap(
     // 🚀 优化：预构建模板，只替换变量部分
     let mut instruction_data = WHIRLPOOL_INSTRUCTION_DATA;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
     instruction_data[41] = if a_to_b { 1 } else { 0 };
 
     let swap_instruction = Instruction {
@@ -137,20 +140,21 @@ pub fn execute_whirlpool_swap(
 
 pub fn execute_whirlpool_swap_hop3(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     whirlpool_accounts: &[AccountInfo],
     step: u8,
-    is_wsol_token_a: bool,
+    is_base_token_a: bool,
 ) -> ProgramResult {
     match step {
         1 => {
-            execute_whirlpool_swap(trade_amount, header_accounts, whirlpool_accounts, true, is_wsol_token_a)
+            execute_whirlpool_swap(trade_amount, min_out, header_accounts, whirlpool_accounts, true, is_base_token_a)
         }
         2 => {
-            execute_whirlpool_swap_mid(trade_amount, header_accounts, whirlpool_accounts, is_wsol_token_a)
+            execute_whirlpool_swap_mid(trade_amount, min_out, header_accounts, whirlpool_accounts, is_base_token_a)
         }
         3 => {
-            execute_whirlpool_swap_sell(trade_amount, header_accounts, whirlpool_accounts, is_wsol_token_a)
+            execute_whirlpool_swap_sell(trade_amount, min_out, header_accounts, whirlpool_accounts, is_base_token_a)
         }
         _ => {
             Err(PinocchioCpiError::UnsupportedPoolType.into())
@@ -160,6 +164,7 @@ pub fn execute_whirlpool_swap_hop3(
 
 fn execute_whirlpool_swap_mid(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     whirlpool_accounts: &[AccountInfo],
     is_mid_zero_to_one: bool,
@@ -212,6 +217,7 @@ fn execute_whirlpool_swap_mid(
 
     let mut instruction_data = WHIRLPOOL_INSTRUCTION_DATA;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
     instruction_data[41] = if a_to_b { 1 } else { 0 };
 
     let swap_instruction = Instruction {
@@ -243,35 +249,36 @@ fn execute_whirlpool_swap_mid(
 
 fn execute_whirlpool_swap_sell(
     trade_amount: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     whirlpool_accounts: &[AccountInfo],
-    is_wsol_token_a: bool,
+    is_base_token_a: bool,
 ) -> ProgramResult {
-    // 卖出交换：Token2 -> WSOL
+    // 卖出交换：Token2 -> 基础资产
     // 输入：header_accounts[11] (token2_account)
-    // 输出：header_accounts[2] (wsol_account)
+    // 输出：header_accounts[2] (base_account)
     
-    let (token_program_a, token_program_b, token_account_a, token_account_b, mint_a, mint_b, a_to_b) = if is_wsol_token_a {
-        // WSOL是tokenA，Token2是tokenB
+    let (token_program_a, token_program_b, token_account_a, token_account_b, mint_a, mint_b, a_to_b) = if is_base_token_a {
+        // 基础资产是tokenA，Token2是tokenB
         (
-            &header_accounts[3],  // wsol_program
+            &header_accounts[3],  // base_program
             &header_accounts[10], // token2_program
-            &header_accounts[2],  // wsol_account
+            &header_accounts[2],  // base_account
             &header_accounts[11], // token2_account
-            &header_accounts[1],  // wsol_mint
+            &header_accounts[1],  // base_mint
             &header_accounts[9],  // token2_mint
-            false, // Token2 -> WSOL (B to A)
+            false, // Token2 -> base (B to A)
         )
     } else {
-        // WSOL是tokenB，Token2是tokenA
+        // 基础资产是tokenB，Token2是tokenA
         (
             &header_accounts[10], // token2_program
-            &header_accounts[3],  // wsol_program
+            &header_accounts[3],  // base_program
             &header_accounts[11], // token2_account
-            &header_accounts[2],  // wsol_account
+            &header_accounts[2],  // base_account
             &header_accounts[9],  // token2_mint
-            &header_accounts[1],  // wsol_mint
-            true, // Token2 -> WSOL (A to B)
+            &header_accounts[1],  // base_mint
+            true, // Token2 -> base (A to B)
         )
     };
 
@@ -295,6 +302,7 @@ fn execute_whirlpool_swap_sell(
 
     let mut instruction_data = WHIRLPOOL_INSTRUCTION_DATA;
     instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
     instruction_data[41] = if a_to_b { 1 } else { 0 };
 
     let swap_instruction = Instruction {
@@ -323,3 +331,70 @@ fn execute_whirlpool_swap_sell(
 
     invoke::<15>(&swap_instruction, &account_infos)
 }
+
+/// N-hop路由中的通用交换：input -> output
+/// is_zero_to_one 表示输入token是否为tokenA (即a_to_b)
+pub fn execute_whirlpool_swap_route(
+    trade_amount: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    whirlpool_accounts: &[AccountInfo],
+    input: &TokenSlot,
+    output: &TokenSlot,
+    is_zero_to_one: bool,
+) -> ProgramResult {
+    let (token_a, token_b) = if is_zero_to_one {
+        (input, output)
+    } else {
+        (output, input)
+    };
+
+    let account_metas = [
+        AccountMeta::new(token_a.program.key(), false, false),      // tokenProgramA
+        AccountMeta::new(token_b.program.key(), false, false),      // tokenProgramB
+        AccountMeta::new(header_accounts[5].key(), false, false),   // memoProgram
+        AccountMeta::new(header_accounts[0].key(), true, true),     // tokenAuthority (payer)
+        AccountMeta::new(whirlpool_accounts[1].key(), true, false), // whirlpool
+        AccountMeta::new(token_a.mint.key(), false, false),         // tokenMintA
+        AccountMeta::new(token_b.mint.key(), false, false),         // tokenMintB
+        AccountMeta::new(token_a.account.key(), true, false),       // tokenOwnerAccountA
+        AccountMeta::new(whirlpool_accounts[3].key(), true, false), // tokenVaultA
+        AccountMeta::new(token_b.account.key(), true, false),       // tokenOwnerAccountB
+        AccountMeta::new(whirlpool_accounts[4].key(), true, false), // tokenVaultB
+        AccountMeta::new(whirlpool_accounts[5].key(), true, false), // tickArray0
+        AccountMeta::new(whirlpool_accounts[6].key(), true, false), // tickArray1
+        AccountMeta::new(whirlpool_accounts[7].key(), true, false), // tickArray2
+        AccountMeta::new(whirlpool_accounts[2].key(), true, false), // oracle
+    ];
+
+    let mut instruction_data = WHIRLPOOL_INSTRUCTION_DATA;
+    instruction_data[8..16].copy_from_slice(&trade_amount.to_le_bytes());
+    instruction_data[16..24].copy_from_slice(&min_out.to_le_bytes());
+    instruction_data[41] = if is_zero_to_one { 1 } else { 0 };
+
+    let swap_instruction = Instruction {
+        program_id: whirlpool_accounts[0].key(),
+        accounts: &account_metas,
+        data: &instruction_data,
+    };
+
+    let account_infos = [
+        token_a.program,              // tokenProgramA
+        token_b.program,              // tokenProgramB
+        &header_accounts[5],          // memoProgram
+        &header_accounts[0],          // tokenAuthority (payer)
+        &whirlpool_accounts[1],       // whirlpool
+        token_a.mint,                 // tokenMintA
+        token_b.mint,                 // tokenMintB
+        token_a.account,              // tokenOwnerAccountA
+        &whirlpool_accounts[3],       // tokenVaultA
+        token_b.account,              // tokenOwnerAccountB
+        &whirlpool_accounts[4],       // tokenVaultB
+        &whirlpool_accounts[5],       // tickArray0
+        &whirlpool_accounts[6],       // tickArray1
+        &whirlpool_accounts[7],       // tickArray2
+        &whirlpool_accounts[2],       // oracle
+    ];
+
+    invoke::<15>(&swap_instruction, &account_infos)
+}
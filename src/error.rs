@@ -61,6 +61,46 @@ pub enum PinocchioCpiError {
     /// 账户所有者不匹配
     #[error("Pump not supported in step 2")]
     PumpNotSupported,
+
+    // 11
+    /// 该池类型不支持精确输出
+    #[error("Exact output not supported for this pool type")]
+    ExactOutputNotSupported,
+
+    // 12
+    /// 小费以 lamports 支付，基础资产必须为 WSOL
+    #[error("Tip requires a WSOL base asset")]
+    TipRequiresWsolBase,
+
+    // 13
+    /// 反向路由的利润以 token 计，不支持小费
+    #[error("Tip not supported on reverse routes")]
+    TipNotSupportedOnReverse,
+
+    // 14
+    /// pump_base_amount_out 只有一个值，不能同时用于多个 Pump 买入跳
+    #[error("Pump base amount out is ambiguous across multiple Pump buys")]
+    AmbiguousPumpBaseAmountOut,
+
+    // 15
+    /// 模拟模式的返回数据最多记录 SIMULATION_MAX_HOPS 跳
+    #[error("Too many hops to simulate")]
+    SimulationTooManyHops,
+
+    // 16
+    /// 闪电贷路由之后没有归还同一 reserve、同一账户、同一金额的 Solend flash_repay
+    #[error("Flash loan not repaid")]
+    FlashLoanNotRepaid,
+
+    // 17
+    /// Pump.fun 联合曲线以原生 SOL 收付，基础资产必须是 WSOL
+    #[error("Pump.fun requires a WSOL base asset")]
+    PumpFunRequiresWsolBase,
+
+    // 18
+    /// Pump.fun 买入会关闭该跳的临时 WSOL 账户，每条路由最多一个 Pump.fun 买入跳
+    #[error("Multiple Pump.fun buys in one route")]
+    MultiplePumpFunBuys,
 }
 
 /// 🚀 从自定义错误转换为 ProgramError
@@ -86,13 +126,13 @@ impl ToStr for PinocchioCpiError {
     fn to_str<E>(&self) -> &'static str {
         match self {
             PinocchioCpiError::InstructionDataTooShort => {
-                "Error: Instruction data too short - minimum 11 bytes required"
+                "Error: Instruction data too short for the selected instruction layout"
             }
             PinocchioCpiError::InvalidTradeAmount => {
                 "Error: Invalid trade amount - amount must be greater than zero"
             }
             PinocchioCpiError::UnsupportedPoolType => {
-                "Error: Unsupported pool type - valid types are 0-6"
+                "Error: Unsupported pool type - valid types are 0-11"
             }
             PinocchioCpiError::NotEnoughAccounts => {
                 "Error: Not enough accounts provided for the operation"
@@ -101,7 +141,7 @@ impl ToStr for PinocchioCpiError {
                 "Error: Invalid token account data - account may be uninitialized or corrupted"
             }
             PinocchioCpiError::ArbitrageFailed => {
-                "Error: Arbitrage failed - final WSOL balance not greater than initial balance"
+                "Error: Arbitrage failed - final base asset balance not greater than initial balance"
             }
             PinocchioCpiError::InsufficientBalance => {
                 "Error: Insufficient token balance for the operation"
@@ -118,6 +158,30 @@ impl ToStr for PinocchioCpiError {
             PinocchioCpiError::PumpNotSupported => {
                 "Error: Pump not supported in step 2"
             }
+            PinocchioCpiError::ExactOutputNotSupported => {
+                "Error: Exact output not supported - only CPMM (0), Raydium AMM v4 (4, 7) and CLMM (5) support exact-output hops"
+            }
+            PinocchioCpiError::TipRequiresWsolBase => {
+                "Error: Tip requires a WSOL base asset - tips are paid in lamports sized from the base-asset profit"
+            }
+            PinocchioCpiError::TipNotSupportedOnReverse => {
+                "Error: Tip not supported on reverse routes - their profit is measured in the token, not in lamports"
+            }
+            PinocchioCpiError::AmbiguousPumpBaseAmountOut => {
+                "Error: Pump base amount out is ambiguous - set it to 0 when more than one hop is a Pump buy"
+            }
+            PinocchioCpiError::SimulationTooManyHops => {
+                "Error: Too many hops to simulate - simulate mode records at most SIMULATION_MAX_HOPS hops"
+            }
+            PinocchioCpiError::FlashLoanNotRepaid => {
+                "Error: Flash loan not repaid - no later Solend flash_repay of amount_in references a flash_borrow before this instruction"
+            }
+            PinocchioCpiError::PumpFunRequiresWsolBase => {
+                "Error: Pump.fun requires a WSOL base asset - the bonding curve settles in native SOL through the base account"
+            }
+            PinocchioCpiError::MultiplePumpFunBuys => {
+                "Error: Multiple Pump.fun buys in one route - each buy closes its scratch WSOL account, only one buy per route is supported"
+            }
         }
     }
 }
@@ -0,0 +1,135 @@
+//! # 闪电贷
+//!
+//! FLAG_FLASH_LOAN 时 amount_in 由包住本指令的顶层 Solend flash_borrow / flash_repay 借入和归还：
+//!
+//! ```text
+//! [i]      Solend flash_borrow(amount_in)       -> 借入到基础资产账户（反向路由为 token 账户）
+//! [i + 1]  本程序（套利指令）
+//! [j > i+1] Solend flash_repay(amount_in, i)     -> 归还本金 + 手续费
+//! ```
+//!
+//! Solend 禁止通过 CPI 借款，借款和还款只能是交易顶层指令，因此本程序不发起借款 CPI，
+//! 而是在执行任何 swap 之前通过 instructions sysvar 确认：
+//! - 本指令之后存在归还同一 reserve、同一账户、同一金额的 flash_repay
+//! - 该 flash_repay 的 borrow_instruction_index 指向本指令之前对应的 flash_borrow
+//!
+//! 手续费从 reserve 账户的 `config.fees.flash_loan_fee_wad` 按 Solend 的规则计算，
+//! 不由调用方提供。只支持 Solend（及沿用其 program id 和 reserve 布局的部署），
+//! Kamino / MarginFi 的闪电贷模型不同，不受支持。
+
+use pinocchio::account_info::AccountInfo;
+use pinocchio::program_error::ProgramError;
+use pinocchio::pubkey::Pubkey;
+use pinocchio::sysvars::instructions::{Instructions, IntrospectedInstruction};
+use crate::constants::SOLEND_PROGRAM_ID;
+use crate::error::PinocchioCpiError;
+
+/// 🚀 Solend 闪电贷指令的 tag 和数据长度
+pub const FLASH_BORROW_TAG: u8 = 19;
+pub const FLASH_REPAY_TAG: u8 = 20;
+const FLASH_BORROW_DATA_LEN: usize = 9; // tag + amount
+const FLASH_REPAY_DATA_LEN: usize = 10; // tag + amount + borrow_instruction_index
+
+/// 🚀 flash_borrow 的 [1] destination_liquidity / [2] reserve
+const BORROW_DESTINATION_INDEX: usize = 1;
+const BORROW_RESERVE_INDEX: usize = 2;
+/// 🚀 flash_repay 的 [0] source_liquidity / [4] reserve
+const REPAY_SOURCE_INDEX: usize = 0;
+const REPAY_RESERVE_INDEX: usize = 4;
+
+/// 🚀 Solend `Reserve` 打包布局中的 config.fees.flash_loan_fee_wad (u64) 和 host_fee_percentage (u8)
+pub const RESERVE_FLASH_LOAN_FEE_WAD_OFFSET: usize = 314;
+pub const RESERVE_HOST_FEE_PERCENTAGE_OFFSET: usize = 322;
+/// 🚀 1.0 的 WAD 表示
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+/// 🚀 确认本指令被 Solend 闪电贷包住，返回 flash_repay 将额外扣除的手续费
+///
+/// - `flash_loan_accounts`: [0] reserve, [1] instructions sysvar；未设置 FLAG_FLASH_LOAN 时为 None，手续费为0
+/// - `borrowed_account`: 借入和归还 amount 的账户（基础资产账户，反向路由为 token 账户）
+#[inline(always)]
+pub fn check_flash_loan(
+    flash_loan_accounts: Option<&[AccountInfo]>,
+    amount: u64,
+    borrowed_account: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    let Some(flash_loan_accounts) = flash_loan_accounts else {
+        return Ok(0);
+    };
+    let reserve = &flash_loan_accounts[0];
+    if !reserve.is_owned_by(&SOLEND_PROGRAM_ID) {
+        return Err(PinocchioCpiError::AccountOwnerMismatch.into());
+    }
+
+    let instructions = Instructions::try_from(&flash_loan_accounts[1])?;
+    let current = instructions.load_current_index() as usize;
+
+    // 🚀 本指令之后的 flash_repay，且其 borrow_instruction_index 指向本指令之前的 flash_borrow
+    let is_repaid = (current + 1..instructions.num_instructions() as usize)
+        .filter_map(|i| instructions.load_instruction_at(i).ok())
+        .filter(|repay| is_repay(repay, amount, borrowed_account.key(), reserve.key()))
+        .any(|repay| {
+            let borrow_index = repay.get_instruction_data()[9] as usize;
+            borrow_index < current
+                && instructions
+                    .load_instruction_at(borrow_index)
+                    .is_ok_and(|borrow| is_borrow(&borrow, amount, borrowed_account.key(), reserve.key()))
+        });
+    if !is_repaid {
+        return Err(PinocchioCpiError::FlashLoanNotRepaid.into());
+    }
+
+    let data = reserve.try_borrow_data()?;
+    if data.len() <= RESERVE_HOST_FEE_PERCENTAGE_OFFSET {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let fee_wad = u64::from_le_bytes(
+        data[RESERVE_FLASH_LOAN_FEE_WAD_OFFSET..RESERVE_FLASH_LOAN_FEE_WAD_OFFSET + 8].try_into().unwrap(),
+    );
+    Ok(flash_loan_fee(amount, fee_wad, data[RESERVE_HOST_FEE_PERCENTAGE_OFFSET]))
+}
+
+/// 🚀 Solend 的闪电贷手续费：amount * fee_wad 四舍五入，费率非0时至少1（有 host fee 时至少2）
+#[inline(always)]
+pub fn flash_loan_fee(amount: u64, fee_wad: u64, host_fee_percentage: u8) -> u64 {
+    if amount == 0 || fee_wad == 0 {
+        return 0;
+    }
+    let fee = (amount as u128 * fee_wad as u128 + WAD / 2) / WAD;
+    let minimum_fee = if host_fee_percentage > 0 { 2 } else { 1 };
+    fee.max(minimum_fee).min(u64::MAX as u128) as u64
+}
+
+/// 🚀 从 `reserve` 向 `destination` 借出 `amount` 的 flash_borrow
+#[inline(always)]
+fn is_borrow(instruction: &IntrospectedInstruction, amount: u64, destination: &Pubkey, reserve: &Pubkey) -> bool {
+    let data = instruction.get_instruction_data();
+    instruction.get_program_id() == &SOLEND_PROGRAM_ID
+        && data.len() == FLASH_BORROW_DATA_LEN
+        && data[0] == FLASH_BORROW_TAG
+        && read_u64(data, 1) == amount
+        && account_key(instruction, BORROW_DESTINATION_INDEX) == Some(destination)
+        && account_key(instruction, BORROW_RESERVE_INDEX) == Some(reserve)
+}
+
+/// 🚀 从 `source` 向 `reserve` 归还 `amount` 的 flash_repay
+#[inline(always)]
+fn is_repay(instruction: &IntrospectedInstruction, amount: u64, source: &Pubkey, reserve: &Pubkey) -> bool {
+    let data = instruction.get_instruction_data();
+    instruction.get_program_id() == &SOLEND_PROGRAM_ID
+        && data.len() == FLASH_REPAY_DATA_LEN
+        && data[0] == FLASH_REPAY_TAG
+        && read_u64(data, 1) == amount
+        && account_key(instruction, REPAY_SOURCE_INDEX) == Some(source)
+        && account_key(instruction, REPAY_RESERVE_INDEX) == Some(reserve)
+}
+
+#[inline(always)]
+fn account_key<'a>(instruction: &'a IntrospectedInstruction, index: usize) -> Option<&'a Pubkey> {
+    instruction.get_account_meta_at(index).ok().map(|meta| &meta.key)
+}
+
+#[inline(always)]
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
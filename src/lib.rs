@@ -1,13 +1,17 @@
-use pinocchio::cpi::set_return_data;
-use pinocchio::{account_info::AccountInfo, entrypoint, pubkey::Pubkey, ProgramResult};
+use pinocchio::{account_info::AccountInfo, pubkey::Pubkey, ProgramResult};
+pub mod constants;
 pub mod cpi;
 pub mod error;
+pub mod flash_loan;
+pub mod simulation;
 pub mod utils;
 
 use error::PinocchioCpiError;
+use simulation::Simulation;
 
 // 使用标准入口点
-entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
+pinocchio::entrypoint!(process_instruction);
 
 pub fn process_instruction(
     _program_id: &Pubkey,
@@ -17,207 +21,618 @@ pub fn process_instruction(
     if let Some((first, tail)) = instruction_data.split_first() {
         match first {
             4 => {
-                execute_direct_cpi(accounts, tail)?;
+                execute_direct_cpi(accounts, tail, false)?;
             }
             5 => {
-                execute_direct_cpi_3hop(accounts, tail)?;
+                execute_direct_cpi_3hop(accounts, tail, false)?;
+            }
+            6 => {
+                execute_direct_cpi_route(accounts, tail, false)?;
+            }
+            // 🚀 v2：u64 min_profit + min_profit_bps
+            7 => {
+                execute_direct_cpi(accounts, tail, true)?;
+            }
+            8 => {
+                execute_direct_cpi_3hop(accounts, tail, true)?;
+            }
+            9 => {
+                execute_direct_cpi_route(accounts, tail, true)?;
+            }
+            // 🚀 反向2hop：先卖出 token 再买回，利润以 token 计
+            10 => {
+                execute_direct_cpi_reverse(accounts, tail, false)?;
+            }
+            11 => {
+                execute_direct_cpi_reverse(accounts, tail, true)?;
             }
             _ => {
                 return Err(PinocchioCpiError::UnsupportedPoolType.into());
             }
         }
         
+    } else if cfg!(feature = "checked") {
+        return Err(PinocchioCpiError::InstructionDataTooShort.into());
     }
 
     Ok(())
 }
 
-fn execute_direct_cpi(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    let params = utils::parse_instruction_data(instruction_data, false)?;
+fn execute_direct_cpi(accounts: &[AccountInfo], instruction_data: &[u8], is_v2: bool) -> ProgramResult {
+    let params = utils::parse_instruction_data(instruction_data, false, is_v2)?;
 
-    let buy_count = utils::validate_pool_types(params.buy)?;
+    // 🚀 Pump.fun 卖出跳不需要临时 WSOL 账户，账户数量按方向确定
+    let buy_count = utils::hop_account_count(params.buy, true)?;
+    let sell_count = utils::hop_account_count(params.sell, false)?;
 
     // 🚀 优化：使用更高效的账户分割
-    let (header_accounts, pool_accounts) = accounts.split_at(9); // 改为9个header账户
-    let (buy_accounts, sell_accounts) = pool_accounts.split_at(buy_count);
+    let (header_accounts, pool_accounts) = utils::split_accounts(accounts, utils::HEADER_ACCOUNT_COUNT)?; // 改为9个header账户
+    // 🚀 FLAG_TIP 时 header 之后紧跟小费账户，FLAG_FLASH_LOAN 时再紧跟 reserve 和 instructions sysvar
+    let (tip_account, pool_accounts) = utils::split_tip_account(pool_accounts, params.has_tip)?;
+    let (flash_loan_accounts, pool_accounts) = utils::split_flash_loan_accounts(pool_accounts, params.has_flash_loan)?;
+    let (buy_accounts, remaining) = utils::split_accounts(pool_accounts, buy_count)?;
+    let (sell_accounts, _) = utils::split_accounts(remaining, sell_count)?;
+
+    if params.validate_accounts {
+        utils::validate_header_accounts(header_accounts)?;
+    }
 
-    let initial_wsol_balance = utils::get_token_balance(&header_accounts[2])?;
+    utils::check_tip(params.has_tip, header_accounts)?;
+    utils::check_pump_fun_base(params.buy == 8 || params.sell == 8, header_accounts)?;
 
+    // 🚀 闪电贷：执行 swap 之前确认之后有归还 amount_in 的 flash_repay，手续费按 reserve 的费率计算
+    let flash_loan_fee = flash_loan::check_flash_loan(flash_loan_accounts, params.amount_in, &header_accounts[2])?;
+
+    // 🚀 包装 SOL：在读取初始余额之前执行，包装的本金不计入利润
+    if params.has_wrap_sol {
+        cpi::native_sol::wrap_sol(params.wrap_lamports, header_accounts)?;
+    }
+
+    let initial_base_balance = utils::get_token_balance(&header_accounts[2])?;
+    let mut simulation = Simulation::new(params.is_simulate);
+
+    // 🚀 记录中间token账户的原有余额，下一跳只卖出本跳收到的数量
+    let token_balance_before = utils::get_token_balance(&header_accounts[8])?;
+
+    simulation.start_hop(&header_accounts[8])?;
     execute_swap_optimized(
         params.buy,
         params.amount_in,
+        params.buy_min_out,
         header_accounts,
         buy_accounts,
         true,
-        params.is_wsol_pool_0_buy,
+        params.is_base_pool_0_buy,
         params.pump_base_amount_out,
+        params.buy_exact_out,
     )?;
+    simulation.end_hop(params.amount_in, &header_accounts[8])?;
 
-    let token_balance = utils::get_token_balance(&header_accounts[8])?;
+    let token_amount = utils::get_received_amount(&header_accounts[8], token_balance_before, params.include_existing_balance)?;
 
+    simulation.start_hop(&header_accounts[2])?;
     execute_swap_optimized(
         params.sell,
-        token_balance,
+        token_amount,
+        params.sell_min_out,
         header_accounts,
         sell_accounts,
         false,
-        params.is_wsol_pool_0_sell,
+        params.is_base_pool_0_sell,
         params.pump_base_amount_out,
+        params.sell_exact_out,
     )?;
+    simulation.end_hop(token_amount, &header_accounts[2])?;
 
-    let final_wsol_balance = utils::get_token_balance(&header_accounts[2])?;
+    // 🚀 闪电贷：顶层 flash_repay 在本指令之后才扣除手续费，利润按扣除手续费后的余额校验
+    let final_base_balance = utils::get_token_balance(&header_accounts[2])?.saturating_sub(flash_loan_fee);
 
-    if final_wsol_balance <= initial_wsol_balance + params.min_profit as u64 {
-        return Err(PinocchioCpiError::ArbitrageFailed.into());
-    }
+    let profit = utils::check_profit(
+        initial_base_balance,
+        final_base_balance,
+        params.amount_in,
+        params.min_profit,
+        params.min_profit_bps,
+    );
 
-    if params.is_simulate {
-        let profit = final_wsol_balance - initial_wsol_balance;
+    // 🚀 模拟模式下利润不足不报错，照常返回带符号的盈亏
+    let profit = if params.is_simulate { profit.ok() } else { Some(profit?) };
 
-        let mut return_data = [0u8; 8];
-        return_data[0..8].copy_from_slice(&profit.to_le_bytes());
+    // 🚀 解包 SOL：利润校验之后关闭基础资产账户，本金和利润退回 payer
+    if params.unwrap_sol {
+        cpi::native_sol::unwrap_sol(header_accounts)?;
+    }
 
-        // 🚀 返回利润数据给客户端
-        set_return_data(&return_data);
+    // 🚀 利润校验通过后才支付小费
+    if let (Some(tip_account), Some(profit)) = (tip_account, profit) {
+        cpi::tip::pay_tip(profit, params.tip_lamports, params.tip_bps, header_accounts, tip_account)?;
     }
 
+    // 🚀 模拟模式下返回盈亏和每跳数据给客户端
+    simulation.finish(initial_base_balance, final_base_balance);
+
     Ok(())
 }
 
 
-fn execute_direct_cpi_3hop(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    let params = utils::parse_instruction_data(instruction_data, true)?;
+/// 🚀 反向2hop：token -> 基础资产 -> token，利润按 header_accounts[8] 的 token 余额校验
+///
+/// 指令数据与2hop相同，data[0] / data[2] 为先执行的卖出池，data[1] / data[3] 为随后的买入池；
+/// amount_in、min_profit 和闪电贷均以 token 计，不支持小费。
+fn execute_direct_cpi_reverse(accounts: &[AccountInfo], instruction_data: &[u8], is_v2: bool) -> ProgramResult {
+    let params = utils::parse_reverse_instruction_data(instruction_data, is_v2)?;
+
+    let sell_count = utils::hop_account_count(params.sell, false)?;
+    let buy_count = utils::hop_account_count(params.buy, true)?;
+
+    let (header_accounts, pool_accounts) = utils::split_accounts(accounts, utils::HEADER_ACCOUNT_COUNT)?;
+    // 🚀 反向路由不支持小费，FLAG_FLASH_LOAN 时 header 之后紧跟 reserve 和 instructions sysvar
+    let (flash_loan_accounts, pool_accounts) = utils::split_flash_loan_accounts(pool_accounts, params.has_flash_loan)?;
+    let (sell_accounts, remaining) = utils::split_accounts(pool_accounts, sell_count)?;
+    let (buy_accounts, _) = utils::split_accounts(remaining, buy_count)?;
+
+    if params.validate_accounts {
+        utils::validate_header_accounts(header_accounts)?;
+    }
+
+    utils::check_pump_fun_base(params.buy == 8 || params.sell == 8, header_accounts)?;
+
+    let token = utils::get_token_slot(header_accounts, 8);
+
+    // 🚀 闪电贷：借入和归还的是 token，手续费同样以 token 计
+    let flash_loan_fee = flash_loan::check_flash_loan(flash_loan_accounts, params.amount_in, token.account)?;
+
+    // 🚀 包装 SOL：在记录基础资产余额之前执行，包装的 SOL 不会在买回时被花掉
+    if params.has_wrap_sol {
+        cpi::native_sol::wrap_sol(params.wrap_lamports, header_accounts)?;
+    }
+
+    let initial_token_balance = utils::get_token_balance(token.account)?;
+    let mut simulation = Simulation::new(params.is_simulate);
+
+    // 🚀 记录基础资产账户的原有余额，买回时只花费卖出收到的数量
+    let base_balance_before = utils::get_token_balance(&header_accounts[2])?;
+
+    simulation.start_hop(&header_accounts[2])?;
+    execute_swap_optimized(
+        params.sell,
+        params.amount_in,
+        params.sell_min_out,
+        header_accounts,
+        sell_accounts,
+        false,
+        params.is_base_pool_0_sell,
+        params.pump_base_amount_out,
+        params.sell_exact_out,
+    )?;
+    simulation.end_hop(params.amount_in, &header_accounts[2])?;
+
+    let base_amount = utils::get_received_amount(&header_accounts[2], base_balance_before, params.include_existing_balance)?;
+
+    simulation.start_hop(token.account)?;
+    execute_swap_optimized(
+        params.buy,
+        base_amount,
+        params.buy_min_out,
+        header_accounts,
+        buy_accounts,
+        true,
+        params.is_base_pool_0_buy,
+        params.pump_base_amount_out,
+        params.buy_exact_out,
+    )?;
+    simulation.end_hop(base_amount, token.account)?;
+
+    // 🚀 闪电贷：手续费由之后的顶层 flash_repay 以 token 扣除
+    let final_token_balance = utils::get_token_balance(token.account)?.saturating_sub(flash_loan_fee);
+
+    let profit = utils::check_profit(
+        initial_token_balance,
+        final_token_balance,
+        params.amount_in,
+        params.min_profit,
+        params.min_profit_bps,
+    );
+
+    // 🚀 模拟模式下利润不足不报错，照常返回带符号的盈亏
+    if !params.is_simulate {
+        profit?;
+    }
+
+    // 🚀 解包 SOL：关闭基础资产账户，其中的 SOL 退回 payer
+    if params.unwrap_sol {
+        cpi::native_sol::unwrap_sol(header_accounts)?;
+    }
+
+    // 🚀 模拟模式下返回 token 盈亏和每跳数据给客户端
+    simulation.finish(initial_token_balance, final_token_balance);
+
+    Ok(())
+}
+
+fn execute_direct_cpi_3hop(accounts: &[AccountInfo], instruction_data: &[u8], is_v2: bool) -> ProgramResult {
+    let params = utils::parse_instruction_data(instruction_data, true, is_v2)?;
 
-    let buy_count = utils::validate_pool_types(params.buy)?;
-    let mid_count = utils::validate_pool_types(params.mid.unwrap())?;
+    let buy_count = utils::hop_account_count(params.buy, true)?;
+    let mid_count = utils::hop_account_count(params.mid.unwrap(), false)?;
+    let sell_count = utils::hop_account_count(params.sell, false)?;
 
     // 🚀 优化：使用更高效的账户分割
-    let (header_accounts, pool_accounts) = accounts.split_at(12); // 改为12个header账户(3hop+mid的basemint的mint+ tokenprogram +tokenacc信息账户)
+    let (header_accounts, pool_accounts) = utils::split_accounts(accounts, utils::HEADER_ACCOUNT_COUNT_3HOP)?; // 改为12个header账户(3hop+mid的basemint的mint+ tokenprogram +tokenacc信息账户)
+    // 🚀 FLAG_TIP 时 header 之后紧跟小费账户，FLAG_FLASH_LOAN 时再紧跟 reserve 和 instructions sysvar
+    let (tip_account, pool_accounts) = utils::split_tip_account(pool_accounts, params.has_tip)?;
+    let (flash_loan_accounts, pool_accounts) = utils::split_flash_loan_accounts(pool_accounts, params.has_flash_loan)?;
     
     // 优雅地分割三个pool的账户
-    let (buy_accounts, remaining) = pool_accounts.split_at(buy_count);
-    let (mid_accounts, sell_accounts) = remaining.split_at(mid_count);
+    let (buy_accounts, remaining) = utils::split_accounts(pool_accounts, buy_count)?;
+    let (mid_accounts, remaining) = utils::split_accounts(remaining, mid_count)?;
+    let (sell_accounts, _) = utils::split_accounts(remaining, sell_count)?;
+
+    if params.validate_accounts {
+        utils::validate_header_accounts(header_accounts)?;
+    }
+
+    utils::check_tip(params.has_tip, header_accounts)?;
+    utils::check_pump_fun_base(params.buy == 8 || params.mid == Some(8) || params.sell == 8, header_accounts)?;
+
+    // 🚀 闪电贷：执行 swap 之前确认之后有归还 amount_in 的 flash_repay，手续费按 reserve 的费率计算
+    let flash_loan_fee = flash_loan::check_flash_loan(flash_loan_accounts, params.amount_in, &header_accounts[2])?;
+
+    // 🚀 买入跳和中间跳都可能是 Pump 买入，pump_base_amount_out 只能对应其中一个
+    utils::check_pump_base_amount_out(
+        params.pump_base_amount_out,
+        utils::is_pump_buy(params.buy, params.is_base_pool_0_buy, true) as usize
+            + utils::is_pump_buy(params.mid.unwrap(), params.is_mid_zero_to_one.unwrap(), false) as usize,
+    )?;
+
+    // 🚀 包装 SOL：在读取初始余额之前执行，包装的本金不计入利润
+    if params.has_wrap_sol {
+        cpi::native_sol::wrap_sol(params.wrap_lamports, header_accounts)?;
+    }
 
-    let initial_wsol_balance = utils::get_token_balance(&header_accounts[2])?;
+    let initial_base_balance = utils::get_token_balance(&header_accounts[2])?;
+    let mut simulation = Simulation::new(params.is_simulate);
+
+    // 🚀 记录中间token账户的原有余额，下一跳只卖出本跳收到的数量
+    let token1_balance_before = utils::get_token_balance(&header_accounts[8])?;
+    let token2_balance_before = utils::get_token_balance(&header_accounts[11])?;
 
     //buy_pool
+    simulation.start_hop(&header_accounts[8])?;
     execute_swap_optimized_3hop(
         params.buy,
         params.amount_in,
+        params.buy_min_out,
         header_accounts,
         buy_accounts,
         1,
-        params.is_wsol_pool_0_buy,
+        params.is_base_pool_0_buy,
         params.pump_base_amount_out,
+        params.buy_exact_out,
     )?;
+    simulation.end_hop(params.amount_in, &header_accounts[8])?;
 
-    let token1_balance = utils::get_token_balance(&header_accounts[8])?;
+    let token1_amount = utils::get_received_amount(&header_accounts[8], token1_balance_before, params.include_existing_balance)?;
     
     //mid_pool
+    simulation.start_hop(&header_accounts[11])?;
     execute_swap_optimized_3hop(
         params.mid.unwrap(),
-        token1_balance,
+        token1_amount,
+        params.mid_min_out,
         header_accounts,
         mid_accounts,
         2,
         params.is_mid_zero_to_one.unwrap(),
         params.pump_base_amount_out,
+        params.mid_exact_out,
     )?;
+    simulation.end_hop(token1_amount, &header_accounts[11])?;
 
-    let token2_balance = utils::get_token_balance(&header_accounts[11])?;
+    let token2_amount = utils::get_received_amount(&header_accounts[11], token2_balance_before, params.include_existing_balance)?;
 
+    simulation.start_hop(&header_accounts[2])?;
     execute_swap_optimized_3hop(
         params.sell,
-        token2_balance,
+        token2_amount,
+        params.sell_min_out,
         header_accounts,
         sell_accounts,
         3,
-        params.is_wsol_pool_0_sell,
+        params.is_base_pool_0_sell,
         params.pump_base_amount_out,
+        params.sell_exact_out,
     )?;
+    simulation.end_hop(token2_amount, &header_accounts[2])?;
 
-    let final_wsol_balance = utils::get_token_balance(&header_accounts[2])?;
+    // 🚀 闪电贷：顶层 flash_repay 在本指令之后才扣除手续费，利润按扣除手续费后的余额校验
+    let final_base_balance = utils::get_token_balance(&header_accounts[2])?.saturating_sub(flash_loan_fee);
 
-    if final_wsol_balance <= initial_wsol_balance + params.min_profit as u64 {
-        return Err(PinocchioCpiError::ArbitrageFailed.into());
-    }
+    let profit = utils::check_profit(
+        initial_base_balance,
+        final_base_balance,
+        params.amount_in,
+        params.min_profit,
+        params.min_profit_bps,
+    );
 
-    if params.is_simulate {
-        let profit = final_wsol_balance - initial_wsol_balance;
+    // 🚀 模拟模式下利润不足不报错，照常返回带符号的盈亏
+    let profit = if params.is_simulate { profit.ok() } else { Some(profit?) };
 
-        let mut return_data = [0u8; 8];
-        return_data[0..8].copy_from_slice(&profit.to_le_bytes());
+    // 🚀 解包 SOL：利润校验之后关闭基础资产账户，本金和利润退回 payer
+    if params.unwrap_sol {
+        cpi::native_sol::unwrap_sol(header_accounts)?;
+    }
 
-        // 🚀 返回利润数据给客户端
-        set_return_data(&return_data);
+    // 🚀 利润校验通过后才支付小费
+    if let (Some(tip_account), Some(profit)) = (tip_account, profit) {
+        cpi::tip::pay_tip(profit, params.tip_lamports, params.tip_bps, header_accounts, tip_account)?;
     }
 
+    // 🚀 模拟模式下返回盈亏和每跳数据给客户端
+    simulation.finish(initial_base_balance, final_base_balance);
+
     Ok(())
 }
 
+/// 🚀 通用N-hop路由：每个hop自带池类型、方向以及输入/输出token账户下标
+fn execute_direct_cpi_route(accounts: &[AccountInfo], instruction_data: &[u8], is_v2: bool) -> ProgramResult {
+    let params = utils::parse_route_data(instruction_data, is_v2)?;
+
+    // 🚀 模拟模式的返回数据容纳不下的路由直接拒绝，不返回不完整的逐跳结果
+    simulation::check_hop_count(params.is_simulate, params.hop_count)?;
+
+    #[cfg(feature = "checked")]
+    if params.header_count < utils::HEADER_ACCOUNT_COUNT {
+        return Err(PinocchioCpiError::NotEnoughAccounts.into());
+    }
+
+    let (header_accounts, pool_accounts) = utils::split_accounts(accounts, params.header_count)?;
+    // 🚀 FLAG_TIP 时 header 之后紧跟小费账户，FLAG_FLASH_LOAN 时再紧跟 reserve 和 instructions sysvar
+    let (tip_account, pool_accounts) = utils::split_tip_account(pool_accounts, params.has_tip)?;
+    let (flash_loan_accounts, mut pool_accounts) = utils::split_flash_loan_accounts(pool_accounts, params.has_flash_loan)?;
+
+    if params.validate_accounts {
+        utils::validate_header_accounts(header_accounts)?;
+    }
 
+    utils::check_tip(params.has_tip, header_accounts)?;
+
+    // 🚀 闪电贷：执行 swap 之前确认之后有归还 amount_in 的 flash_repay，手续费按 reserve 的费率计算
+    let flash_loan_fee = flash_loan::check_flash_loan(flash_loan_accounts, params.amount_in, &header_accounts[2])?;
+
+    // 🚀 执行前逐跳检查：pump_base_amount_out 只能对应一个 Pump 买入跳，
+    // Pump.fun 需要 WSOL 基础资产且每条路由最多买入一次（买入会关闭该跳的临时 WSOL 账户）
+    let mut pump_buy_count = 0;
+    let mut pump_fun_buy_count = 0;
+    let mut uses_pump_fun = false;
+    for i in 0..params.hop_count {
+        let hop = utils::get_route_hop(params.hops, params.hop_len, i);
+        let is_pump_buy = utils::is_pump_buy(hop.pool_type, hop.is_zero_to_one, hop.input_index == 2);
+        pump_buy_count += is_pump_buy as usize;
+        pump_fun_buy_count += (is_pump_buy && hop.pool_type == 8) as usize;
+        uses_pump_fun |= hop.pool_type == 8;
+    }
+    utils::check_pump_base_amount_out(params.pump_base_amount_out, pump_buy_count)?;
+    utils::check_pump_fun_base(uses_pump_fun, header_accounts)?;
+    utils::check_pump_fun_buy_count(pump_fun_buy_count)?;
+
+    // 🚀 包装 SOL：在读取初始余额之前执行，包装的本金不计入利润
+    if params.has_wrap_sol {
+        cpi::native_sol::wrap_sol(params.wrap_lamports, header_accounts)?;
+    }
+
+    let initial_base_balance = utils::get_token_balance(&header_accounts[2])?;
+    let mut simulation = Simulation::new(params.is_simulate);
+
+    // 🚀 当前腿的总输入和尚未分配的部分，未拆分时每一跳即为一条腿
+    let mut leg_amount = params.amount_in;
+    let mut leg_remaining = leg_amount;
+    // 🚀 每条腿开始前输出账户的余额，下一腿只使用本腿收到的数量
+    let mut is_leg_start = true;
+    let mut output_balance_before = 0;
+
+    for i in 0..params.hop_count {
+        let hop = utils::get_route_hop(params.hops, params.hop_len, i);
+
+        // 🚀 拆分路由：腿内按份额分配输入，最后一个子swap使用剩余的全部输入
+        let is_leg_end = !params.is_split
+            || i + 1 == params.hop_count
+            || !utils::is_same_leg(&hop, &utils::get_route_hop(params.hops, params.hop_len, i + 1));
+        let amount_in = if is_leg_end {
+            leg_remaining
+        } else {
+            utils::split_share(&hop, leg_amount, leg_remaining)
+        };
+        leg_remaining -= amount_in;
+
+        let pool_count = utils::hop_account_count(hop.pool_type, hop.input_index == 2)?;
+        let (hop_accounts, remaining) = utils::split_accounts(pool_accounts, pool_count)?;
+        pool_accounts = remaining;
+
+        #[cfg(feature = "checked")]
+        {
+            utils::check_token_index(header_accounts, hop.input_index)?;
+            utils::check_token_index(header_accounts, hop.output_index)?;
+        }
+
+        let input = utils::get_token_slot(header_accounts, hop.input_index);
+        let output = utils::get_token_slot(header_accounts, hop.output_index);
+
+        if is_leg_start {
+            output_balance_before = utils::get_token_balance(output.account)?;
+        }
+
+        simulation.start_hop(output.account)?;
+        execute_swap_optimized_route(
+            hop.pool_type,
+            amount_in,
+            utils::get_route_min_out(params.min_outs, i),
+            header_accounts,
+            hop_accounts,
+            &input,
+            &output,
+            hop.is_zero_to_one,
+            params.pump_base_amount_out,
+            hop.is_exact_out,
+        )?;
+        simulation.end_hop(amount_in, output.account)?;
+
+        // 下一腿使用本腿收到的数量（拆分时为所有子swap输出之和）
+        if is_leg_end {
+            leg_amount = utils::get_received_amount(output.account, output_balance_before, params.include_existing_balance)?;
+            leg_remaining = leg_amount;
+        }
+        is_leg_start = is_leg_end;
+    }
+
+    // 🚀 闪电贷：顶层 flash_repay 在本指令之后才扣除手续费，利润按扣除手续费后的余额校验
+    let final_base_balance = utils::get_token_balance(&header_accounts[2])?.saturating_sub(flash_loan_fee);
+
+    let profit = utils::check_profit(
+        initial_base_balance,
+        final_base_balance,
+        params.amount_in,
+        params.min_profit,
+        params.min_profit_bps,
+    );
+
+    // 🚀 模拟模式下利润不足不报错，照常返回带符号的盈亏
+    let profit = if params.is_simulate { profit.ok() } else { Some(profit?) };
+
+    // 🚀 解包 SOL：利润校验之后关闭基础资产账户，本金和利润退回 payer
+    if params.unwrap_sol {
+        cpi::native_sol::unwrap_sol(header_accounts)?;
+    }
+
+    // 🚀 利润校验通过后才支付小费
+    if let (Some(tip_account), Some(profit)) = (tip_account, profit) {
+        cpi::tip::pay_tip(profit, params.tip_lamports, params.tip_bps, header_accounts, tip_account)?;
+    }
+
+    // 🚀 模拟模式下返回盈亏和每跳数据给客户端
+    simulation.finish(initial_base_balance, final_base_balance);
+
+    Ok(())
+}
 
 
 #[inline(always)]
+#[allow(clippy::too_many_arguments)]
 fn execute_swap_optimized(
     pool_type: u8,
     amount_in: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     pool_accounts: &[AccountInfo],
     is_buy: bool,
-    is_wsol_pool_0: bool,
+    is_base_pool_0: bool,
     pump_base_amount_out: u64,
+    exact_out: bool,
 ) -> ProgramResult {
+    utils::check_exact_out(pool_type, exact_out, min_out)?;
+
     match pool_type {
         0 => cpi::cpmm::execute_cpmm_swap(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             is_buy,
-            is_wsol_pool_0,
+            is_base_pool_0,
+            exact_out,
         ),
         1 => cpi::dlmm::execute_dlmm_swap(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             is_buy,
-            is_wsol_pool_0,
+            is_base_pool_0,
         ),
         2 => cpi::dammv2::execute_dammv2_swap(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             is_buy,
-            is_wsol_pool_0,
+            is_base_pool_0,
         ),
         3 => cpi::pump::execute_pump_swap(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             is_buy,
-            is_wsol_pool_0,
+            is_base_pool_0,
             pump_base_amount_out,
         ),
         4 => cpi::raydium::execute_raydium_swap(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             is_buy,
-            is_wsol_pool_0,
+            is_base_pool_0,
+            exact_out,
         ),
         5 => cpi::clmm::execute_clmm_swap(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             is_buy,
-            is_wsol_pool_0,
+            is_base_pool_0,
+            exact_out,
         ),
         6 => cpi::whirlpool::execute_whirlpool_swap(
             amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            is_buy,
+            is_base_pool_0,
+        ),
+        7 => cpi::raydium::execute_raydium_openbook_swap(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            is_buy,
+            is_base_pool_0,
+            exact_out,
+        ),
+        8 => cpi::pump_fun::execute_pump_fun_swap(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            is_buy,
+            pump_base_amount_out,
+        ),
+        9 => cpi::dammv1::execute_dammv1_swap(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            is_buy,
+            is_base_pool_0,
+        ),
+        10 => cpi::dbc::execute_dbc_swap(
+            amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             is_buy,
-            is_wsol_pool_0,
+            is_base_pool_0,
+        ),
+        11 => cpi::launchlab::execute_launchlab_swap(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            is_buy,
+            is_base_pool_0,
         ),
         _ => Err(PinocchioCpiError::UnsupportedPoolType.into()),
     }
@@ -226,65 +641,256 @@ fn execute_swap_optimized(
 
  
 #[inline(always)]
+#[allow(clippy::too_many_arguments)]
 fn execute_swap_optimized_3hop(
     pool_type: u8,
     amount_in: u64,
+    min_out: u64,
     header_accounts: &[AccountInfo],
     pool_accounts: &[AccountInfo],
     step: u8,
-    is_wsol_pool_0: bool,
+    is_base_pool_0: bool,
     pump_base_amount_out: u64,
+    exact_out: bool,
 ) -> ProgramResult {
+    utils::check_exact_out(pool_type, exact_out, min_out)?;
+
     match pool_type {
         0 => cpi::cpmm::execute_cpmm_swap_hop3(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             step,
-            is_wsol_pool_0
+            is_base_pool_0,
+            exact_out,
         ),
         1 => cpi::dlmm::execute_dlmm_swap_hop3(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             step,
-            is_wsol_pool_0,
+            is_base_pool_0,
         ),
         2 => cpi::dammv2::execute_dammv2_swap_hop3(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             step,
-            is_wsol_pool_0,
+            is_base_pool_0,
         ),
         3 => cpi::pump::execute_pump_swap_hop3(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             step, 
-            is_wsol_pool_0,
+            is_base_pool_0,
             pump_base_amount_out,
         ),
         4 => cpi::raydium::execute_raydium_swap_hop3(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             step,
-            is_wsol_pool_0,
+            is_base_pool_0,
+            exact_out,
         ),
         5 => cpi::clmm::execute_clmm_swap_hop3(
             amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             step,
-            is_wsol_pool_0,
+            is_base_pool_0,
+            exact_out,
         ),
         6 => cpi::whirlpool::execute_whirlpool_swap_hop3(
             amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            step,
+            is_base_pool_0,
+        ),
+        7 => cpi::raydium::execute_raydium_openbook_swap_hop3(
+            amount_in,
+            min_out,
             header_accounts,
             pool_accounts,
             step,
-            is_wsol_pool_0,
+            is_base_pool_0,
+            exact_out,
+        ),
+        8 => cpi::pump_fun::execute_pump_fun_swap_hop3(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            step,
+            pump_base_amount_out,
+        ),
+        9 => cpi::dammv1::execute_dammv1_swap_hop3(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            step,
+            is_base_pool_0,
+        ),
+        10 => cpi::dbc::execute_dbc_swap_hop3(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            step,
+            is_base_pool_0,
+        ),
+        11 => cpi::launchlab::execute_launchlab_swap_hop3(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            step,
+            is_base_pool_0,
+        ),
+        _ => Err(PinocchioCpiError::UnsupportedPoolType.into()),
+    }
+}
+
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn execute_swap_optimized_route(
+    pool_type: u8,
+    amount_in: u64,
+    min_out: u64,
+    header_accounts: &[AccountInfo],
+    pool_accounts: &[AccountInfo],
+    input: &utils::TokenSlot,
+    output: &utils::TokenSlot,
+    is_zero_to_one: bool,
+    pump_base_amount_out: u64,
+    exact_out: bool,
+) -> ProgramResult {
+    utils::check_exact_out(pool_type, exact_out, min_out)?;
+
+    match pool_type {
+        0 => cpi::cpmm::execute_cpmm_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+            exact_out,
+        ),
+        1 => cpi::dlmm::execute_dlmm_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+        ),
+        2 => cpi::dammv2::execute_dammv2_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+        ),
+        3 => cpi::pump::execute_pump_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+            pump_base_amount_out,
+        ),
+        4 => cpi::raydium::execute_raydium_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+            exact_out,
+        ),
+        5 => cpi::clmm::execute_clmm_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+            exact_out,
+        ),
+        6 => cpi::whirlpool::execute_whirlpool_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+        ),
+        7 => cpi::raydium::execute_raydium_openbook_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+            exact_out,
+        ),
+        8 => cpi::pump_fun::execute_pump_fun_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            pump_base_amount_out,
+        ),
+        9 => cpi::dammv1::execute_dammv1_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+        ),
+        10 => cpi::dbc::execute_dbc_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
+        ),
+        11 => cpi::launchlab::execute_launchlab_swap_route(
+            amount_in,
+            min_out,
+            header_accounts,
+            pool_accounts,
+            input,
+            output,
+            is_zero_to_one,
         ),
         _ => Err(PinocchioCpiError::UnsupportedPoolType.into()),
     }
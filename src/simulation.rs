@@ -0,0 +1,155 @@
+//! # 模拟模式返回数据
+//!
+//! 模拟模式下通过 `set_return_data` 返回带版本号的结构化数据：
+//!
+//! ```text
+//! [0]      version (= SIMULATION_VERSION)
+//! [1]      hop_count
+//! [2..10]  pnl (i64, 最终余额 - 初始余额，亏损时为负)
+//! 之后每跳 SIMULATION_HOP_LEN 字节：
+//!   [0..8]   amount_in (u64)
+//!   [8..16]  amount_out (u64, 输出token账户余额的增量)
+//!   [16..24] compute_units (u64, 该跳消耗的计算单元)
+//! ```
+//!
+//! 逐跳记录只保存在 SIMULATION_MAX_HOPS 个定长槽位中，返回数据在 `finish` 里组装。
+//! 模拟模式下跳数超过 SIMULATION_MAX_HOPS 的路由在执行前由 `check_hop_count` 拒绝，
+//! 返回数据中的 hop_count 总是路由的完整跳数。
+
+use core::mem::MaybeUninit;
+use pinocchio::account_info::AccountInfo;
+use pinocchio::cpi::set_return_data;
+use crate::error::{PinocchioCpiError, PinocchioResult};
+use crate::utils;
+
+/// 🚀 返回数据布局版本
+pub const SIMULATION_VERSION: u8 = 2;
+/// 🚀 头部长度: version + hop_count + pnl
+pub const SIMULATION_HEADER_LEN: usize = 10;
+/// 🚀 每跳长度: amount_in + amount_out + compute_units
+pub const SIMULATION_HOP_LEN: usize = 24;
+/// 🚀 返回数据中最多记录的跳数，模拟模式下跳数更多的路由返回 SimulationTooManyHops
+pub const SIMULATION_MAX_HOPS: usize = 16;
+/// 🚀 返回数据最大长度
+pub const SIMULATION_MAX_LEN: usize = SIMULATION_HEADER_LEN + SIMULATION_MAX_HOPS * SIMULATION_HOP_LEN;
+
+/// 🚀 单跳记录，布局即返回数据中每跳的字节
+pub type SimulationHop = [u8; SIMULATION_HOP_LEN];
+
+const UNINIT_BYTE: MaybeUninit<u8> = MaybeUninit::uninit();
+const UNINIT_HOP: MaybeUninit<SimulationHop> = MaybeUninit::uninit();
+
+/// 🚀 模拟模式下逐跳记录输入/输出和计算单元，非模拟模式下所有方法均为空操作
+pub struct Simulation {
+    enabled: bool,
+    hop_count: usize,
+    balance_before: u64,
+    compute_units_before: u64,
+    // 🚀 未初始化的逐跳槽位，非模拟模式下不产生清零开销
+    hops: [MaybeUninit<SimulationHop>; SIMULATION_MAX_HOPS],
+}
+
+impl Simulation {
+    #[inline(always)]
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            hop_count: 0,
+            balance_before: 0,
+            compute_units_before: 0,
+            hops: [UNINIT_HOP; SIMULATION_MAX_HOPS],
+        }
+    }
+
+    /// 🚀 在一跳开始前记录输出账户余额和剩余计算单元
+    #[inline(always)]
+    pub fn start_hop(&mut self, output_account: &AccountInfo) -> PinocchioResult<()> {
+        if self.enabled {
+            self.balance_before = utils::get_token_balance(output_account)?;
+            self.compute_units_before = remaining_compute_units();
+        }
+        Ok(())
+    }
+
+    /// 🚀 一跳结束后记录 amount_in / amount_out / compute_units
+    #[inline(always)]
+    pub fn end_hop(&mut self, amount_in: u64, output_account: &AccountInfo) -> PinocchioResult<()> {
+        if self.enabled && self.hop_count < SIMULATION_MAX_HOPS {
+            let compute_units = self.compute_units_before.saturating_sub(remaining_compute_units());
+            let amount_out = utils::get_token_balance(output_account)?.saturating_sub(self.balance_before);
+
+            self.hops[self.hop_count].write(encode_hop(amount_in, amount_out, compute_units));
+            self.hop_count += 1;
+        }
+        Ok(())
+    }
+
+    /// 🚀 组装返回数据给客户端，利润不足时同样返回（pnl 为负或低于阈值）
+    ///
+    /// 返回数据缓冲区只在本函数的栈帧中，不内联进各入口函数
+    #[inline(never)]
+    pub fn finish(&self, initial_balance: u64, final_balance: u64) {
+        if !self.enabled {
+            return;
+        }
+
+        let pnl = (final_balance as i128 - initial_balance as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+
+        // SAFETY: [0..hop_count) 已由 end_hop 写入
+        let hops = unsafe { core::slice::from_raw_parts(self.hops.as_ptr() as *const SimulationHop, self.hop_count) };
+        let mut buffer = [UNINIT_BYTE; SIMULATION_MAX_LEN];
+        set_return_data(encode(&mut buffer, pnl, hops));
+    }
+}
+
+/// 🚀 模拟模式下路由的跳数不能超过 SIMULATION_MAX_HOPS，否则返回数据会缺少后面的跳
+#[inline(always)]
+pub fn check_hop_count(is_simulate: bool, hop_count: usize) -> PinocchioResult<()> {
+    if is_simulate && hop_count > SIMULATION_MAX_HOPS {
+        return Err(PinocchioCpiError::SimulationTooManyHops);
+    }
+    Ok(())
+}
+
+/// 🚀 单跳记录: amount_in + amount_out + compute_units
+#[inline(always)]
+pub fn encode_hop(amount_in: u64, amount_out: u64, compute_units: u64) -> SimulationHop {
+    let mut hop = [0; SIMULATION_HOP_LEN];
+    hop[0..8].copy_from_slice(&amount_in.to_le_bytes());
+    hop[8..16].copy_from_slice(&amount_out.to_le_bytes());
+    hop[16..24].copy_from_slice(&compute_units.to_le_bytes());
+    hop
+}
+
+/// 🚀 按返回数据布局把头部和前 SIMULATION_MAX_HOPS 跳写入 `buffer`，返回已写入的部分
+#[inline(always)]
+pub fn encode<'a>(buffer: &'a mut [MaybeUninit<u8>; SIMULATION_MAX_LEN], pnl: i64, hops: &[SimulationHop]) -> &'a [u8] {
+    let hops = &hops[..hops.len().min(SIMULATION_MAX_HOPS)];
+    write(buffer, 0, &[SIMULATION_VERSION, hops.len() as u8]);
+    write(buffer, 2, &pnl.to_le_bytes());
+    for (i, hop) in hops.iter().enumerate() {
+        write(buffer, SIMULATION_HEADER_LEN + i * SIMULATION_HOP_LEN, hop);
+    }
+
+    let len = SIMULATION_HEADER_LEN + hops.len() * SIMULATION_HOP_LEN;
+    // SAFETY: [0..len) 已全部写入
+    unsafe { core::slice::from_raw_parts(buffer.as_ptr() as *const u8, len) }
+}
+
+#[inline(always)]
+fn write(buffer: &mut [MaybeUninit<u8>], offset: usize, bytes: &[u8]) {
+    for (slot, byte) in buffer[offset..offset + bytes.len()].iter_mut().zip(bytes) {
+        slot.write(*byte);
+    }
+}
+
+/// 🚀 剩余计算单元，链下构建时为0
+#[inline(always)]
+fn remaining_compute_units() -> u64 {
+    #[cfg(target_os = "solana")]
+    unsafe {
+        pinocchio::syscalls::sol_remaining_compute_units()
+    }
+    #[cfg(not(target_os = "solana"))]
+    0
+}
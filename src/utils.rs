@@ -1,5 +1,6 @@
-use crate::error::PinocchioResult;
-use pinocchio::account_info::AccountInfo;
+use crate::constants::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, WSOL_MINT};
+use crate::error::{PinocchioCpiError, PinocchioResult};
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
 
 /// 每种池类型需要的账户数量
 pub const CPMM_ACCOUNT_COUNT: usize = 7;
@@ -9,9 +10,16 @@ pub const PUMP_ACCOUNT_COUNT: usize = 16;
 pub const RAYDIUM_ACCOUNT_COUNT: usize = 5;
 pub const CLMM_ACCOUNT_COUNT: usize = 10;
 pub const WHIRLPOOL_ACCOUNT_COUNT: usize = 8;
+pub const RAYDIUM_OPENBOOK_ACCOUNT_COUNT: usize = 14;
+pub const PUMP_FUN_ACCOUNT_COUNT: usize = 13;
+/// Pump.fun 卖出不需要买入时解包用的临时 WSOL 账户 [12]
+pub const PUMP_FUN_SELL_ACCOUNT_COUNT: usize = 12;
+pub const DAMMV1_ACCOUNT_COUNT: usize = 13;
+pub const DBC_ACCOUNT_COUNT: usize = 7;
+pub const LAUNCHLAB_ACCOUNT_COUNT: usize = 8;
 
 // 使用编译时常量数组，零运行时开销
-const POOL_COUNTS: [usize; 7] = [
+const POOL_COUNTS: [usize; 12] = [
     CPMM_ACCOUNT_COUNT,      // 0
     DLMM_ACCOUNT_COUNT,      // 1
     DAMMV2_ACCOUNT_COUNT,    // 2
@@ -19,21 +27,147 @@ const POOL_COUNTS: [usize; 7] = [
     RAYDIUM_ACCOUNT_COUNT,   // 4
     CLMM_ACCOUNT_COUNT,      // 5
     WHIRLPOOL_ACCOUNT_COUNT, // 6
+    RAYDIUM_OPENBOOK_ACCOUNT_COUNT, // 7
+    PUMP_FUN_ACCOUNT_COUNT,  // 8
+    DAMMV1_ACCOUNT_COUNT,    // 9
+    DBC_ACCOUNT_COUNT,       // 10
+    LAUNCHLAB_ACCOUNT_COUNT, // 11
 ];
 
+/// 🚀 方向字节：bit0 = is_base_pool_0 / is_zero_to_one，bit1 = 该跳为精确输出（仅 CPMM / Raydium AMM v4 / CLMM），
+/// 此时该跳的 min_out 为精确输出数量，该跳的输入数量为最大输入
+pub const DIRECTION_ZERO_TO_ONE: u8 = 1;
+pub const DIRECTION_EXACT_OUT: u8 = 2;
+
+/// 🚀 2hop/3hop 指令固定部分长度（不含可选的每跳 min_out）
+pub const SWAP_DATA_LEN: usize = 25;
+pub const SWAP_DATA_LEN_3HOP: usize = 27;
+
+/// 🚀 v2 指令：min_profit 扩展为 u64，并追加 u16 的 min_profit_bps
+pub const SWAP_DATA_LEN_V2: usize = 31;
+pub const SWAP_DATA_LEN_3HOP_V2: usize = 33;
+
+/// bps 分母
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+/// 🚀 2hop/3hop 的header账户数量
+pub const HEADER_ACCOUNT_COUNT: usize = 9;
+pub const HEADER_ACCOUNT_COUNT_3HOP: usize = 12;
+
+/// 🚀 flags字节：bit0 = 模拟模式，bit1 = 执行前校验header账户，bit2 = 从利润中支付小费，
+/// bit3 = amount_in 由包住本指令的顶层 Solend 闪电贷借入，bit4 = 第一跳前把 payer 的 SOL 包装进基础资产账户，
+/// bit5 = 利润校验后关闭基础资产账户解包为 SOL，bit6 = 路由拆分（仅N-hop路由），
+/// bit7 = 下一跳连同中间token账户原有的余额一起卖出
+pub const FLAG_SIMULATE: u8 = 1;
+pub const FLAG_VALIDATE_ACCOUNTS: u8 = 2;
+pub const FLAG_TIP: u8 = 4;
+pub const FLAG_FLASH_LOAN: u8 = 8;
+pub const FLAG_WRAP_SOL: u8 = 16;
+pub const FLAG_UNWRAP_SOL: u8 = 32;
+pub const FLAG_SPLIT: u8 = 64;
+pub const FLAG_INCLUDE_EXISTING_BALANCE: u8 = 128;
+
+/// 🚀 FLAG_TIP 时紧跟在固定部分之后的小费参数: tip_lamports(u64) + tip_bps(u16)
+pub const TIP_DATA_LEN: usize = 10;
+/// 🚀 FLAG_TIP 时紧跟在 header 账户之后、第一个池账户之前的 tip_account
+/// （转账 CPI 的被调用程序 System Program 追加在所有池账户之后，与 FLAG_WRAP_SOL 共用同一个）
+pub const TIP_ACCOUNT_COUNT: usize = 1;
+/// 🚀 FLAG_WRAP_SOL 时紧跟在小费参数之后的包装金额: wrap_lamports(u64)
+pub const WRAP_DATA_LEN: usize = 8;
+/// 🚀 FLAG_FLASH_LOAN 时紧跟在 header（和小费账户）之后的 Solend reserve + instructions sysvar，
+/// 手续费从 reserve 读取，指令数据中没有闪电贷参数
+pub const FLASH_LOAN_ACCOUNT_COUNT: usize = 2;
+
+/// SPL Token / Token-2022 账户中 mint 字段的长度（账户数据前32字节）
+const TOKEN_ACCOUNT_MINT_LEN: usize = 32;
+/// 读取余额所需的最小账户数据长度（amount 位于 [64..72]）
+const TOKEN_ACCOUNT_MIN_LEN: usize = 72;
+
 /// 🚀 优化的指令数据解析结构
 #[derive(Debug)]
 pub struct SwapParams {
     pub buy: u8,
     pub mid: Option<u8>,
     pub sell: u8,
-    pub is_wsol_pool_0_buy: bool,
+    pub is_base_pool_0_buy: bool,
     pub is_mid_zero_to_one: Option<bool>,
-    pub is_wsol_pool_0_sell: bool,
+    pub is_base_pool_0_sell: bool,
+    pub buy_exact_out: bool,
+    pub mid_exact_out: bool,
+    pub sell_exact_out: bool,
     pub is_simulate: bool,
+    pub validate_accounts: bool,
     pub amount_in: u64,
     pub pump_base_amount_out: u64,
-    pub min_profit: u32,
+    pub min_profit: u64,
+    pub min_profit_bps: u16, // 相对 amount_in 的利润下限，v1 指令中为0
+    pub has_tip: bool,
+    pub tip_lamports: u64,   // 固定小费
+    pub tip_bps: u16,        // 按利润比例的小费
+    pub has_flash_loan: bool,
+    pub has_wrap_sol: bool,
+    pub wrap_lamports: u64,  // 第一跳前包装进基础资产账户的 lamports
+    pub unwrap_sol: bool,
+    pub include_existing_balance: bool, // 为false时下一跳只使用本跳收到的数量
+    pub buy_min_out: u64,
+    pub mid_min_out: u64,
+    pub sell_min_out: u64,
+}
+
+/// 🚀 N-hop路由指令头部长度: hop_count + header_count + flags + amount_in + pump_base_amount_out + min_profit
+pub const ROUTE_HEADER_LEN: usize = 23;
+/// 🚀 v2 路由指令头部长度: min_profit 为 u64 并追加 min_profit_bps
+pub const ROUTE_HEADER_LEN_V2: usize = 29;
+/// 🚀 每个hop的描述长度: pool_type + is_zero_to_one + input_index + output_index
+pub const ROUTE_HOP_LEN: usize = 4;
+/// 🚀 FLAG_SPLIT 时每个hop追加 share_kind(u8) + share(u64)
+pub const ROUTE_HOP_LEN_SPLIT: usize = 13;
+/// 🚀 share_kind：份额为该腿输入的 bps / 固定数量
+pub const SHARE_KIND_BPS: u8 = 0;
+pub const SHARE_KIND_AMOUNT: u8 = 1;
+
+/// 🚀 N-hop路由指令解析结构
+#[derive(Debug)]
+pub struct RouteParams<'a> {
+    pub hop_count: usize,
+    pub header_count: usize,
+    pub is_simulate: bool,
+    pub validate_accounts: bool,
+    pub amount_in: u64,
+    pub pump_base_amount_out: u64,
+    pub min_profit: u64,
+    pub min_profit_bps: u16,
+    pub has_tip: bool,
+    pub tip_lamports: u64,
+    pub tip_bps: u16,
+    pub has_flash_loan: bool,
+    pub has_wrap_sol: bool,
+    pub wrap_lamports: u64,
+    pub unwrap_sol: bool,
+    pub include_existing_balance: bool,
+    pub is_split: bool,
+    pub hop_len: usize, // 每个hop描述的长度，拆分路由时为 ROUTE_HOP_LEN_SPLIT
+    pub hops: &'a [u8],
+    pub min_outs: &'a [u8],
+}
+
+/// 🚀 单个hop描述
+#[derive(Debug, Clone, Copy)]
+pub struct RouteHop {
+    pub pool_type: u8,
+    pub is_zero_to_one: bool, // 输入token是否为池的token0 (x / a / base)
+    pub is_exact_out: bool,   // 精确输出，min_out 为输出数量
+    pub input_index: usize,   // 输入token账户在header中的下标
+    pub output_index: usize,  // 输出token账户在header中的下标
+    pub share_kind: u8,       // 拆分路由中的份额类型，非拆分路由为0
+    pub share: u64,
+}
+
+/// 🚀 header中一个token的 mint / token_program / token_account 三元组
+pub struct TokenSlot<'a> {
+    pub mint: &'a AccountInfo,
+    pub program: &'a AccountInfo,
+    pub account: &'a AccountInfo,
 }
 
 /// 🚀 超高效获取池账户数量 - 直接索引访问，零边界检查
@@ -43,59 +177,545 @@ pub fn get_pool_info_by_num(buy: u8) -> usize {
     unsafe { *POOL_COUNTS.get_unchecked(buy as usize) }
 }
 
-/// 🚀 高效解析指令数据
+/// 🚀 高效解析指令数据，`is_v2` 时按 u64 min_profit + u16 min_profit_bps 解析
 #[inline(always)]
-pub fn parse_instruction_data(data: &[u8], is_3hop: bool) -> PinocchioResult<SwapParams> {
- 
+pub fn parse_instruction_data(data: &[u8], is_3hop: bool, is_v2: bool) -> PinocchioResult<SwapParams> {
+    #[cfg(feature = "checked")]
+    {
+        let min_len = match (is_3hop, is_v2) {
+            (false, false) => SWAP_DATA_LEN,
+            (true, false) => SWAP_DATA_LEN_3HOP,
+            (false, true) => SWAP_DATA_LEN_V2,
+            (true, true) => SWAP_DATA_LEN_3HOP_V2,
+        };
+        if data.len() < min_len {
+            return Err(PinocchioCpiError::InstructionDataTooShort);
+        }
+        let flags = if is_3hop { data[6] } else { data[4] };
+        if data.len() < min_len + optional_data_len(flags) {
+            return Err(PinocchioCpiError::InstructionDataTooShort);
+        }
+    }
+
     let params = if !is_3hop {
+        let (min_profit, min_profit_bps, tip) = read_min_profit(data, 21, is_v2);
+        let (has_tip, tip_lamports, tip_bps, wrap) = read_tip(data, tip, data[4]);
+        let (has_wrap_sol, wrap_lamports, min_outs) = read_wrap(data, wrap, data[4]);
         SwapParams {
             buy: data[0],
             mid: None,
             sell: data[1],
-            is_wsol_pool_0_buy: data[2] == 1,
+            is_base_pool_0_buy: data[2] & DIRECTION_ZERO_TO_ONE != 0,
             is_mid_zero_to_one: None,
-            is_wsol_pool_0_sell: data[3] == 1,
-            is_simulate: data[4] == 1,
+            is_base_pool_0_sell: data[3] & DIRECTION_ZERO_TO_ONE != 0,
+            buy_exact_out: data[2] & DIRECTION_EXACT_OUT != 0,
+            mid_exact_out: false,
+            sell_exact_out: data[3] & DIRECTION_EXACT_OUT != 0,
+            is_simulate: data[4] & FLAG_SIMULATE != 0,
+            validate_accounts: data[4] & FLAG_VALIDATE_ACCOUNTS != 0,
             amount_in: u64::from_le_bytes(data[5..13].try_into().unwrap()),
             pump_base_amount_out: u64::from_le_bytes(data[13..21].try_into().unwrap()),
-            min_profit: u32::from_le_bytes(data[21..25].try_into().unwrap()),
+            min_profit,
+            min_profit_bps,
+            has_tip,
+            tip_lamports,
+            tip_bps,
+            has_flash_loan: data[4] & FLAG_FLASH_LOAN != 0,
+            has_wrap_sol,
+            wrap_lamports,
+            unwrap_sol: data[4] & FLAG_UNWRAP_SOL != 0,
+            include_existing_balance: data[4] & FLAG_INCLUDE_EXISTING_BALANCE != 0,
+            buy_min_out: read_optional_u64(data, min_outs),
+            mid_min_out: 0,
+            sell_min_out: read_optional_u64(data, min_outs + 8),
         }
     } else {
+        let (min_profit, min_profit_bps, tip) = read_min_profit(data, 23, is_v2);
+        let (has_tip, tip_lamports, tip_bps, wrap) = read_tip(data, tip, data[6]);
+        let (has_wrap_sol, wrap_lamports, min_outs) = read_wrap(data, wrap, data[6]);
         SwapParams {
             buy: data[0],
             mid: Some(data[1]),
             sell: data[2],
-            is_wsol_pool_0_buy: data[3] == 1,
-            is_mid_zero_to_one: Some(data[4] == 1),
-            is_wsol_pool_0_sell: data[5] == 1,
-            is_simulate: data[6] == 1,
+            is_base_pool_0_buy: data[3] & DIRECTION_ZERO_TO_ONE != 0,
+            is_mid_zero_to_one: Some(data[4] & DIRECTION_ZERO_TO_ONE != 0),
+            is_base_pool_0_sell: data[5] & DIRECTION_ZERO_TO_ONE != 0,
+            buy_exact_out: data[3] & DIRECTION_EXACT_OUT != 0,
+            mid_exact_out: data[4] & DIRECTION_EXACT_OUT != 0,
+            sell_exact_out: data[5] & DIRECTION_EXACT_OUT != 0,
+            is_simulate: data[6] & FLAG_SIMULATE != 0,
+            validate_accounts: data[6] & FLAG_VALIDATE_ACCOUNTS != 0,
             amount_in: u64::from_le_bytes(data[7..15].try_into().unwrap()),
             pump_base_amount_out: u64::from_le_bytes(data[15..23].try_into().unwrap()),
-            min_profit: u32::from_le_bytes(data[23..27].try_into().unwrap()),
+            min_profit,
+            min_profit_bps,
+            has_tip,
+            tip_lamports,
+            tip_bps,
+            has_flash_loan: data[6] & FLAG_FLASH_LOAN != 0,
+            has_wrap_sol,
+            wrap_lamports,
+            unwrap_sol: data[6] & FLAG_UNWRAP_SOL != 0,
+            include_existing_balance: data[6] & FLAG_INCLUDE_EXISTING_BALANCE != 0,
+            buy_min_out: read_optional_u64(data, min_outs),
+            mid_min_out: read_optional_u64(data, min_outs + 8),
+            sell_min_out: read_optional_u64(data, min_outs + 16),
         }
     };
 
     Ok(params)
 }
 
+/// 🚀 反向2hop与2hop共用数据布局，但先执行的 data[0] / data[2] 是把 token 卖成基础资产的池，
+/// 解析后交换两侧的字段，使 `sell` / `buy` 与池的实际方向一致
+///
+/// 反向路由的利润以 token 计，无法换算成小费的 lamports，因此拒绝 FLAG_TIP
+#[inline(always)]
+pub fn parse_reverse_instruction_data(data: &[u8], is_v2: bool) -> PinocchioResult<SwapParams> {
+    let mut params = parse_instruction_data(data, false, is_v2)?;
+    if params.has_tip {
+        return Err(PinocchioCpiError::TipNotSupportedOnReverse);
+    }
+
+    core::mem::swap(&mut params.buy, &mut params.sell);
+    core::mem::swap(&mut params.is_base_pool_0_buy, &mut params.is_base_pool_0_sell);
+    core::mem::swap(&mut params.buy_exact_out, &mut params.sell_exact_out);
+    core::mem::swap(&mut params.buy_min_out, &mut params.sell_min_out);
+
+    Ok(params)
+}
+
+/// 🚀 读取利润阈值，返回 (min_profit, min_profit_bps, 固定部分结束位置)
+/// - v1: u32 min_profit
+/// - v2: u64 min_profit + u16 min_profit_bps
+#[inline(always)]
+fn read_min_profit(data: &[u8], offset: usize, is_v2: bool) -> (u64, u16, usize) {
+    if is_v2 {
+        (
+            u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap()),
+            u16::from_le_bytes(data[offset + 8..offset + 10].try_into().unwrap()),
+            offset + 10,
+        )
+    } else {
+        (u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as u64, 0, offset + 4)
+    }
+}
+
+/// 🚀 读取可选的小费参数，返回 (has_tip, tip_lamports, tip_bps, 小费参数结束位置)
+#[inline(always)]
+fn read_tip(data: &[u8], offset: usize, flags: u8) -> (bool, u64, u16, usize) {
+    if flags & FLAG_TIP != 0 {
+        (
+            true,
+            u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap()),
+            u16::from_le_bytes(data[offset + 8..offset + 10].try_into().unwrap()),
+            offset + TIP_DATA_LEN,
+        )
+    } else {
+        (false, 0, 0, offset)
+    }
+}
+
+/// 🚀 读取可选的包装金额，返回 (has_wrap_sol, wrap_lamports, 包装参数结束位置)
+#[inline(always)]
+fn read_wrap(data: &[u8], offset: usize, flags: u8) -> (bool, u64, usize) {
+    if flags & FLAG_WRAP_SOL != 0 {
+        (
+            true,
+            u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap()),
+            offset + WRAP_DATA_LEN,
+        )
+    } else {
+        (false, 0, offset)
+    }
+}
+
+/// 🚀 固定部分之后由 flags 决定的可选参数长度（小费参数 + 包装金额）
+#[inline(always)]
+pub fn optional_data_len(flags: u8) -> usize {
+    let mut len = 0;
+    if flags & FLAG_TIP != 0 {
+        len += TIP_DATA_LEN;
+    }
+    if flags & FLAG_WRAP_SOL != 0 {
+        len += WRAP_DATA_LEN;
+    }
+    len
+}
+
+/// 🚀 小费金额：tip_lamports + profit * tip_bps / 10000，且不超过利润本身
+#[inline(always)]
+pub fn compute_tip(profit: u64, tip_lamports: u64, tip_bps: u16) -> u64 {
+    let share = (profit as u128 * tip_bps as u128 / BPS_DENOMINATOR) as u64;
+    tip_lamports.saturating_add(share).min(profit)
+}
+
+/// 🚀 FLAG_TIP 时从 header 之后的账户中取出小费账户，返回 (tip_account, 池账户)
+///
+/// 小费账户的位置固定：2hop 为账户 [9]，3hop 为 [12]，N-hop 为 [header_count]，
+/// 与包装 SOL、闪电贷等其他可选功能无关
+#[inline(always)]
+pub fn split_tip_account(accounts: &[AccountInfo], has_tip: bool) -> PinocchioResult<(Option<&AccountInfo>, &[AccountInfo])> {
+    if !has_tip {
+        return Ok((None, accounts));
+    }
+    let (tip_accounts, pool_accounts) = split_accounts(accounts, TIP_ACCOUNT_COUNT)?;
+    Ok((Some(&tip_accounts[0]), pool_accounts))
+}
+
+/// 🚀 FLAG_FLASH_LOAN 时从小费账户之后取出闪电贷账户，返回 ([reserve, instructions sysvar], 池账户)
+#[inline(always)]
+pub fn split_flash_loan_accounts(accounts: &[AccountInfo], has_flash_loan: bool) -> PinocchioResult<(Option<&[AccountInfo]>, &[AccountInfo])> {
+    if !has_flash_loan {
+        return Ok((None, accounts));
+    }
+    let (flash_loan_accounts, pool_accounts) = split_accounts(accounts, FLASH_LOAN_ACCOUNT_COUNT)?;
+    Ok((Some(flash_loan_accounts), pool_accounts))
+}
+
+/// 🚀 小费以 payer 的 lamports 支付、按基础资产的利润计算，只有基础资产为 WSOL 时单位一致
+#[inline(always)]
+pub fn check_tip(has_tip: bool, header_accounts: &[AccountInfo]) -> PinocchioResult<()> {
+    if has_tip && header_accounts[1].key() != &WSOL_MINT {
+        return Err(PinocchioCpiError::TipRequiresWsolBase);
+    }
+    Ok(())
+}
+
+/// 🚀 该跳是否为 Pump 买入（会使用 pump_base_amount_out）：Pump AMM 用 quote 买 base，
+/// 或在 Pump.fun 联合曲线上用基础资产（SOL）买入
+#[inline(always)]
+pub fn is_pump_buy(pool_type: u8, is_zero_to_one: bool, is_base_input: bool) -> bool {
+    match pool_type {
+        3 => !is_zero_to_one,
+        8 => is_base_input,
+        _ => false,
+    }
+}
+
+/// 🚀 Pump.fun 联合曲线以原生 SOL 收付，经由基础资产账户包装 / 解包，基础资产必须是 WSOL
+#[inline(always)]
+pub fn check_pump_fun_base(uses_pump_fun: bool, header_accounts: &[AccountInfo]) -> PinocchioResult<()> {
+    if uses_pump_fun && header_accounts[1].key() != &WSOL_MINT {
+        return Err(PinocchioCpiError::PumpFunRequiresWsolBase);
+    }
+    Ok(())
+}
+
+/// 🚀 Pump.fun 买入会关闭该跳的临时 WSOL 账户，每条路由最多一个 Pump.fun 买入跳
+#[inline(always)]
+pub fn check_pump_fun_buy_count(pump_fun_buy_count: usize) -> PinocchioResult<()> {
+    if pump_fun_buy_count > 1 {
+        return Err(PinocchioCpiError::MultiplePumpFunBuys);
+    }
+    Ok(())
+}
+
+/// 🚀 pump_base_amount_out 是单个 token 数量，多于一个 Pump 买入跳时无法确定对应哪一跳，必须为0
+#[inline(always)]
+pub fn check_pump_base_amount_out(pump_base_amount_out: u64, pump_buy_count: usize) -> PinocchioResult<()> {
+    if pump_base_amount_out != 0 && pump_buy_count > 1 {
+        return Err(PinocchioCpiError::AmbiguousPumpBaseAmountOut);
+    }
+    Ok(())
+}
+
+/// 🚀 利润校验：最终余额必须严格大于 初始余额 + max(min_profit, amount_in * min_profit_bps / 10000)
+///
+/// 阈值加法溢出时视为无法满足，返回 ArbitrageFailed；成功时返回利润
+#[inline(always)]
+pub fn check_profit(
+    initial_balance: u64,
+    final_balance: u64,
+    amount_in: u64,
+    min_profit: u64,
+    min_profit_bps: u16,
+) -> PinocchioResult<u64> {
+    let bps_floor = amount_in as u128 * min_profit_bps as u128 / BPS_DENOMINATOR;
+    let threshold = u64::try_from(bps_floor).unwrap_or(u64::MAX).max(min_profit);
+
+    match initial_balance.checked_add(threshold) {
+        Some(required) if final_balance > required => Ok(final_balance - initial_balance),
+        _ => Err(PinocchioCpiError::ArbitrageFailed),
+    }
+}
+
+/// 🚀 读取可选的每跳 min_out，数据不足时视为0（不限制）
+#[inline(always)]
+fn read_optional_u64(data: &[u8], offset: usize) -> u64 {
+    match data.get(offset..offset + 8) {
+        Some(bytes) => u64::from_le_bytes(bytes.try_into().unwrap()),
+        None => 0,
+    }
+}
+
+/// 🚀 精确输出只支持 CPMM(0)、Raydium AMM v4(4 / 7) 和 CLMM(5)，且输出数量（该跳的 min_out 槽位）不能为0
+#[inline(always)]
+pub fn check_exact_out(pool_type: u8, exact_out: bool, min_out: u64) -> PinocchioResult<()> {
+    if !exact_out {
+        return Ok(());
+    }
+    if !matches!(pool_type, 0 | 4 | 5 | 7) {
+        return Err(PinocchioCpiError::ExactOutputNotSupported);
+    }
+    if min_out == 0 {
+        return Err(PinocchioCpiError::InvalidTradeAmount);
+    }
+    Ok(())
+}
+
 /// 🚀 验证池类型并返回账户数量 - 一次调用获取两个值
+#[cfg(not(feature = "checked"))]
 #[inline(always)]
 pub fn validate_pool_types(buy: u8) -> PinocchioResult<usize> {
     let buy_count = get_pool_info_by_num(buy);
 
-    // if buy_count == 111 || sell_count == 111 {
-    //     return Err(PinocchioCpiError::UnsupportedPoolType);
-    // }
-
     Ok(buy_count)
 }
 
+/// 🛡️ checked: 池类型越界时返回 UnsupportedPoolType
+#[cfg(feature = "checked")]
+#[inline(always)]
+pub fn validate_pool_types(buy: u8) -> PinocchioResult<usize> {
+    POOL_COUNTS
+        .get(buy as usize)
+        .copied()
+        .ok_or(PinocchioCpiError::UnsupportedPoolType)
+}
+
+/// 🚀 该跳的池账户数量：Pump.fun 卖出（输入不是基础资产）不需要临时 WSOL 账户，比买入少一个
+#[inline(always)]
+pub fn hop_account_count(pool_type: u8, is_base_input: bool) -> PinocchioResult<usize> {
+    let count = validate_pool_types(pool_type)?;
+    if pool_type == 8 && !is_base_input {
+        return Ok(PUMP_FUN_SELL_ACCOUNT_COUNT);
+    }
+    Ok(count)
+}
+
+/// 🚀 按数量切分账户列表
+#[cfg(not(feature = "checked"))]
+#[inline(always)]
+pub fn split_accounts(accounts: &[AccountInfo], count: usize) -> PinocchioResult<(&[AccountInfo], &[AccountInfo])> {
+    Ok(accounts.split_at(count))
+}
+
+/// 🛡️ checked: 账户不足时返回 NotEnoughAccounts 而不是 panic
+#[cfg(feature = "checked")]
+#[inline(always)]
+pub fn split_accounts(accounts: &[AccountInfo], count: usize) -> PinocchioResult<(&[AccountInfo], &[AccountInfo])> {
+    if accounts.len() < count {
+        return Err(PinocchioCpiError::NotEnoughAccounts);
+    }
+    Ok(accounts.split_at(count))
+}
+
+/// 🛡️ checked: 路由hop中的token账户下标必须是基础资产槽位(2)或header内的三元组账户(8, 11, ...)
+#[cfg(feature = "checked")]
+#[inline(always)]
+pub fn check_token_index(header_accounts: &[AccountInfo], account_index: usize) -> PinocchioResult<()> {
+    if account_index >= header_accounts.len() {
+        return Err(PinocchioCpiError::NotEnoughAccounts);
+    }
+    // 2, 8, 11, 14, ... 均满足 % 3 == 2，[5] 是 memo_program 需排除
+    if account_index % 3 != 2 || account_index == 5 {
+        return Err(PinocchioCpiError::InvalidTokenAccountData);
+    }
+    Ok(())
+}
+
 /// 🚀 超高效获取 Token 余额 - 直接读取账户数据
 #[inline(always)]
 pub fn get_token_balance(token_account: &AccountInfo) -> PinocchioResult<u64> {
+    #[cfg(feature = "checked")]
+    if token_account.data_len() < TOKEN_ACCOUNT_MIN_LEN {
+        return Err(PinocchioCpiError::InvalidTokenAccountData);
+    }
+
     // 🚀 优化：直接unsafe读取，避免slice操作和错误检查
     unsafe {
         let data_ptr = token_account.data_ptr().add(64);
         Ok(core::ptr::read_unaligned(data_ptr as *const u64))
     }
 }
+
+/// 🚀 下一跳的输入数量：默认只使用本跳收到的数量（相对 `balance_before` 的增量），
+/// 中间token账户原有的余额（库存、dust）不会被卖出；`include_existing` 时使用全部余额
+#[inline(always)]
+pub fn get_received_amount(output_account: &AccountInfo, balance_before: u64, include_existing: bool) -> PinocchioResult<u64> {
+    let balance = get_token_balance(output_account)?;
+    if include_existing {
+        Ok(balance)
+    } else {
+        Ok(balance.saturating_sub(balance_before))
+    }
+}
+
+/// 🚀 解析N-hop路由指令数据，`is_v2` 时头部为 ROUTE_HEADER_LEN_V2
+#[inline(always)]
+pub fn parse_route_data(data: &[u8], is_v2: bool) -> PinocchioResult<RouteParams<'_>> {
+    let header_len = if is_v2 { ROUTE_HEADER_LEN_V2 } else { ROUTE_HEADER_LEN };
+    if data.len() < header_len {
+        return Err(PinocchioCpiError::InstructionDataTooShort);
+    }
+
+    let hop_count = data[0] as usize;
+    let (min_profit, min_profit_bps, tip) = read_min_profit(data, 19, is_v2);
+
+    // 可选：FLAG_TIP / FLAG_WRAP_SOL 时头部之后依次紧跟小费参数和包装金额
+    if data.len() < header_len + optional_data_len(data[2]) {
+        return Err(PinocchioCpiError::InstructionDataTooShort);
+    }
+    let (has_tip, tip_lamports, tip_bps, wrap) = read_tip(data, tip, data[2]);
+    let (has_wrap_sol, wrap_lamports, hops_start) = read_wrap(data, wrap, data[2]);
+    let (head, hops) = data.split_at(hops_start);
+
+    let is_split = data[2] & FLAG_SPLIT != 0;
+    let hop_len = if is_split { ROUTE_HOP_LEN_SPLIT } else { ROUTE_HOP_LEN };
+    if hop_count == 0 || hops.len() < hop_count * hop_len {
+        return Err(PinocchioCpiError::InstructionDataTooShort);
+    }
+
+    // 可选：hop列表之后紧跟每跳8字节的 min_out
+    let (hops, min_outs) = hops.split_at(hop_count * hop_len);
+    let min_outs = if min_outs.len() >= hop_count * 8 {
+        &min_outs[..hop_count * 8]
+    } else {
+        &[]
+    };
+
+    Ok(RouteParams {
+        hop_count,
+        header_count: head[1] as usize,
+        is_simulate: head[2] & FLAG_SIMULATE != 0,
+        validate_accounts: head[2] & FLAG_VALIDATE_ACCOUNTS != 0,
+        amount_in: u64::from_le_bytes(head[3..11].try_into().unwrap()),
+        pump_base_amount_out: u64::from_le_bytes(head[11..19].try_into().unwrap()),
+        min_profit,
+        min_profit_bps,
+        has_tip,
+        tip_lamports,
+        tip_bps,
+        has_flash_loan: head[2] & FLAG_FLASH_LOAN != 0,
+        has_wrap_sol,
+        wrap_lamports,
+        unwrap_sol: head[2] & FLAG_UNWRAP_SOL != 0,
+        include_existing_balance: head[2] & FLAG_INCLUDE_EXISTING_BALANCE != 0,
+        is_split,
+        hop_len,
+        hops,
+        min_outs,
+    })
+}
+
+/// 🚀 读取第 i 个hop描述
+#[inline(always)]
+pub fn get_route_hop(hops: &[u8], hop_len: usize, i: usize) -> RouteHop {
+    let hop = &hops[i * hop_len..(i + 1) * hop_len];
+    let (share_kind, share) = if hop_len == ROUTE_HOP_LEN_SPLIT {
+        (hop[4], u64::from_le_bytes(hop[5..13].try_into().unwrap()))
+    } else {
+        (SHARE_KIND_BPS, 0)
+    };
+    RouteHop {
+        pool_type: hop[0],
+        is_zero_to_one: hop[1] & DIRECTION_ZERO_TO_ONE != 0,
+        is_exact_out: hop[1] & DIRECTION_EXACT_OUT != 0,
+        input_index: hop[2] as usize,
+        output_index: hop[3] as usize,
+        share_kind,
+        share,
+    }
+}
+
+/// 🚀 拆分路由：输入和输出账户都相同的相邻hop属于同一腿，依次拆分该腿的输入
+#[inline(always)]
+pub fn is_same_leg(hop: &RouteHop, next: &RouteHop) -> bool {
+    hop.input_index == next.input_index && hop.output_index == next.output_index
+}
+
+/// 🚀 拆分路由中非最后一个子swap的输入：按份额计算，且不超过该腿剩余的输入
+#[inline(always)]
+pub fn split_share(hop: &RouteHop, leg_amount: u64, leg_remaining: u64) -> u64 {
+    let amount = if hop.share_kind == SHARE_KIND_AMOUNT {
+        hop.share
+    } else {
+        (leg_amount as u128 * hop.share as u128 / BPS_DENOMINATOR).min(u64::MAX as u128) as u64
+    };
+    amount.min(leg_remaining)
+}
+
+/// 🚀 读取第 i 个hop的 min_out，未提供时为0
+#[inline(always)]
+pub fn get_route_min_out(min_outs: &[u8], i: usize) -> u64 {
+    read_optional_u64(min_outs, i * 8)
+}
+
+/// 🚀 根据token账户下标取出对应的 mint / token_program
+///
+/// header布局与2hop/3hop保持一致：
+/// - 下标2为基础资产账户（WSOL、USDC、JitoSOL等任意 SPL Token / Token-2022 mint），对应 mint=[1]，program=[3]
+/// - 其余token按 (mint, program, account) 三个一组排列，从[6]开始，即账户下标为 8, 11, 14, ...
+#[inline(always)]
+pub fn get_token_slot(header_accounts: &[AccountInfo], account_index: usize) -> TokenSlot<'_> {
+    if account_index == 2 {
+        TokenSlot {
+            mint: &header_accounts[1],
+            program: &header_accounts[3],
+            account: &header_accounts[2],
+        }
+    } else {
+        TokenSlot {
+            mint: &header_accounts[account_index - 2],
+            program: &header_accounts[account_index - 1],
+            account: &header_accounts[account_index],
+        }
+    }
+}
+
+/// 🚀 可选的header账户校验，在任何CPI之前执行
+///
+/// - payer ([0]) 必须是签名者
+/// - [3] 是基础资产的token程序，SPL Token 或 Token-2022 均可；[4] 必须是真实的 Token-2022 程序
+/// - 基础资产 ([1] / [2]) 以及每个 (mint, program, account) 三元组中的 mint 和token账户
+///   必须由对应的token程序拥有，且token账户的 mint 字段与header中的 mint 一致
+pub fn validate_header_accounts(header_accounts: &[AccountInfo]) -> ProgramResult {
+    if !header_accounts[0].is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if header_accounts[4].key() != &TOKEN_2022_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    validate_token_slot(&get_token_slot(header_accounts, 2))?;
+
+    let mut account_index = 8;
+    while account_index < header_accounts.len() {
+        validate_token_slot(&get_token_slot(header_accounts, account_index))?;
+        account_index += 3;
+    }
+
+    Ok(())
+}
+
+/// 校验单个token三元组：program为真实token程序，mint 和账户归其所有，mint字段匹配
+fn validate_token_slot(slot: &TokenSlot) -> ProgramResult {
+    let program = slot.program.key();
+    if program != &TOKEN_PROGRAM_ID && program != &TOKEN_2022_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if !slot.mint.is_owned_by(program) || !slot.account.is_owned_by(program) {
+        return Err(PinocchioCpiError::AccountOwnerMismatch.into());
+    }
+
+    let data = slot.account.try_borrow_data()?;
+    if data.len() < TOKEN_ACCOUNT_MIN_LEN
+        || data[..TOKEN_ACCOUNT_MINT_LEN] != slot.mint.key()[..]
+    {
+        return Err(PinocchioCpiError::InvalidTokenAccountData.into());
+    }
+
+    Ok(())
+}
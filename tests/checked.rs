@@ -0,0 +1,163 @@
+//! `checked` 特性的边界检查：指令数据不足、池类型越界、账户不足时返回错误码而不是 panic。
+//!
+//! 数据和池类型的用例只涉及指令解析和 header 账户切分，在宿主机上直接调用 `process_instruction`：
+//!
+//! ```text
+//! cargo test --features checked
+//! ```
+//!
+//! 池账户不足的用例需要真实的账户，和其他集成测试一样加载 SBF 产物：
+//!
+//! ```text
+//! cargo test-sbf --features checked
+//! ```
+#![cfg(feature = "checked")]
+
+#[cfg(feature = "test-sbf")]
+#[allow(dead_code)]
+mod common;
+
+use my_pinocchio_cpi::{
+    error::PinocchioCpiError,
+    process_instruction,
+    utils::{
+        FLAG_TIP, HEADER_ACCOUNT_COUNT, ROUTE_HEADER_LEN, ROUTE_HEADER_LEN_V2, ROUTE_HOP_LEN, SWAP_DATA_LEN,
+        SWAP_DATA_LEN_3HOP, SWAP_DATA_LEN_3HOP_V2, SWAP_DATA_LEN_V2,
+    },
+};
+use pinocchio::program_error::ProgramError;
+
+/// 2hop / 3hop / 反向2hop 的 opcode、固定部分长度和池类型字节的个数
+const SWAP_OPCODES: [(u8, usize, usize); 6] = [
+    (4, SWAP_DATA_LEN, 2),
+    (5, SWAP_DATA_LEN_3HOP, 3),
+    (7, SWAP_DATA_LEN_V2, 2),
+    (8, SWAP_DATA_LEN_3HOP_V2, 3),
+    (10, SWAP_DATA_LEN, 2),
+    (11, SWAP_DATA_LEN_V2, 2),
+];
+const ROUTE_OPCODES: [(u8, usize); 2] = [(6, ROUTE_HEADER_LEN), (9, ROUTE_HEADER_LEN_V2)];
+
+/// 有效的池类型为 0-11
+const FIRST_UNSUPPORTED_POOL_TYPE: u8 = 12;
+
+fn run(data: &[u8]) -> Result<(), ProgramError> {
+    process_instruction(&[0; 32], &[], data)
+}
+
+fn error(e: PinocchioCpiError) -> Result<(), ProgramError> {
+    Err(e.into())
+}
+
+/// opcode + 全零的固定部分：所有池都是 CPMM(0)，flags 为0
+fn swap_data(opcode: u8, len: usize) -> Vec<u8> {
+    let mut data = vec![0; 1 + len];
+    data[0] = opcode;
+    data
+}
+
+/// 单跳的N-hop路由：9个header账户，hop 为 CPMM 从基础资产槽位到基础资产槽位
+fn route_data(opcode: u8, header_len: usize) -> Vec<u8> {
+    let mut data = swap_data(opcode, header_len + ROUTE_HOP_LEN);
+    data[1] = 1;
+    data[2] = HEADER_ACCOUNT_COUNT as u8;
+    data[1 + header_len + 2] = 2;
+    data[1 + header_len + 3] = 2;
+    data
+}
+
+#[test]
+fn short_instruction_data_is_rejected() {
+    assert_eq!(run(&[]), error(PinocchioCpiError::InstructionDataTooShort));
+
+    for (opcode, len, _) in SWAP_OPCODES {
+        let data = swap_data(opcode, len - 1);
+        assert_eq!(run(&data), error(PinocchioCpiError::InstructionDataTooShort), "opcode {opcode}");
+    }
+    for (opcode, len) in ROUTE_OPCODES {
+        let data = route_data(opcode, len);
+        assert_eq!(run(&data[..len]), error(PinocchioCpiError::InstructionDataTooShort), "opcode {opcode}");
+    }
+}
+
+#[test]
+fn missing_optional_data_is_rejected() {
+    // FLAG_TIP 需要固定部分之后的10字节小费参数
+    for (opcode, len, pools) in SWAP_OPCODES {
+        let mut data = swap_data(opcode, len);
+        data[1 + 2 * pools] = FLAG_TIP;
+        assert_eq!(run(&data), error(PinocchioCpiError::InstructionDataTooShort), "opcode {opcode}");
+    }
+    for (opcode, len) in ROUTE_OPCODES {
+        let mut data = route_data(opcode, len);
+        data[3] = FLAG_TIP;
+        assert_eq!(run(&data), error(PinocchioCpiError::InstructionDataTooShort), "opcode {opcode}");
+    }
+}
+
+#[test]
+fn unsupported_pool_type_is_rejected() {
+    // 每个位置（买入 / 中间 / 卖出）的池类型都要校验
+    for (opcode, len, pools) in SWAP_OPCODES {
+        for position in 0..pools {
+            let mut data = swap_data(opcode, len);
+            data[1 + position] = FIRST_UNSUPPORTED_POOL_TYPE;
+            assert_eq!(
+                run(&data),
+                error(PinocchioCpiError::UnsupportedPoolType),
+                "opcode {opcode} position {position}"
+            );
+        }
+    }
+}
+
+#[test]
+fn missing_header_accounts_are_rejected() {
+    for (opcode, len, _) in SWAP_OPCODES {
+        let data = swap_data(opcode, len);
+        assert_eq!(run(&data), error(PinocchioCpiError::NotEnoughAccounts), "opcode {opcode}");
+    }
+    for (opcode, len) in ROUTE_OPCODES {
+        let data = route_data(opcode, len);
+        assert_eq!(run(&data), error(PinocchioCpiError::NotEnoughAccounts), "opcode {opcode}");
+    }
+}
+
+#[cfg(feature = "test-sbf")]
+mod sbf {
+    use super::common::*;
+    use my_pinocchio_cpi::error::PinocchioCpiError;
+    use my_pinocchio_cpi_client::{Route, RouteHeader, WSOL_MINT};
+    use solana_instruction::error::InstructionError;
+
+    const RESERVE: u64 = 1_000_000_000;
+
+    /// 去掉最后一个池的最后一个账户后执行，最后一个池的账户切片不足
+    fn run_without_last_account(env: &TestEnv, route: &Route, header: &RouteHeader) {
+        let mut instruction = route.instruction(PROGRAM_ID, header).unwrap();
+        instruction.accounts.pop();
+        let result = env.mollusk.process_instruction(&instruction, &env.accounts);
+        assert_eq!(
+            result.raw_result,
+            Err(InstructionError::Custom(PinocchioCpiError::NotEnoughAccounts as u32))
+        );
+    }
+
+    #[test]
+    fn missing_last_pool_accounts_are_rejected() {
+        let mut env = TestEnv::new();
+        let header = env.header(RESERVE, 1);
+        let token = header.tokens[0].mint;
+        let first = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE);
+        let last = env.pool(DLMM, token, WSOL_MINT, RESERVE, RESERVE);
+        run_without_last_account(&env, &Route::two_hop(first.clone(), last.clone()), &header);
+        run_without_last_account(&env, &Route::two_hop_reverse(first, last), &header);
+
+        let header = env.header(RESERVE, 2);
+        let (token1, token2) = (header.tokens[0].mint, header.tokens[1].mint);
+        let buy = env.pool(CPMM, token1, WSOL_MINT, RESERVE, RESERVE);
+        let mid = env.pool(CPMM, token1, token2, RESERVE, RESERVE);
+        let sell = env.pool(WHIRLPOOL, token2, WSOL_MINT, RESERVE, RESERVE);
+        run_without_last_account(&env, &Route::three_hop(buy, mid, sell), &header);
+    }
+}
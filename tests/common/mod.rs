@@ -0,0 +1,403 @@
+//! mollusk 测试环境：加载 `my_pinocchio_cpi` 和 `mock_dex` 两个 SBF 产物，
+//! 并提供创建 mint、token 账户、header 和各 DEX mock 池的辅助函数。
+
+use mock_dex::{mock_program_id, VAULT_AUTHORITY_SEED};
+use mock_lending::{reserve_data, FLASH_LOAN_FEE_WAD, LENDING_AUTHORITY_SEED, LENDING_PROGRAM_ID};
+use mollusk_svm::{
+    program::{create_program_account_loader_v3, keyed_account_for_system_program},
+    result::InstructionResult,
+    Mollusk,
+};
+use mollusk_svm_programs_token::{token, token2022};
+use my_pinocchio_cpi_client::{
+    ClmmKeys, CpmmKeys, Dammv1Keys, Dammv2Keys, DbcKeys, DlmmKeys, FlashLoanKeys, LaunchlabKeys, PoolKeys,
+    PumpFunKeys, PumpKeys, RaydiumKeys, RaydiumOpenbookKeys, Route, RouteHeader, TokenKeys, WhirlpoolKeys,
+    MEMO_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, WSOL_MINT,
+};
+use solana_account::Account;
+use solana_instruction::Instruction;
+use solana_program_option::COption;
+use solana_pubkey::Pubkey;
+use spl_token_interface::state::{Account as TokenAccount, AccountState, Mint};
+
+pub const PROGRAM_ID: Pubkey = Pubkey::new_from_array([0xa1; 32]);
+
+pub const CPMM: u8 = 0;
+pub const DLMM: u8 = 1;
+pub const DAMMV2: u8 = 2;
+pub const PUMP: u8 = 3;
+pub const RAYDIUM: u8 = 4;
+pub const CLMM: u8 = 5;
+pub const WHIRLPOOL: u8 = 6;
+pub const RAYDIUM_OPENBOOK: u8 = 7;
+pub const DAMMV1: u8 = 9;
+pub const DBC: u8 = 10;
+pub const LAUNCHLAB: u8 = 11;
+pub const POOL_TYPES: [u8; 11] =
+    [CPMM, DLMM, DAMMV2, PUMP, RAYDIUM, CLMM, WHIRLPOOL, RAYDIUM_OPENBOOK, DAMMV1, DBC, LAUNCHLAB];
+/// Pump.fun 联合曲线只能和 WSOL 兑换，不在 POOL_TYPES 的任意组合测试中
+pub const PUMP_FUN: u8 = 8;
+
+pub struct TestEnv {
+    pub mollusk: Mollusk,
+    pub accounts: Vec<(Pubkey, Account)>,
+    pub payer: Pubkey,
+}
+
+impl TestEnv {
+    pub fn new() -> Self {
+        let mut mollusk = Mollusk::new(&PROGRAM_ID, "my_pinocchio_cpi");
+        token::add_program(&mut mollusk);
+        token2022::add_program(&mut mollusk);
+
+        let payer = Pubkey::new_unique();
+        let mut env = Self {
+            mollusk,
+            accounts: vec![
+                (payer, Account::new(10_000_000_000, 0, &Pubkey::default())),
+                token::keyed_account(),
+                keyed_account_for_system_program(),
+                token2022::keyed_account(),
+                (MEMO_PROGRAM_ID, Account::default()),
+            ],
+            payer,
+        };
+
+        for pool_type in POOL_TYPES.into_iter().chain([PUMP_FUN]) {
+            let program = Pubkey::new_from_array(mock_program_id(pool_type));
+            env.mollusk.add_program(&program, "mock_dex");
+            env.add(program, create_program_account_loader_v3(&program));
+        }
+        let lending = Pubkey::new_from_array(LENDING_PROGRAM_ID);
+        env.mollusk.add_program(&lending, "mock_lending");
+        env.add(lending, create_program_account_loader_v3(&lending));
+
+        env.add(WSOL_MINT, mint_account());
+        env
+    }
+
+    pub fn add(&mut self, key: Pubkey, account: Account) {
+        if !self.accounts.iter().any(|(existing, _)| *existing == key) {
+            self.accounts.push((key, account));
+        }
+    }
+
+    pub fn dummy(&mut self) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.add(key, Account::default());
+        key
+    }
+
+    /// 持有 `lamports` 的系统账户，例如小费账户
+    pub fn system_account(&mut self, lamports: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.add(key, Account::new(lamports, 0, &Pubkey::default()));
+        key
+    }
+
+    pub fn mint(&mut self) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.add(key, mint_account());
+        key
+    }
+
+    /// Token-2022 程序拥有的 mint（不带扩展）
+    pub fn mint_2022(&mut self) -> Pubkey {
+        let key = Pubkey::new_unique();
+        let mut account = mint_account();
+        account.owner = TOKEN_2022_PROGRAM_ID;
+        self.add(key, account);
+        key
+    }
+
+    /// token 账户由 mint 所属的 token 程序拥有
+    pub fn token_account(&mut self, mint: Pubkey, owner: Pubkey, amount: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        let mut account = token_account(mint, owner, amount);
+        account.owner = self.account_mut(&mint).owner;
+        self.add(key, account);
+        key
+    }
+
+    pub fn account_mut(&mut self, key: &Pubkey) -> &mut Account {
+        let (_, account) = self
+            .accounts
+            .iter_mut()
+            .find(|(existing, _)| existing == key)
+            .expect("account");
+        account
+    }
+
+    /// 修改已有 token 账户的余额，例如给中间 token 账户预置库存
+    pub fn set_balance(&mut self, token_account: Pubkey, amount: u64) {
+        self.account_mut(&token_account).data[64..72].copy_from_slice(&amount.to_le_bytes());
+    }
+
+    /// 持有 `wsol_amount` WSOL 的 header，附带 `token_count` 个余额为0的中间 token
+    pub fn header(&mut self, wsol_amount: u64, token_count: usize) -> RouteHeader {
+        self.header_with_base(WSOL_MINT, wsol_amount, token_count)
+    }
+
+    /// 以 `base_mint` 为基础资产、持有 `base_amount` 的 header，[3] 为 mint 所属的 token 程序
+    pub fn header_with_base(&mut self, base_mint: Pubkey, base_amount: u64, token_count: usize) -> RouteHeader {
+        let base_account = self.token_account(base_mint, self.payer, base_amount);
+        let tokens = (0..token_count)
+            .map(|_| {
+                let mint = self.mint();
+                TokenKeys {
+                    mint,
+                    token_program: TOKEN_PROGRAM_ID,
+                    account: self.token_account(mint, self.payer, 0),
+                }
+            })
+            .collect();
+        let base_token_program = self.account_mut(&base_mint).owner;
+        RouteHeader::new(self.payer, base_mint, base_account, tokens).base_token_program(base_token_program)
+    }
+
+    /// 创建一个 mock 池：token0 (x / a / base / coin) 为 `mint_0`，token1 为 `mint_1`
+    pub fn pool(&mut self, pool_type: u8, mint_0: Pubkey, mint_1: Pubkey, reserve_0: u64, reserve_1: u64) -> PoolKeys {
+        let program = Pubkey::new_from_array(mock_program_id(pool_type));
+        let (authority, _) = Pubkey::find_program_address(&[VAULT_AUTHORITY_SEED], &program);
+        self.add(authority, Account::default());
+        let vault_0 = self.token_account(mint_0, authority, reserve_0);
+        let vault_1 = self.token_account(mint_1, authority, reserve_1);
+
+        match pool_type {
+            CPMM => PoolKeys::Cpmm(CpmmKeys {
+                program,
+                authority,
+                amm_config: self.dummy(),
+                observation_state: self.dummy(),
+                pool_state: self.dummy(),
+                token_0_vault: vault_0,
+                token_1_vault: vault_1,
+            }),
+            DLMM => PoolKeys::Dlmm(DlmmKeys {
+                program,
+                event_authority: self.dummy(),
+                oracle: self.dummy(),
+                lb_pair: authority,
+                reserve_x: vault_0,
+                reserve_y: vault_1,
+                bin_array_minus_1: self.dummy(),
+                bin_array_0: self.dummy(),
+                bin_array_1: self.dummy(),
+            }),
+            DAMMV2 => PoolKeys::Dammv2(Dammv2Keys {
+                program,
+                event_authority: self.dummy(),
+                pool_authority: authority,
+                pool: self.dummy(),
+                token_a_vault: vault_0,
+                token_b_vault: vault_1,
+            }),
+            PUMP => PoolKeys::Pump(PumpKeys {
+                program,
+                pool: authority,
+                global_config: self.dummy(),
+                event_authority: self.dummy(),
+                coin_creator_vault_ata: self.dummy(),
+                coin_creator_vault_authority: self.dummy(),
+                protocol_fee_recipient: self.dummy(),
+                protocol_fee_recipient_token_account: self.dummy(),
+                global_volume_accumulator: self.dummy(),
+                user_volume_accumulator: self.dummy(),
+                system_program: self.dummy(),
+                associated_token_program: self.dummy(),
+                pool_base_token_account: vault_0,
+                pool_quote_token_account: vault_1,
+                fee_config: self.dummy(),
+                fee_program: self.dummy(),
+            }),
+            RAYDIUM => PoolKeys::Raydium(RaydiumKeys {
+                program,
+                authority,
+                amm: self.dummy(),
+                pool_coin_token_account: vault_0,
+                pool_pc_token_account: vault_1,
+            }),
+            CLMM => PoolKeys::Clmm(ClmmKeys {
+                program,
+                pool_state: authority,
+                amm_config: self.dummy(),
+                observation_state: self.dummy(),
+                bitmap_extension: self.dummy(),
+                token_vault_0: vault_0,
+                token_vault_1: vault_1,
+                tick_array_minus_1: self.dummy(),
+                tick_array_0: self.dummy(),
+                tick_array_1: self.dummy(),
+            }),
+            WHIRLPOOL => PoolKeys::Whirlpool(WhirlpoolKeys {
+                program,
+                whirlpool: authority,
+                oracle: self.dummy(),
+                token_vault_a: vault_0,
+                token_vault_b: vault_1,
+                tick_array_0: self.dummy(),
+                tick_array_1: self.dummy(),
+                tick_array_2: self.dummy(),
+            }),
+            RAYDIUM_OPENBOOK => PoolKeys::RaydiumOpenbook(RaydiumOpenbookKeys {
+                program,
+                authority,
+                amm: self.dummy(),
+                pool_coin_token_account: vault_0,
+                pool_pc_token_account: vault_1,
+                open_orders: self.dummy(),
+                serum_program: self.dummy(),
+                serum_market: self.dummy(),
+                serum_bids: self.dummy(),
+                serum_asks: self.dummy(),
+                serum_event_queue: self.dummy(),
+                serum_coin_vault: self.dummy(),
+                serum_pc_vault: self.dummy(),
+                serum_vault_signer: self.dummy(),
+            }),
+            PUMP_FUN => {
+                // SOL 储备是 bonding_curve 自身的 lamports
+                let (reserve_sol, curve_vault) = if mint_0 == WSOL_MINT {
+                    (reserve_0, vault_1)
+                } else {
+                    (reserve_1, vault_0)
+                };
+                self.accounts.retain(|(key, _)| *key != authority);
+                self.add(authority, Account::new(reserve_sol, 0, &program));
+                PoolKeys::PumpFun(PumpFunKeys {
+                    program,
+                    global: self.dummy(),
+                    fee_recipient: self.dummy(),
+                    bonding_curve: authority,
+                    associated_bonding_curve: curve_vault,
+                    creator_vault: self.dummy(),
+                    event_authority: self.dummy(),
+                    global_volume_accumulator: self.dummy(),
+                    user_volume_accumulator: self.dummy(),
+                    fee_config: self.dummy(),
+                    fee_program: self.dummy(),
+                    system_program: Pubkey::default(),
+                    user_sol_account: self.token_account(WSOL_MINT, self.payer, 0),
+                })
+            }
+            DAMMV1 => PoolKeys::Dammv1(Dammv1Keys {
+                program,
+                pool: authority,
+                a_vault: self.dummy(),
+                b_vault: self.dummy(),
+                a_token_vault: vault_0,
+                b_token_vault: vault_1,
+                a_vault_lp_mint: self.dummy(),
+                b_vault_lp_mint: self.dummy(),
+                a_vault_lp: self.dummy(),
+                b_vault_lp: self.dummy(),
+                protocol_token_a_fee: self.token_account(mint_0, authority, 0),
+                protocol_token_b_fee: self.token_account(mint_1, authority, 0),
+                vault_program: self.dummy(),
+            }),
+            DBC => PoolKeys::Dbc(DbcKeys {
+                program,
+                event_authority: self.dummy(),
+                pool_authority: authority,
+                config: self.dummy(),
+                pool: self.dummy(),
+                base_vault: vault_0,
+                quote_vault: vault_1,
+            }),
+            LAUNCHLAB => PoolKeys::Launchlab(LaunchlabKeys {
+                program,
+                authority,
+                global_config: self.dummy(),
+                platform_config: self.dummy(),
+                pool_state: self.dummy(),
+                base_vault: vault_0,
+                quote_vault: vault_1,
+                event_authority: self.dummy(),
+            }),
+            _ => panic!("unknown pool type {pool_type}"),
+        }
+    }
+
+    /// 创建一个 mock 借贷池：reserve supply 持有 `liquidity` 个 `mint`，费率为 `FLASH_LOAN_FEE_WAD`
+    pub fn flash_loan(&mut self, mint: Pubkey, liquidity: u64) -> FlashLoanKeys {
+        self.flash_loan_with_fee(mint, liquidity, FLASH_LOAN_FEE_WAD)
+    }
+
+    /// 创建一个 flash_loan_fee_wad 为 `fee_wad` 的 mock 借贷池
+    pub fn flash_loan_with_fee(&mut self, mint: Pubkey, liquidity: u64, fee_wad: u64) -> FlashLoanKeys {
+        let program = Pubkey::new_from_array(LENDING_PROGRAM_ID);
+        let (authority, _) = Pubkey::find_program_address(&[LENDING_AUTHORITY_SEED], &program);
+        self.add(authority, Account::default());
+
+        let reserve = Pubkey::new_unique();
+        self.add(reserve, Account {
+            lamports: 1_000_000_000,
+            data: reserve_data(fee_wad),
+            owner: program,
+            ..Account::default()
+        });
+
+        FlashLoanKeys {
+            reserve,
+            reserve_liquidity_supply: self.token_account(mint, authority, liquidity),
+            reserve_liquidity_fee_receiver: self.token_account(mint, authority, 0),
+            host_fee_receiver: self.token_account(mint, authority, 0),
+            lending_market: self.dummy(),
+            lending_market_authority: authority,
+        }
+    }
+
+    /// 以交易第一条指令的位置执行路由，设置了闪电贷时连同前后的借还款指令一起执行
+    pub fn run(&self, route: &Route, header: &RouteHeader) -> InstructionResult {
+        self.run_chain(&route.instructions(PROGRAM_ID, header, 0).unwrap())
+    }
+
+    pub fn run_chain(&self, instructions: &[Instruction]) -> InstructionResult {
+        self.mollusk.process_instruction_chain(instructions, &self.accounts)
+    }
+}
+
+/// 从执行结果中读取账户的 lamports
+pub fn lamports(result: &InstructionResult, key: &Pubkey) -> u64 {
+    result.get_account(key).expect("account").lamports
+}
+
+/// 从执行结果中读取 token 账户余额
+pub fn balance(result: &InstructionResult, token_account: &Pubkey) -> u64 {
+    let account = result.get_account(token_account).expect("token account");
+    u64::from_le_bytes(account.data[64..72].try_into().unwrap())
+}
+
+fn mint_account() -> Account {
+    token::create_account_for_mint(Mint {
+        mint_authority: COption::None,
+        supply: u64::MAX,
+        decimals: 9,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    })
+}
+
+/// WSOL 账户按原生账户创建：is_native 记录 rent-exempt 储备，lamports = 储备 + amount，
+/// 与链上一致，转账时 lamports 随 amount 一起移动，SyncNative / CloseAccount 可正常工作
+fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    let mut state = TokenAccount {
+        mint,
+        owner,
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    };
+    if mint != WSOL_MINT {
+        return token::create_account_for_token_account(state);
+    }
+
+    let rent_exempt_reserve = token::create_account_for_token_account(state).lamports;
+    state.is_native = COption::Some(rent_exempt_reserve);
+    let mut account = token::create_account_for_token_account(state);
+    account.lamports = rent_exempt_reserve + amount;
+    account
+}
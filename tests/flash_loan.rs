@@ -0,0 +1,25 @@
+//! 闪电贷手续费按 Solend 的规则从 reserve 的费率计算，与 flash_repay 实际扣除的一致：
+//!
+//! ```text
+//! cargo test --test flash_loan
+//! ```
+
+use my_pinocchio_cpi::flash_loan::{flash_loan_fee, WAD};
+
+/// 30 bps
+const FEE_WAD: u64 = 3_000_000_000_000_000;
+
+#[test]
+fn flash_loan_fee_rounds_like_solend() {
+    assert_eq!(flash_loan_fee(1_000_000, FEE_WAD, 0), 3_000);
+    // 0.5 向上取整，小于 0.5 向下取整
+    assert_eq!(flash_loan_fee(500, FEE_WAD, 0), 2);
+    assert_eq!(flash_loan_fee(166, FEE_WAD, 0), 1);
+    // 费率非0时至少1，有 host fee 时至少2
+    assert_eq!(flash_loan_fee(10, FEE_WAD, 0), 1);
+    assert_eq!(flash_loan_fee(10, FEE_WAD, 20), 2);
+    // 费率或金额为0时不收费
+    assert_eq!(flash_loan_fee(1_000_000, 0, 20), 0);
+    assert_eq!(flash_loan_fee(0, FEE_WAD, 20), 0);
+    assert_eq!(flash_loan_fee(u64::MAX, WAD as u64, 0), u64::MAX);
+}
@@ -0,0 +1,1220 @@
+//! 端到端路由测试：程序、mock DEX 和 mock 借贷程序都以 SBF 产物加载进 mollusk。
+//!
+//! 先构建 mock 的 SBF 产物再运行：
+//!
+//! ```text
+//! cargo build-sbf --manifest-path mocks/mock-dex/Cargo.toml
+//! cargo build-sbf --manifest-path mocks/mock-lending/Cargo.toml
+//! cargo test-sbf
+//! ```
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use mock_dex::{ERROR_MINT_MISMATCH, ERROR_SLIPPAGE, PUMP_FUN_USER_VOLUME_ACCUMULATOR_RENT};
+use mock_lending::flash_loan_fee;
+use my_pinocchio_cpi::error::PinocchioCpiError;
+use my_pinocchio_cpi_client::{
+    token_account_index, Hop, PoolKeys, Route, RouteHeader, SimulationResult, BASE_ACCOUNT_INDEX,
+    TOKEN_2022_PROGRAM_ID, WSOL_MINT,
+};
+use solana_instruction::error::InstructionError;
+use solana_pubkey::Pubkey;
+
+const WSOL_BALANCE: u64 = 10_000_000;
+const AMOUNT_IN: u64 = 1_000_000;
+/// 小费账户预先持有 rent-exempt 以上的 lamports
+const TIP_ACCOUNT_LAMPORTS: u64 = 1_000_000_000;
+const RESERVE: u64 = 1_000_000_000;
+/// 按 base_amount_out 买入时，1:1 的池买 990_000 需要约 991_000 WSOL
+const PUMP_BASE_AMOUNT_OUT: u64 = 990_000;
+
+/// 按 token0 / token1 顺序创建池，`a` 是否为 token0 由 `a_is_0` 决定
+fn pair(env: &mut TestEnv, pool_type: u8, a: Pubkey, b: Pubkey, reserve_a: u64, reserve_b: u64, a_is_0: bool) -> PoolKeys {
+    if a_is_0 {
+        env.pool(pool_type, a, b, reserve_a, reserve_b)
+    } else {
+        env.pool(pool_type, b, a, reserve_b, reserve_a)
+    }
+}
+
+/// WSOL -> token 价格 1:1，token -> WSOL 价格 1:2
+fn profitable_two_hop(env: &mut TestEnv, buy: u8, sell: u8, wsol_pool_0: bool) -> (Route, RouteHeader) {
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let buy_pool = pair(env, buy, WSOL_MINT, token, RESERVE, RESERVE, wsol_pool_0);
+    let sell_pool = pair(env, sell, WSOL_MINT, token, 2 * RESERVE, RESERVE, wsol_pool_0);
+
+    let route = Route::two_hop(buy_pool, sell_pool)
+        .base_pool_0(wsol_pool_0, wsol_pool_0).unwrap()
+        .amount_in(AMOUNT_IN);
+    (route, header)
+}
+
+fn assert_custom_error(result: &mollusk_svm::result::InstructionResult, code: u32) {
+    assert_eq!(result.raw_result, Err(InstructionError::Custom(code)));
+}
+
+#[test]
+fn two_hop_across_every_dex_pair() {
+    for buy in POOL_TYPES {
+        for sell in POOL_TYPES {
+            let mut env = TestEnv::new();
+            let (route, header) = profitable_two_hop(&mut env, buy, sell, false);
+
+            let result = env.run(&route.simulate(true), &header);
+            assert!(result.program_result.is_ok(), "buy {buy} sell {sell}: {:?}", result.program_result);
+
+            let profit = balance(&result, &header.base_account) - WSOL_BALANCE;
+            assert!(profit > 0, "buy {buy} sell {sell}");
+            let simulation = SimulationResult::decode(&result.return_data).expect("simulation data");
+            assert_eq!(simulation.pnl, profit as i64);
+            assert_eq!(simulation.hops.len(), 2);
+            assert_eq!(balance(&result, &header.tokens[0].account), 0, "buy {buy} sell {sell}");
+        }
+    }
+}
+
+#[test]
+fn two_hop_with_wsol_as_token0() {
+    for pool_type in POOL_TYPES {
+        let mut env = TestEnv::new();
+        let (route, header) = profitable_two_hop(&mut env, pool_type, pool_type, true);
+
+        let result = env.run(&route, &header);
+        assert!(result.program_result.is_ok(), "pool type {pool_type}: {:?}", result.program_result);
+        assert!(balance(&result, &header.base_account) > WSOL_BALANCE);
+    }
+}
+
+#[test]
+fn two_hop_with_non_wsol_base_asset() {
+    for pool_type in POOL_TYPES {
+        let mut env = TestEnv::new();
+        let usdc = env.mint();
+        let header = env.header_with_base(usdc, WSOL_BALANCE, 1);
+        let token = header.tokens[0].mint;
+        let buy = env.pool(pool_type, usdc, token, RESERVE, RESERVE);
+        let sell = env.pool(pool_type, token, usdc, RESERVE, 2 * RESERVE);
+
+        let route = Route::two_hop(buy, sell).base_pool_0(true, false).unwrap().amount_in(AMOUNT_IN);
+        let result = env.run(&route.validate_accounts(true), &header);
+        assert!(result.program_result.is_ok(), "pool type {pool_type}: {:?}", result.program_result);
+        assert!(balance(&result, &header.base_account) > WSOL_BALANCE);
+    }
+}
+
+#[test]
+fn two_hop_with_token_2022_base_asset() {
+    for pool_type in POOL_TYPES {
+        let mut env = TestEnv::new();
+        let base = env.mint_2022();
+        let header = env.header_with_base(base, WSOL_BALANCE, 1);
+        assert_eq!(header.base_token_program, TOKEN_2022_PROGRAM_ID);
+        let token = header.tokens[0].mint;
+        let buy = env.pool(pool_type, base, token, RESERVE, RESERVE);
+        let sell = env.pool(pool_type, token, base, RESERVE, 2 * RESERVE);
+
+        let route = Route::two_hop(buy, sell).base_pool_0(true, false).unwrap().amount_in(AMOUNT_IN);
+        let result = env.run(&route.validate_accounts(true), &header);
+        assert!(result.program_result.is_ok(), "pool type {pool_type}: {:?}", result.program_result);
+        assert!(balance(&result, &header.base_account) > WSOL_BALANCE);
+    }
+}
+
+#[test]
+fn two_hop_without_profit_fails() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let buy = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE);
+    let sell = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE);
+
+    let route = Route::two_hop(buy, sell).amount_in(AMOUNT_IN);
+    let result = env.run(&route, &header);
+    assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+}
+
+#[test]
+fn min_profit_bps_floor_is_relative_to_amount_in() {
+    let mut env = TestEnv::new();
+    // 利润约为 AMOUNT_IN 的 99.6%
+    let (route, header) = profitable_two_hop(&mut env, CPMM, DLMM, false);
+
+    let result = env.run(&route.clone().min_profit_bps(9_900), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+
+    let result = env.run(&route.clone().min_profit_bps(10_000), &header);
+    assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+
+    // bps 下限更宽松时仍以绝对下限为准
+    let result = env.run(&route.min_profit(AMOUNT_IN).min_profit_bps(1), &header);
+    assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+}
+
+#[test]
+fn min_profit_above_u32_is_not_truncated() {
+    for n_hop in [false, true] {
+        let mut env = TestEnv::new();
+        let header = env.header(WSOL_BALANCE, 1);
+        let token = header.tokens[0].mint;
+        let slot = token_account_index(0);
+        let buy = env.pool(RAYDIUM, token, WSOL_MINT, RESERVE, RESERVE);
+        let sell = env.pool(WHIRLPOOL, token, WSOL_MINT, RESERVE, 2 * RESERVE);
+
+        let route = if n_hop {
+            Route::n_hop(vec![
+                Hop::new(buy, BASE_ACCOUNT_INDEX, slot, false),
+                Hop::new(sell, slot, BASE_ACCOUNT_INDEX, true),
+            ])
+        } else {
+            Route::two_hop(buy, sell)
+        }
+        .amount_in(AMOUNT_IN);
+
+        // u32::MAX + 1 截断成 u32 后为0，会错误地放行
+        let result = env.run(&route.clone().min_profit(u32::MAX as u64 + 1), &header);
+        assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+
+        // 阈值加法溢出同样视为未达标
+        let result = env.run(&route.min_profit(u64::MAX), &header);
+        assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+    }
+}
+
+#[test]
+fn tip_is_paid_from_realized_profit() {
+    let mut env = TestEnv::new();
+    let tip_account = env.system_account(TIP_ACCOUNT_LAMPORTS);
+    let (route, header) = profitable_two_hop(&mut env, CPMM, WHIRLPOOL, false);
+
+    let result = env.run(&route.clone().tip(tip_account, 1_000, 5_000), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    let profit = balance(&result, &header.base_account) - WSOL_BALANCE;
+    let tip = lamports(&result, &tip_account) - TIP_ACCOUNT_LAMPORTS;
+    assert_eq!(tip, 1_000 + profit / 2);
+    // 小费来自 payer 的原生 SOL
+    assert_eq!(lamports(&result, &env.payer), env.accounts[0].1.lamports - tip);
+}
+
+#[test]
+fn tip_never_exceeds_profit() {
+    let mut env = TestEnv::new();
+    let tip_account = env.system_account(TIP_ACCOUNT_LAMPORTS);
+    let (route, header) = profitable_two_hop(&mut env, DAMMV2, RAYDIUM, false);
+
+    let result = env.run(&route.tip(tip_account, u64::MAX, 0), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    let profit = balance(&result, &header.base_account) - WSOL_BALANCE;
+    assert_eq!(lamports(&result, &tip_account) - TIP_ACCOUNT_LAMPORTS, profit);
+}
+
+#[test]
+fn tip_is_not_paid_without_profit() {
+    let mut env = TestEnv::new();
+    let tip_account = env.system_account(TIP_ACCOUNT_LAMPORTS);
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let buy = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE);
+    let sell = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE);
+
+    let route = Route::two_hop(buy, sell).amount_in(AMOUNT_IN).tip(tip_account, 1_000, 0);
+    let result = env.run(&route, &header);
+    assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+}
+
+#[test]
+fn tip_requires_wsol_base_asset() {
+    let mut env = TestEnv::new();
+    let tip_account = env.system_account(TIP_ACCOUNT_LAMPORTS);
+    let usdc = env.mint();
+    let header = env.header_with_base(usdc, WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let buy = env.pool(CPMM, usdc, token, RESERVE, RESERVE);
+    let sell = env.pool(CPMM, token, usdc, RESERVE, 2 * RESERVE);
+
+    // 利润以 USDC 计，不能直接当作 lamports 支付小费
+    let route = Route::two_hop(buy, sell).base_pool_0(true, false).unwrap().amount_in(AMOUNT_IN).tip(tip_account, 1_000, 0);
+    let result = env.run(&route, &header);
+    assert_custom_error(&result, PinocchioCpiError::TipRequiresWsolBase as u32);
+}
+
+#[test]
+fn tip_account_follows_the_header_for_every_route_kind() {
+    // 3hop 和 N-hop 同样从 header 之后的固定槽位读取小费账户，包装 SOL 不影响其位置
+    let mut env = TestEnv::new();
+    let tip_account = env.system_account(TIP_ACCOUNT_LAMPORTS);
+    let header = env.header(0, 2);
+    let (token1, token2) = (header.tokens[0].mint, header.tokens[1].mint);
+    let buy = env.pool(CPMM, WSOL_MINT, token1, RESERVE, RESERVE);
+    let mid = env.pool(CPMM, token1, token2, RESERVE, RESERVE);
+    let sell = env.pool(CPMM, token2, WSOL_MINT, RESERVE, 2 * RESERVE);
+
+    let three_hop = Route::three_hop(buy.clone(), mid.clone(), sell.clone())
+        .base_pool_0(true, false).unwrap()
+        .mid_zero_to_one(true).unwrap();
+    let n_hop = Route::n_hop(vec![
+        Hop::new(buy, BASE_ACCOUNT_INDEX, token_account_index(0), true),
+        Hop::new(mid, token_account_index(0), token_account_index(1), true),
+        Hop::new(sell, token_account_index(1), BASE_ACCOUNT_INDEX, true),
+    ]);
+    for route in [three_hop, n_hop] {
+        let route = route.amount_in(AMOUNT_IN).wrap_sol(AMOUNT_IN).tip(tip_account, 1_000, 0);
+        let result = env.run(&route, &header);
+        assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+        assert_eq!(lamports(&result, &tip_account) - TIP_ACCOUNT_LAMPORTS, 1_000);
+    }
+}
+
+#[test]
+fn simulation_reports_losses_instead_of_failing() {
+    let mut env = TestEnv::new();
+    let tip_account = env.system_account(TIP_ACCOUNT_LAMPORTS);
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let buy = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE);
+    let sell = env.pool(WHIRLPOOL, token, WSOL_MINT, RESERVE, RESERVE);
+
+    let route = Route::two_hop(buy, sell).amount_in(AMOUNT_IN).tip(tip_account, 1_000, 0);
+    let result = env.run(&route.simulate(true), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+
+    let simulation = SimulationResult::decode(&result.return_data).expect("simulation data");
+    let loss = WSOL_BALANCE - balance(&result, &header.base_account);
+    assert!(loss > 0);
+    assert_eq!(simulation.pnl, -(loss as i64));
+    // 亏损时不支付小费
+    assert_eq!(lamports(&result, &tip_account), TIP_ACCOUNT_LAMPORTS);
+}
+
+#[test]
+fn simulation_ignores_min_profit() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, DLMM, DAMMV2, false);
+
+    let result = env.run(&route.clone().min_profit(u64::MAX), &header);
+    assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+
+    let result = env.run(&route.min_profit(u64::MAX).simulate(true), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    let simulation = SimulationResult::decode(&result.return_data).expect("simulation data");
+    assert_eq!(simulation.pnl, (balance(&result, &header.base_account) - WSOL_BALANCE) as i64);
+}
+
+#[test]
+fn flash_loan_routes_need_no_inventory() {
+    for n_hop in [false, true] {
+        let mut env = TestEnv::new();
+        let header = env.header(0, 1);
+        let token = header.tokens[0].mint;
+        let slot = token_account_index(0);
+        let buy = env.pool(CPMM, WSOL_MINT, token, RESERVE, RESERVE);
+        let sell = env.pool(DLMM, token, WSOL_MINT, RESERVE, 2 * RESERVE);
+        let flash_loan = env.flash_loan(WSOL_MINT, RESERVE);
+
+        let route = if n_hop {
+            Route::n_hop(vec![
+                Hop::new(buy, BASE_ACCOUNT_INDEX, slot, true),
+                Hop::new(sell, slot, BASE_ACCOUNT_INDEX, true),
+            ])
+        } else {
+            Route::two_hop(buy, sell).base_pool_0(true, false).unwrap()
+        }
+        .amount_in(AMOUNT_IN)
+        .flash_loan(flash_loan.clone());
+
+        let result = env.run(&route, &header);
+        assert!(result.program_result.is_ok(), "n_hop {n_hop}: {:?}", result.program_result);
+        assert!(balance(&result, &header.base_account) > 0);
+        // 本金归还，手续费进入 fee receiver
+        assert_eq!(balance(&result, &flash_loan.reserve_liquidity_supply), RESERVE);
+        assert_eq!(balance(&result, &flash_loan.reserve_liquidity_fee_receiver), flash_loan_fee(AMOUNT_IN));
+    }
+}
+
+#[test]
+fn flash_loan_fee_is_deducted_before_profit_check() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let buy = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE);
+    // 毛利约 1_000，低于 AMOUNT_IN 的闪电贷手续费
+    let sell = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE + RESERVE * 3 / 1_000);
+    let flash_loan = env.flash_loan(WSOL_MINT, RESERVE);
+
+    let route = Route::two_hop(buy, sell).amount_in(AMOUNT_IN);
+    let result = env.run(&route, &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    assert!(balance(&result, &header.base_account) - WSOL_BALANCE < flash_loan_fee(AMOUNT_IN));
+
+    // 手续费按 reserve 的 flash_loan_fee_wad 计算
+    let result = env.run(&route.clone().flash_loan(flash_loan), &header);
+    assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+
+    let free_flash_loan = env.flash_loan_with_fee(WSOL_MINT, RESERVE, 0);
+    let result = env.run(&route.flash_loan(free_flash_loan), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+}
+
+#[test]
+fn flash_loan_without_repay_is_rejected() {
+    let mut env = TestEnv::new();
+    let header = env.header(0, 1);
+    let token = header.tokens[0].mint;
+    let buy = env.pool(CPMM, WSOL_MINT, token, RESERVE, RESERVE);
+    let sell = env.pool(DLMM, token, WSOL_MINT, RESERVE, 2 * RESERVE);
+    let flash_loan = env.flash_loan(WSOL_MINT, RESERVE);
+
+    let route = Route::two_hop(buy, sell)
+        .base_pool_0(true, false).unwrap()
+        .amount_in(AMOUNT_IN)
+        .flash_loan(flash_loan);
+    // 只有借款和套利指令，之后没有还款
+    let instructions = route.instructions(PROGRAM_ID, &header, 0).unwrap();
+    let result = env.run_chain(&instructions[..2]);
+    assert_custom_error(&result, PinocchioCpiError::FlashLoanNotRepaid as u32);
+}
+
+#[test]
+fn flash_repay_must_reference_the_borrow() {
+    let mut env = TestEnv::new();
+    let header = env.header(0, 1);
+    let token = header.tokens[0].mint;
+    let buy = env.pool(CPMM, WSOL_MINT, token, RESERVE, RESERVE);
+    let sell = env.pool(DLMM, token, WSOL_MINT, RESERVE, 2 * RESERVE);
+    let flash_loan = env.flash_loan(WSOL_MINT, RESERVE);
+
+    let route = Route::two_hop(buy, sell)
+        .base_pool_0(true, false).unwrap()
+        .amount_in(AMOUNT_IN)
+        .flash_loan(flash_loan);
+    // 借款是第0条指令，还款却引用第1条（套利指令），程序在执行 swap 之前拒绝
+    let result = env.run_chain(&route.instructions(PROGRAM_ID, &header, 1).unwrap());
+    assert_custom_error(&result, PinocchioCpiError::FlashLoanNotRepaid as u32);
+}
+
+/// 基础资产账户为空，amount_in 全部由 payer 的 SOL 包装而来
+fn wrapped_two_hop(env: &mut TestEnv) -> (Route, RouteHeader) {
+    let header = env.header(0, 1);
+    let token = header.tokens[0].mint;
+    let buy = env.pool(CPMM, WSOL_MINT, token, RESERVE, RESERVE);
+    let sell = env.pool(WHIRLPOOL, token, WSOL_MINT, RESERVE, 2 * RESERVE);
+
+    let route = Route::two_hop(buy, sell)
+        .base_pool_0(true, false).unwrap()
+        .amount_in(AMOUNT_IN)
+        .wrap_sol(AMOUNT_IN);
+    (route, header)
+}
+
+/// 初始账户列表中 `key` 的 lamports
+fn initial_lamports(env: &TestEnv, key: &Pubkey) -> u64 {
+    env.accounts.iter().find(|(existing, _)| existing == key).expect("account").1.lamports
+}
+
+#[test]
+fn wrap_sol_funds_the_route_from_payer_lamports() {
+    let mut env = TestEnv::new();
+    let (route, header) = wrapped_two_hop(&mut env);
+
+    let result = env.run(&route, &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    // 包装的本金不计入利润，余额只多出利润部分
+    assert!(balance(&result, &header.base_account) > AMOUNT_IN);
+    assert_eq!(lamports(&result, &env.payer), initial_lamports(&env, &env.payer) - AMOUNT_IN);
+}
+
+#[test]
+fn wrapped_principal_is_not_counted_as_profit() {
+    let mut env = TestEnv::new();
+    let header = env.header(0, 1);
+    let token = header.tokens[0].mint;
+    let buy = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE);
+    let sell = env.pool(CPMM, token, WSOL_MINT, RESERVE, RESERVE);
+
+    let route = Route::two_hop(buy, sell).amount_in(AMOUNT_IN).wrap_sol(AMOUNT_IN);
+    let result = env.run(&route, &header);
+    assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+}
+
+#[test]
+fn unwrap_sol_returns_principal_and_profit_as_sol() {
+    let mut env = TestEnv::new();
+    let (route, header) = wrapped_two_hop(&mut env);
+
+    let result = env.run(&route.unwrap_sol(true), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    // 基础资产账户被关闭，租金、本金和利润全部回到 payer
+    assert_eq!(lamports(&result, &header.base_account), 0);
+    let rent = initial_lamports(&env, &header.base_account);
+    assert!(lamports(&result, &env.payer) > initial_lamports(&env, &env.payer) + rent);
+}
+
+/// 精确买入的 token 数量，1:1 的池约需 500_250 WSOL，低于 AMOUNT_IN
+const EXACT_OUT: u64 = 500_000;
+
+/// 支持精确输出的池的 (token0 vault, token1 vault)
+fn exact_out_vaults(pool: &PoolKeys) -> (Pubkey, Pubkey) {
+    match pool {
+        PoolKeys::Cpmm(cpmm) => (cpmm.token_0_vault, cpmm.token_1_vault),
+        PoolKeys::Clmm(clmm) => (clmm.token_vault_0, clmm.token_vault_1),
+        PoolKeys::Raydium(raydium) => (raydium.pool_coin_token_account, raydium.pool_pc_token_account),
+        PoolKeys::RaydiumOpenbook(raydium) => (raydium.pool_coin_token_account, raydium.pool_pc_token_account),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn exact_out_buys_exact_amount() {
+    for pool_type in [CPMM, RAYDIUM, CLMM, RAYDIUM_OPENBOOK] {
+        for n_hop in [false, true] {
+            let mut env = TestEnv::new();
+            let header = env.header(WSOL_BALANCE, 1);
+            let token = header.tokens[0].mint;
+            let slot = token_account_index(0);
+            let buy = env.pool(pool_type, WSOL_MINT, token, RESERVE, RESERVE);
+            let sell = env.pool(DLMM, WSOL_MINT, token, 2 * RESERVE, RESERVE);
+            let (wsol_vault, token_vault) = exact_out_vaults(&buy);
+
+            let route = if n_hop {
+                Route::n_hop(vec![
+                    Hop::new(buy, BASE_ACCOUNT_INDEX, slot, true).exact_out(EXACT_OUT),
+                    Hop::new(sell, slot, BASE_ACCOUNT_INDEX, false),
+                ])
+            } else {
+                Route::two_hop(buy, sell).base_pool_0(true, true).unwrap().exact_out(0, EXACT_OUT).unwrap()
+            }
+            .amount_in(AMOUNT_IN);
+
+            let result = env.run(&route, &header);
+            let label = format!("pool type {pool_type} n_hop {n_hop}");
+            assert!(result.program_result.is_ok(), "{label}: {:?}", result.program_result);
+            assert_eq!(balance(&result, &token_vault), RESERVE - EXACT_OUT, "{label}");
+            // 只花费买到 EXACT_OUT 所需的输入，其余留在基础资产账户
+            let spent = balance(&result, &wsol_vault) - RESERVE;
+            assert!(spent > EXACT_OUT && spent < AMOUNT_IN, "{label}: spent {spent}");
+            assert_eq!(balance(&result, &header.tokens[0].account), 0, "{label}");
+        }
+    }
+}
+
+#[test]
+fn exact_out_input_is_bounded_by_hop_amount() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, CLMM, CPMM, true);
+
+    // 输入上限为该跳的输入数量，买不到 EXACT_OUT
+    let result = env.run(&route.amount_in(EXACT_OUT / 2).exact_out(0, EXACT_OUT).unwrap(), &header);
+    assert_custom_error(&result, ERROR_SLIPPAGE);
+}
+
+#[test]
+fn exact_out_is_rejected_for_other_pool_types() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, DLMM, CPMM, true);
+
+    let result = env.run(&route.exact_out(0, EXACT_OUT).unwrap(), &header);
+    assert_custom_error(&result, PinocchioCpiError::ExactOutputNotSupported as u32);
+}
+
+#[test]
+fn exact_out_of_zero_is_rejected() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, CPMM, CPMM, true);
+
+    let result = env.run(&route.exact_out(0, 0).unwrap(), &header);
+    assert_custom_error(&result, PinocchioCpiError::InvalidTradeAmount as u32);
+}
+
+#[test]
+fn raydium_openbook_requires_open_orders() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let PoolKeys::RaydiumOpenbook(mut buy) = env.pool(RAYDIUM_OPENBOOK, WSOL_MINT, token, RESERVE, RESERVE) else {
+        unreachable!()
+    };
+    let sell = env.pool(CPMM, WSOL_MINT, token, 2 * RESERVE, RESERVE);
+
+    let route = Route::two_hop(PoolKeys::RaydiumOpenbook(buy.clone()), sell.clone())
+        .base_pool_0(true, true).unwrap()
+        .amount_in(AMOUNT_IN);
+    let result = env.run(&route, &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+
+    // 用 AMM id 占位 open orders 时旧池会拒绝
+    buy.open_orders = buy.amm;
+    let route = Route::two_hop(PoolKeys::RaydiumOpenbook(buy), sell)
+        .base_pool_0(true, true).unwrap()
+        .amount_in(AMOUNT_IN);
+    let result = env.run(&route, &header);
+    assert_eq!(result.raw_result, Err(InstructionError::InvalidAccountData));
+}
+
+#[test]
+fn dammv1_passes_input_token_protocol_fee_account() {
+    for wsol_pool_0 in [true, false] {
+        let mut env = TestEnv::new();
+        let (route, header) = profitable_two_hop(&mut env, DAMMV1, CPMM, wsol_pool_0);
+        let result = env.run(&route, &header);
+        assert!(result.program_result.is_ok(), "wsol_pool_0 {wsol_pool_0}: {:?}", result.program_result);
+
+        // 手续费账户必须对应输入 token，交换 a / b 后 mock 拒绝
+        let header = env.header(WSOL_BALANCE, 1);
+        let token = header.tokens[0].mint;
+        let PoolKeys::Dammv1(mut buy) = pair(&mut env, DAMMV1, WSOL_MINT, token, RESERVE, RESERVE, wsol_pool_0) else {
+            unreachable!()
+        };
+        std::mem::swap(&mut buy.protocol_token_a_fee, &mut buy.protocol_token_b_fee);
+        let sell = pair(&mut env, CPMM, WSOL_MINT, token, 2 * RESERVE, RESERVE, wsol_pool_0);
+        let route = Route::two_hop(PoolKeys::Dammv1(buy), sell)
+            .base_pool_0(wsol_pool_0, wsol_pool_0).unwrap()
+            .amount_in(AMOUNT_IN);
+        let result = env.run(&route, &header);
+        assert_custom_error(&result, ERROR_MINT_MISMATCH);
+    }
+}
+
+/// DBC / LaunchLab 池的 (base_vault, quote_vault)
+fn launch_pool_vaults(pool: &PoolKeys) -> (Pubkey, Pubkey) {
+    match pool {
+        PoolKeys::Dbc(keys) => (keys.base_vault, keys.quote_vault),
+        PoolKeys::Launchlab(keys) => (keys.base_vault, keys.quote_vault),
+        _ => unreachable!(),
+    }
+}
+
+/// 发行池的 base 为发行的 token、quote 为 WSOL，`launch_buys` 决定发行池在买入还是卖出一侧
+fn assert_launch_pool_swaps(pool_type: u8, launch_buys: bool) {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let (launch_wsol, other_wsol) = if launch_buys { (RESERVE, 2 * RESERVE) } else { (2 * RESERVE, RESERVE) };
+    let launch = env.pool(pool_type, token, WSOL_MINT, RESERVE, launch_wsol);
+    let other = env.pool(CPMM, token, WSOL_MINT, RESERVE, other_wsol);
+    let (base_vault, quote_vault) = launch_pool_vaults(&launch);
+
+    let route = |launch_is_base_pool_0: bool| {
+        let route = if launch_buys {
+            Route::two_hop(launch.clone(), other.clone()).base_pool_0(launch_is_base_pool_0, false).unwrap()
+        } else {
+            Route::two_hop(other.clone(), launch.clone()).base_pool_0(false, launch_is_base_pool_0).unwrap()
+        };
+        route.amount_in(AMOUNT_IN)
+    };
+
+    let result = env.run(&route(false), &header);
+    assert!(result.program_result.is_ok(), "launch_buys {launch_buys}: {:?}", result.program_result);
+    assert!(balance(&result, &header.base_account) > WSOL_BALANCE);
+
+    // 买入 WSOL 进 quote 金库、token 出 base 金库，卖出相反；
+    // mock 的 LaunchLab 按 buy_exact_in / sell_exact_in 的 discriminator 决定方向，DBC 校验 swap 的 discriminator
+    if launch_buys {
+        assert_eq!(balance(&result, &quote_vault), launch_wsol + AMOUNT_IN);
+        assert!(balance(&result, &base_vault) < RESERVE);
+    } else {
+        assert!(balance(&result, &quote_vault) < launch_wsol);
+        assert!(balance(&result, &base_vault) > RESERVE);
+    }
+
+    // 把 WSOL 当作发行池的 base mint 时方向反了，mint 与金库不符
+    let result = env.run(&route(true), &header);
+    assert_custom_error(&result, ERROR_MINT_MISMATCH);
+}
+
+#[test]
+fn dbc_buys_and_sells_launched_token() {
+    for launch_buys in [true, false] {
+        assert_launch_pool_swaps(DBC, launch_buys);
+    }
+}
+
+#[test]
+fn launchlab_buys_and_sells_launched_token() {
+    for launch_buys in [true, false] {
+        assert_launch_pool_swaps(LAUNCHLAB, launch_buys);
+    }
+}
+
+#[test]
+fn two_hop_min_out_reaches_every_dex() {
+    for pool_type in POOL_TYPES {
+        let mut env = TestEnv::new();
+        let (route, header) = profitable_two_hop(&mut env, pool_type, pool_type, false);
+
+        // 1:1 的池有价格冲击，拿不到 AMOUNT_IN 个 token
+        let result = env.run(&route.min_outs(&[AMOUNT_IN, 0]).unwrap(), &header);
+        assert_custom_error(&result, ERROR_SLIPPAGE);
+    }
+}
+
+#[test]
+fn pump_buy_with_base_amount_out_spends_at_most_amount_in() {
+    for sell in [CPMM, PUMP] {
+        let mut env = TestEnv::new();
+        let (route, header) = profitable_two_hop(&mut env, PUMP, sell, false);
+
+        let result = env.run(&route.clone().pump_base_amount_out(PUMP_BASE_AMOUNT_OUT), &header);
+        assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+        assert!(balance(&result, &header.base_account) > WSOL_BALANCE);
+
+        // 1:1 的池买 AMOUNT_IN 个 token 需要超过 AMOUNT_IN 的 WSOL
+        let result = env.run(&route.pump_base_amount_out(AMOUNT_IN), &header);
+        assert_custom_error(&result, ERROR_SLIPPAGE);
+    }
+}
+
+#[test]
+fn three_hop_through_every_mid_dex() {
+    for mid in POOL_TYPES {
+        for mid_zero_to_one in [true, false] {
+            let mut env = TestEnv::new();
+            let header = env.header(WSOL_BALANCE, 2);
+            let (token1, token2) = (header.tokens[0].mint, header.tokens[1].mint);
+            let buy = env.pool(CPMM, token1, WSOL_MINT, RESERVE, RESERVE);
+            let mid_pool = pair(&mut env, mid, token1, token2, RESERVE, RESERVE, mid_zero_to_one);
+            let sell = env.pool(WHIRLPOOL, token2, WSOL_MINT, RESERVE, 2 * RESERVE);
+
+            let route = Route::three_hop(buy, mid_pool, sell)
+                .mid_zero_to_one(mid_zero_to_one).unwrap()
+                .amount_in(AMOUNT_IN);
+            let result = env.run(&route, &header);
+            assert!(
+                result.program_result.is_ok(),
+                "mid {mid} zero_to_one {mid_zero_to_one}: {:?}",
+                result.program_result
+            );
+            assert!(balance(&result, &header.base_account) > WSOL_BALANCE);
+            assert_eq!(balance(&result, &header.tokens[1].account), 0);
+        }
+    }
+}
+
+#[test]
+fn three_hop_sells_into_wsol_base_pump_pool() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 2);
+    let (token1, token2) = (header.tokens[0].mint, header.tokens[1].mint);
+    let buy = env.pool(PUMP, WSOL_MINT, token1, RESERVE, RESERVE);
+    let mid = env.pool(PUMP, token1, token2, RESERVE, RESERVE);
+    let sell = env.pool(PUMP, WSOL_MINT, token2, 2 * RESERVE, RESERVE);
+
+    let route = Route::three_hop(buy, mid, sell)
+        .base_pool_0(true, true).unwrap()
+        .mid_zero_to_one(true).unwrap()
+        .amount_in(AMOUNT_IN)
+        .min_outs(&[0, 0, AMOUNT_IN]).unwrap();
+    let result = env.run(&route, &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    assert!(balance(&result, &header.base_account) > WSOL_BALANCE + AMOUNT_IN / 2);
+    assert_eq!(balance(&result, &header.tokens[1].account), 0);
+}
+
+#[test]
+fn pump_base_amount_out_rejects_multiple_pump_buys() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 2);
+    let (token1, token2) = (header.tokens[0].mint, header.tokens[1].mint);
+    let (slot1, slot2) = (token_account_index(0), token_account_index(1));
+    // 前两跳都是用 quote 买 base：WSOL -> token1，token1 -> token2
+    let buy = env.pool(PUMP, token1, WSOL_MINT, RESERVE, RESERVE);
+    let mid = env.pool(PUMP, token2, token1, RESERVE, RESERVE);
+    let sell = env.pool(CPMM, token2, WSOL_MINT, RESERVE, 2 * RESERVE);
+
+    let three_hop = Route::three_hop(buy.clone(), mid.clone(), sell.clone()).amount_in(AMOUNT_IN);
+    let n_hop = Route::n_hop(vec![
+        Hop::new(buy, BASE_ACCOUNT_INDEX, slot1, false),
+        Hop::new(mid, slot1, slot2, false),
+        Hop::new(sell, slot2, BASE_ACCOUNT_INDEX, false),
+    ])
+    .amount_in(AMOUNT_IN);
+
+    for route in [three_hop, n_hop] {
+        let result = env.run(&route, &header);
+        assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+
+        // 同一个 base_amount_out 无法同时对应 token1 和 token2
+        let result = env.run(&route.pump_base_amount_out(PUMP_BASE_AMOUNT_OUT), &header);
+        assert_custom_error(&result, PinocchioCpiError::AmbiguousPumpBaseAmountOut as u32);
+    }
+}
+
+/// 联合曲线池的 (bonding_curve, user_sol_account)
+fn pump_fun_accounts(pool: &PoolKeys) -> (Pubkey, Pubkey) {
+    match pool {
+        PoolKeys::PumpFun(pump_fun) => (pump_fun.bonding_curve, pump_fun.user_sol_account),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn pump_fun_two_hop_settles_sol_through_base_account() {
+    for curve_buys in [true, false] {
+        let mut env = TestEnv::new();
+        let header = env.header(WSOL_BALANCE, 1);
+        let token = header.tokens[0].mint;
+        let (buy_reserve, sell_reserve) = (RESERVE, 2 * RESERVE);
+        let (route, curve) = if curve_buys {
+            let curve = env.pool(PUMP_FUN, WSOL_MINT, token, buy_reserve, RESERVE);
+            let sell = env.pool(CPMM, WSOL_MINT, token, sell_reserve, RESERVE);
+            (Route::two_hop(curve.clone(), sell), curve)
+        } else {
+            let buy = env.pool(CPMM, WSOL_MINT, token, buy_reserve, RESERVE);
+            let curve = env.pool(PUMP_FUN, WSOL_MINT, token, sell_reserve, RESERVE);
+            (Route::two_hop(buy, curve.clone()), curve)
+        };
+        let (bonding_curve, user_sol_account) = pump_fun_accounts(&curve);
+        let route = route.base_pool_0(true, true).unwrap().amount_in(AMOUNT_IN).simulate(true);
+
+        let result = env.run(&route, &header);
+        let label = format!("curve buys {curve_buys}");
+        assert!(result.program_result.is_ok(), "{label}: {:?}", result.program_result);
+        let profit = balance(&result, &header.base_account) - WSOL_BALANCE;
+        assert!(profit > 0, "{label}");
+        let simulation = SimulationResult::decode(&result.return_data).expect("simulation data");
+        assert_eq!(simulation.pnl, profit as i64, "{label}");
+
+        if curve_buys {
+            // buy_exact_sol_in 花掉全部 AMOUNT_IN，临时账户被关闭，租金退回 payer；
+            // payer 另外支付了 user_volume_accumulator 的租金
+            assert_eq!(lamports(&result, &bonding_curve), buy_reserve + AMOUNT_IN, "{label}");
+            assert_eq!(lamports(&result, &user_sol_account), 0, "{label}");
+            let rent = initial_lamports(&env, &user_sol_account);
+            assert_eq!(
+                lamports(&result, &env.payer),
+                initial_lamports(&env, &env.payer) + rent - PUMP_FUN_USER_VOLUME_ACCUMULATOR_RENT,
+                "{label}"
+            );
+        } else {
+            // 卖出所得全部包装进基础资产账户
+            assert_eq!(lamports(&result, &env.payer), initial_lamports(&env, &env.payer), "{label}");
+        }
+    }
+}
+
+#[test]
+fn pump_fun_buy_wraps_unspent_sol_back() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let curve = env.pool(PUMP_FUN, WSOL_MINT, token, RESERVE, RESERVE);
+    let sell = env.pool(CPMM, WSOL_MINT, token, 2 * RESERVE, RESERVE);
+    let (bonding_curve, user_sol_account) = pump_fun_accounts(&curve);
+    let PoolKeys::PumpFun(keys) = &curve else { unreachable!() };
+    let user_volume_accumulator = keys.user_volume_accumulator;
+
+    let route = Route::two_hop(curve, sell)
+        .base_pool_0(true, true).unwrap()
+        .amount_in(AMOUNT_IN)
+        .pump_base_amount_out(PUMP_BASE_AMOUNT_OUT);
+    let result = env.run(&route, &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    let spent = lamports(&result, &bonding_curve) - RESERVE;
+    assert!(spent > PUMP_BASE_AMOUNT_OUT && spent < AMOUNT_IN, "spent {spent}");
+    assert!(balance(&result, &header.base_account) > WSOL_BALANCE);
+    // 首次买入由 payer 支付 user_volume_accumulator 的租金，这部分不算花费：
+    // 未花完的 SOL 全部回到基础资产账户，payer 只多出临时账户的租金、少了 accumulator 的租金
+    assert_eq!(lamports(&result, &user_volume_accumulator), PUMP_FUN_USER_VOLUME_ACCUMULATOR_RENT);
+    let rent = initial_lamports(&env, &user_sol_account);
+    assert_eq!(
+        lamports(&result, &env.payer),
+        initial_lamports(&env, &env.payer) + rent - PUMP_FUN_USER_VOLUME_ACCUMULATOR_RENT
+    );
+}
+
+#[test]
+fn pump_fun_three_hop_buys_and_sells_on_curves() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 2);
+    let (token1, token2) = (header.tokens[0].mint, header.tokens[1].mint);
+    let buy = env.pool(PUMP_FUN, WSOL_MINT, token1, RESERVE, RESERVE);
+    let mid = env.pool(CPMM, token1, token2, RESERVE, RESERVE);
+    let sell = env.pool(PUMP_FUN, WSOL_MINT, token2, 2 * RESERVE, RESERVE);
+
+    let route = Route::three_hop(buy, mid.clone(), sell.clone())
+        .mid_zero_to_one(true).unwrap()
+        .amount_in(AMOUNT_IN);
+    let result = env.run(&route, &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    assert!(balance(&result, &header.base_account) > WSOL_BALANCE);
+    assert_eq!(balance(&result, &header.tokens[1].account), 0);
+
+    // 联合曲线的报价资产固定为 SOL，不能作为中间跳
+    let curve = env.pool(PUMP_FUN, WSOL_MINT, token1, RESERVE, RESERVE);
+    let route = Route::three_hop(curve.clone(), curve, sell).amount_in(AMOUNT_IN);
+    let result = env.run(&route, &header);
+    assert_custom_error(&result, PinocchioCpiError::PumpNotSupported as u32);
+}
+
+#[test]
+fn pump_fun_requires_wsol_base_and_one_buy_per_route() {
+    // 联合曲线以原生 SOL 收付，基础资产不是 WSOL 时在执行前拒绝
+    let mut env = TestEnv::new();
+    let base_mint = env.mint();
+    let header = env.header_with_base(base_mint, WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let curve = env.pool(PUMP_FUN, base_mint, token, RESERVE, RESERVE);
+    let sell = env.pool(CPMM, base_mint, token, 2 * RESERVE, RESERVE);
+    let route = Route::two_hop(curve, sell).base_pool_0(true, true).unwrap().amount_in(AMOUNT_IN);
+    let result = env.run(&route, &header);
+    assert_custom_error(&result, PinocchioCpiError::PumpFunRequiresWsolBase as u32);
+
+    // 每个买入都会关闭自己的临时 WSOL 账户，同一路由中的第二个 Pump.fun 买入在执行前拒绝
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let slot = token_account_index(0);
+    let first = env.pool(PUMP_FUN, WSOL_MINT, token, RESERVE, RESERVE);
+    let second = env.pool(PUMP_FUN, WSOL_MINT, token, RESERVE, RESERVE);
+    let sell = env.pool(CPMM, token, WSOL_MINT, RESERVE, 2 * RESERVE);
+    let route = Route::n_hop(vec![
+        Hop::new(first, BASE_ACCOUNT_INDEX, slot, true).share_bps(5_000),
+        Hop::new(second, BASE_ACCOUNT_INDEX, slot, true),
+        Hop::new(sell, slot, BASE_ACCOUNT_INDEX, true),
+    ])
+    .amount_in(AMOUNT_IN);
+    let result = env.run(&route, &header);
+    assert_custom_error(&result, PinocchioCpiError::MultiplePumpFunBuys as u32);
+}
+
+#[test]
+fn n_hop_through_every_dex_in_both_directions() {
+    for pool_type in POOL_TYPES {
+        for zero_to_one in [true, false] {
+            let mut env = TestEnv::new();
+            let header = env.header(WSOL_BALANCE, 2);
+            let (token1, token2) = (header.tokens[0].mint, header.tokens[1].mint);
+            let (slot1, slot2) = (token_account_index(0), token_account_index(1));
+
+            let first = env.pool(CPMM, WSOL_MINT, token1, RESERVE, RESERVE);
+            let mid = pair(&mut env, pool_type, token1, token2, RESERVE, RESERVE, zero_to_one);
+            let last = env.pool(CPMM, token2, WSOL_MINT, RESERVE, 2 * RESERVE);
+
+            let route = Route::n_hop(vec![
+                Hop::new(first, BASE_ACCOUNT_INDEX, slot1, true),
+                Hop::new(mid, slot1, slot2, zero_to_one),
+                Hop::new(last, slot2, BASE_ACCOUNT_INDEX, true),
+            ])
+            .amount_in(AMOUNT_IN);
+
+            let result = env.run(&route, &header);
+            assert!(
+                result.program_result.is_ok(),
+                "pool type {pool_type} zero_to_one {zero_to_one}: {:?}",
+                result.program_result
+            );
+            assert!(balance(&result, &header.base_account) > WSOL_BALANCE);
+            assert_eq!(balance(&result, &header.tokens[1].account), 0);
+        }
+    }
+}
+
+#[test]
+fn simulation_reports_every_hop() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 2);
+    let (token1, token2) = (header.tokens[0].mint, header.tokens[1].mint);
+    let buy = env.pool(CPMM, WSOL_MINT, token1, RESERVE, RESERVE);
+    let mid = env.pool(DLMM, token1, token2, RESERVE, RESERVE);
+    let sell = env.pool(CLMM, token2, WSOL_MINT, RESERVE, 2 * RESERVE);
+
+    let route = Route::three_hop(buy, mid, sell)
+        .base_pool_0(true, false).unwrap()
+        .mid_zero_to_one(true).unwrap()
+        .amount_in(AMOUNT_IN)
+        .simulate(true);
+    let result = env.run(&route, &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+
+    let simulation = SimulationResult::decode(&result.return_data).expect("simulation data");
+    assert_eq!(simulation.hops.len(), 3);
+    assert_eq!(simulation.hops[0].amount_in, AMOUNT_IN);
+    // 每跳的输入等于上一跳的输出
+    for pair in simulation.hops.windows(2) {
+        assert!(pair[0].amount_out > 0);
+        assert_eq!(pair[1].amount_in, pair[0].amount_out);
+    }
+    assert_eq!(simulation.hops[2].amount_out, balance(&result, &header.base_account) - WSOL_BALANCE + AMOUNT_IN);
+    assert_eq!(simulation.pnl, (simulation.hops[2].amount_out - AMOUNT_IN) as i64);
+}
+
+/// 中间 token 账户预置的库存
+const INVENTORY: u64 = 5_000_000;
+
+#[test]
+fn intermediate_inventory_is_not_sold() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, CPMM, DLMM, true);
+    env.set_balance(header.tokens[0].account, INVENTORY);
+
+    let result = env.run(&route, &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    assert_eq!(balance(&result, &header.tokens[0].account), INVENTORY);
+}
+
+#[test]
+fn multi_hop_inventory_is_not_sold() {
+    for n_hop in [false, true] {
+        let mut env = TestEnv::new();
+        let header = env.header(WSOL_BALANCE, 2);
+        let (token1, token2) = (header.tokens[0].mint, header.tokens[1].mint);
+        for token in &header.tokens {
+            env.set_balance(token.account, INVENTORY);
+        }
+
+        let route = if n_hop {
+            let buy = env.pool(RAYDIUM, WSOL_MINT, token1, RESERVE, RESERVE);
+            let mid = env.pool(CPMM, token1, token2, RESERVE, RESERVE);
+            let sell = env.pool(CLMM, token2, WSOL_MINT, RESERVE, 2 * RESERVE);
+            Route::n_hop(vec![
+                Hop::new(buy, BASE_ACCOUNT_INDEX, token_account_index(0), true),
+                Hop::new(mid, token_account_index(0), token_account_index(1), true),
+                Hop::new(sell, token_account_index(1), BASE_ACCOUNT_INDEX, true),
+            ])
+        } else {
+            let buy = env.pool(DLMM, WSOL_MINT, token1, RESERVE, RESERVE);
+            let mid = env.pool(DAMMV2, token1, token2, RESERVE, RESERVE);
+            let sell = env.pool(WHIRLPOOL, WSOL_MINT, token2, 2 * RESERVE, RESERVE);
+            Route::three_hop(buy, mid, sell).base_pool_0(true, true).unwrap().mid_zero_to_one(true).unwrap()
+        }
+        .amount_in(AMOUNT_IN);
+
+        let result = env.run(&route, &header);
+        assert!(result.program_result.is_ok(), "n_hop {n_hop}: {:?}", result.program_result);
+        for token in &header.tokens {
+            assert_eq!(balance(&result, &token.account), INVENTORY, "n_hop {n_hop}");
+        }
+    }
+}
+
+#[test]
+fn include_existing_balance_sells_inventory() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, CPMM, DLMM, true);
+    env.set_balance(header.tokens[0].account, INVENTORY);
+
+    let result = env.run(&route.include_existing_balance(true), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    assert_eq!(balance(&result, &header.tokens[0].account), 0);
+}
+
+/// 反向2hop：token -> WSOL 价格 1:2，WSOL -> token 价格 1:1
+fn profitable_reverse_two_hop(env: &mut TestEnv, sell: u8, buy: u8, token_amount: u64) -> (Route, RouteHeader) {
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    env.set_balance(header.tokens[0].account, token_amount);
+    let sell_pool = env.pool(sell, WSOL_MINT, token, 2 * RESERVE, RESERVE);
+    let buy_pool = env.pool(buy, WSOL_MINT, token, RESERVE, RESERVE);
+
+    let route = Route::two_hop_reverse(sell_pool, buy_pool)
+        .base_pool_0(true, true).unwrap()
+        .amount_in(AMOUNT_IN);
+    (route, header)
+}
+
+#[test]
+fn reverse_two_hop_profits_in_token() {
+    for (sell, buy) in [(CPMM, DLMM), (CLMM, WHIRLPOOL), (DAMMV2, RAYDIUM)] {
+        let mut env = TestEnv::new();
+        let (route, header) = profitable_reverse_two_hop(&mut env, sell, buy, INVENTORY);
+
+        let result = env.run(&route, &header);
+        assert!(result.program_result.is_ok(), "{sell}/{buy}: {:?}", result.program_result);
+        assert!(balance(&result, &header.tokens[0].account) > INVENTORY);
+        // 买回只花费卖出收到的 WSOL
+        assert_eq!(balance(&result, &header.base_account), WSOL_BALANCE);
+    }
+}
+
+#[test]
+fn reverse_two_hop_checks_profit_in_token() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_reverse_two_hop(&mut env, CPMM, CPMM, INVENTORY);
+
+    // 利润以 token 计，略低于 AMOUNT_IN
+    let result = env.run(&route.min_profit(AMOUNT_IN), &header);
+    assert_custom_error(&result, PinocchioCpiError::ArbitrageFailed as u32);
+}
+
+#[test]
+fn reverse_two_hop_rejects_tip() {
+    let mut env = TestEnv::new();
+    let tip_account = env.system_account(TIP_ACCOUNT_LAMPORTS);
+    let (route, header) = profitable_reverse_two_hop(&mut env, CPMM, DLMM, INVENTORY);
+
+    // token 利润不能当作 lamports 支付
+    let result = env.run(&route.tip(tip_account, 1_000, 0), &header);
+    assert_custom_error(&result, PinocchioCpiError::TipNotSupportedOnReverse as u32);
+}
+
+#[test]
+fn reverse_flash_loan_borrows_the_token() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_reverse_two_hop(&mut env, CPMM, DLMM, 0);
+    let flash_loan = env.flash_loan(header.tokens[0].mint, RESERVE);
+
+    let result = env.run(&route.flash_loan(flash_loan.clone()), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    assert!(balance(&result, &header.tokens[0].account) > 0);
+    assert_eq!(balance(&result, &flash_loan.reserve_liquidity_supply), RESERVE);
+    assert_eq!(balance(&result, &flash_loan.reserve_liquidity_fee_receiver), flash_loan_fee(AMOUNT_IN));
+}
+
+#[test]
+fn split_leg_spreads_input_across_pools() {
+    for by_amount in [false, true] {
+        let mut env = TestEnv::new();
+        let header = env.header(WSOL_BALANCE, 1);
+        let token = header.tokens[0].mint;
+        let slot = token_account_index(0);
+        let clmm = env.pool(CLMM, WSOL_MINT, token, RESERVE, RESERVE);
+        let dlmm = env.pool(DLMM, WSOL_MINT, token, RESERVE, RESERVE);
+        let sell = env.pool(CPMM, token, WSOL_MINT, RESERVE, 2 * RESERVE);
+        let (clmm_vault, dlmm_vault) = match (&clmm, &dlmm) {
+            (PoolKeys::Clmm(clmm), PoolKeys::Dlmm(dlmm)) => (clmm.token_vault_0, dlmm.reserve_x),
+            _ => unreachable!(),
+        };
+
+        let first = Hop::new(clmm, BASE_ACCOUNT_INDEX, slot, true);
+        let first = if by_amount { first.share_amount(AMOUNT_IN / 4) } else { first.share_bps(2_500) };
+        let route = Route::n_hop(vec![
+            first,
+            Hop::new(dlmm, BASE_ACCOUNT_INDEX, slot, true),
+            Hop::new(sell, slot, BASE_ACCOUNT_INDEX, true),
+        ])
+        .amount_in(AMOUNT_IN);
+
+        let result = env.run(&route, &header);
+        assert!(result.program_result.is_ok(), "by_amount {by_amount}: {:?}", result.program_result);
+        // 腿内最后一个子swap使用剩余部分
+        assert_eq!(balance(&result, &clmm_vault), RESERVE + AMOUNT_IN / 4);
+        assert_eq!(balance(&result, &dlmm_vault), RESERVE + AMOUNT_IN * 3 / 4);
+        // 卖出腿使用两个子swap输出之和
+        assert_eq!(balance(&result, &header.tokens[0].account), 0);
+    }
+}
+
+#[test]
+fn split_share_is_capped_at_leg_input() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let slot = token_account_index(0);
+    let cpmm = env.pool(CPMM, WSOL_MINT, token, RESERVE, RESERVE);
+    let whirlpool = env.pool(WHIRLPOOL, WSOL_MINT, token, RESERVE, RESERVE);
+    let sell = env.pool(DAMMV2, token, WSOL_MINT, RESERVE, 2 * RESERVE);
+    let (cpmm_vault, whirlpool_vault) = match (&cpmm, &whirlpool) {
+        (PoolKeys::Cpmm(cpmm), PoolKeys::Whirlpool(whirlpool)) => (cpmm.token_0_vault, whirlpool.token_vault_a),
+        _ => unreachable!(),
+    };
+
+    let route = Route::n_hop(vec![
+        Hop::new(cpmm, BASE_ACCOUNT_INDEX, slot, true).share_amount(u64::MAX),
+        Hop::new(whirlpool, BASE_ACCOUNT_INDEX, slot, true),
+        Hop::new(sell, slot, BASE_ACCOUNT_INDEX, true),
+    ])
+    .amount_in(AMOUNT_IN);
+
+    let result = env.run(&route, &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+    // 第一个子swap用完了全部输入，余下的子swap输入为0
+    assert_eq!(balance(&result, &cpmm_vault), RESERVE + AMOUNT_IN);
+    assert_eq!(balance(&result, &whirlpool_vault), RESERVE);
+}
+
+#[test]
+fn n_hop_min_out_is_per_hop() {
+    let mut env = TestEnv::new();
+    let header = env.header(WSOL_BALANCE, 1);
+    let token = header.tokens[0].mint;
+    let slot = token_account_index(0);
+    let buy = env.pool(DAMMV2, WSOL_MINT, token, RESERVE, RESERVE);
+    let sell = env.pool(DLMM, token, WSOL_MINT, RESERVE, 2 * RESERVE);
+
+    let route = Route::n_hop(vec![
+        Hop::new(buy, BASE_ACCOUNT_INDEX, slot, true),
+        Hop::new(sell, slot, BASE_ACCOUNT_INDEX, true).min_out(2 * AMOUNT_IN),
+    ])
+    .amount_in(AMOUNT_IN);
+
+    let result = env.run(&route, &header);
+    assert_custom_error(&result, ERROR_SLIPPAGE);
+}
+
+#[test]
+fn validate_accounts_accepts_well_formed_header() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, RAYDIUM, CLMM, false);
+
+    let result = env.run(&route.validate_accounts(true), &header);
+    assert!(result.program_result.is_ok(), "{:?}", result.program_result);
+}
+
+#[test]
+fn validate_accounts_rejects_token_account_with_wrong_owner() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, CPMM, CPMM, false);
+    env.account_mut(&header.tokens[0].account).owner = Pubkey::new_unique();
+
+    let result = env.run(&route.validate_accounts(true), &header);
+    assert_custom_error(&result, PinocchioCpiError::AccountOwnerMismatch as u32);
+}
+
+#[test]
+fn validate_accounts_rejects_base_program_that_does_not_own_the_mint() {
+    // [3] 是 Token-2022，但基础资产 mint 和账户由 SPL Token 拥有
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, CPMM, CPMM, false);
+    let header = header.base_token_program(TOKEN_2022_PROGRAM_ID);
+    let result = env.run(&route.validate_accounts(true), &header);
+    assert_custom_error(&result, PinocchioCpiError::AccountOwnerMismatch as u32);
+
+    // mint 不归 [3] 所有
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, CPMM, CPMM, false);
+    env.account_mut(&header.base_mint).owner = Pubkey::new_unique();
+    let result = env.run(&route.validate_accounts(true), &header);
+    assert_custom_error(&result, PinocchioCpiError::AccountOwnerMismatch as u32);
+}
+
+#[test]
+fn validate_accounts_rejects_mint_mismatch() {
+    // 基础资产槽位 [1] / [2]
+    let mut env = TestEnv::new();
+    let (route, mut header) = profitable_two_hop(&mut env, CPMM, CPMM, false);
+    header.base_mint = env.mint();
+    let result = env.run(&route.validate_accounts(true), &header);
+    assert_custom_error(&result, PinocchioCpiError::InvalidTokenAccountData as u32);
+
+    // 中间 token 三元组 [6] / [8]
+    let mut env = TestEnv::new();
+    let (route, mut header) = profitable_two_hop(&mut env, CPMM, CPMM, false);
+    header.tokens[0].mint = env.mint();
+    let result = env.run(&route.validate_accounts(true), &header);
+    assert_custom_error(&result, PinocchioCpiError::InvalidTokenAccountData as u32);
+}
+
+#[test]
+fn validate_accounts_requires_payer_signature() {
+    let mut env = TestEnv::new();
+    let (route, header) = profitable_two_hop(&mut env, CPMM, CPMM, false);
+    let mut instruction = route.validate_accounts(true).instruction(PROGRAM_ID, &header).unwrap();
+    instruction.accounts[0].is_signer = false;
+
+    let result = env.run_chain(&[instruction]);
+    assert_eq!(result.raw_result, Err(InstructionError::MissingRequiredSignature));
+}
+
+#[test]
+fn validate_accounts_rejects_wrong_token_programs() {
+    for index in [3, 4] {
+        let mut env = TestEnv::new();
+        let (route, header) = profitable_two_hop(&mut env, CPMM, CPMM, false);
+        let mut instruction = route.validate_accounts(true).instruction(PROGRAM_ID, &header).unwrap();
+        instruction.accounts[index].pubkey = env.dummy();
+
+        let result = env.run_chain(&[instruction]);
+        assert_eq!(result.raw_result, Err(InstructionError::IncorrectProgramId), "header account {index}");
+    }
+}
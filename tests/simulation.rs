@@ -0,0 +1,37 @@
+//! 模拟模式的入口检查：在读取任何账户之前完成，在宿主机上直接调用 `process_instruction`：
+//!
+//! ```text
+//! cargo test --test simulation
+//! ```
+
+use my_pinocchio_cpi::{
+    error::PinocchioCpiError,
+    process_instruction,
+    simulation::SIMULATION_MAX_HOPS,
+    utils::{FLAG_SIMULATE, HEADER_ACCOUNT_COUNT, ROUTE_HEADER_LEN, ROUTE_HOP_LEN},
+};
+
+const OPCODE_ROUTE: u8 = 6;
+
+/// `hop_count` 跳的N-hop路由，所有 hop 为 CPMM 从基础资产槽位到基础资产槽位
+fn route_data(hop_count: usize, flags: u8) -> Vec<u8> {
+    let mut data = vec![0; 1 + ROUTE_HEADER_LEN + hop_count * ROUTE_HOP_LEN];
+    data[0] = OPCODE_ROUTE;
+    data[1] = hop_count as u8;
+    data[2] = HEADER_ACCOUNT_COUNT as u8;
+    data[3] = flags;
+    for hop in data[1 + ROUTE_HEADER_LEN..].chunks_exact_mut(ROUTE_HOP_LEN) {
+        hop[2] = 2;
+        hop[3] = 2;
+    }
+    data
+}
+
+#[test]
+fn simulating_more_hops_than_the_return_data_holds_is_rejected() {
+    let data = route_data(SIMULATION_MAX_HOPS + 1, FLAG_SIMULATE);
+    assert_eq!(
+        process_instruction(&[0; 32], &[], &data),
+        Err(PinocchioCpiError::SimulationTooManyHops.into())
+    );
+}